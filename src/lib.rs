@@ -1,3 +1,7 @@
 pub mod oracle;
 
-pub use oracle::{AccumulatedObservation, ObservationInterval, Oracle};
+pub use oracle::{
+    AccumulatedObservation, CompressedObservation, CompressedOracleSnapshot, ObservationInterval,
+    ObservationIntervalDetailed, Oracle, OracleError, OracleMetadata, OracleSnapshot,
+    SpotPriceEvent, UnixMinutes, UnixSeconds,
+};
@@ -1,7 +1,23 @@
 use common::time::*;
 use scrypto::prelude::*;
 use scrypto_math::*;
-use std::cmp::min;
+use std::cmp::{max, min};
+use std::mem;
+
+/// The number of observations stored by `Oracle::with_default_limit`, covering one day of
+/// minute observations.
+pub const DEFAULT_OBSERVATIONS_LIMIT: u16 = 1440;
+
+/// The number of intervals memoized by `Oracle::observation_intervals_cached`.
+pub const INTERVAL_CACHE_SIZE: usize = 8;
+
+/// The end of an interval meaning "up to the current minute" of the oracle, resolved with the
+/// ledger clock at query time. This avoids mismatches between the caller's and the oracle's clock.
+pub const INTERVAL_END_NOW: u64 = 0;
+
+/// The maximum number of minutes spanned by an interval of `Oracle::log_returns`, which looks up
+/// the accumulator of every minute, keeping these lookups within the cost limit of a transaction.
+pub const LOG_RETURNS_MINUTES_MAX: u64 = 240;
 
 #[derive(ScryptoSbor)]
 pub struct Oracle {
@@ -16,17 +32,84 @@ pub struct Oracle {
     /// minute.
     sub_observations: Option<SubObservations>,
     observations_limit: u16,
+    /// The maximum number of seconds an observation can be extrapolated past the last stored
+    /// one. None if extrapolation is unlimited.
+    max_extrapolation_seconds: Option<u64>,
+    /// The maximum number of minutes between the two stored observations an observation is
+    /// interpolated between. None if interpolation is unlimited.
+    max_interpolation_gap_minutes: Option<u64>,
+    /// The maximum number of minutes an interval may span. None if intervals are unlimited.
+    max_interval_minutes: Option<u64>,
+    /// The maximum factor by which an observed `price_sqrt` may deviate from the average of the
+    /// last finalized minute. None if prices are not bounded.
+    price_sqrt_band: Option<PreciseDecimal>,
+    /// The minimum `price_sqrt` recorded, below which observed prices are clamped. None if prices
+    /// are not floored.
+    price_sqrt_floor: Option<PreciseDecimal>,
+    /// The minimum number of minutes between two stored observations. None if every active
+    /// minute is stored.
+    min_spacing_minutes: Option<u64>,
+    /// The minimum number of minutes a full ring has to keep covering when storing a new
+    /// observation. None if observations are stored regardless of the retained history.
+    min_retained_minutes: Option<u64>,
+    /// The maximum number of minutes between two stored observations before the gap is filled
+    /// with synthetic observations carrying the flat price. None if gaps are not filled.
+    max_observation_gap_minutes: Option<u64>,
+    /// Whether `observe` emits a `SpotPriceEvent` on every call.
+    emit_spot_events: bool,
+    /// The addresses of the tokens x and y of the observed pool, which the `price_sqrt` (y per x)
+    /// refers to. None until set by the embedding hook.
+    token_pair: Option<(ResourceAddress, ResourceAddress)>,
+    /// The total value subtracted from every stored `price_sqrt_log_acc` by `rebase`.
+    price_sqrt_log_acc_offset: Decimal,
+    /// The last rolling average computed by `twap_last_cached`. Cleared whenever a new
+    /// observation is inserted.
+    twap_cache: Option<TwapCache>,
+    /// The intervals last computed by `observation_intervals_cached`, least recently used first.
+    /// Cleared whenever a new observation is inserted.
+    interval_cache: Vec<IntervalCacheEntry>,
 }
 
 impl Oracle {
+    /// Creates a new oracle storing up to `observations_limit` observations.
+    ///
+    /// # Panics
+    /// Panics if `observations_limit` is zero, see `try_new`.
     pub fn new(observations_limit: u16) -> Self {
-        Oracle {
+        Self::try_new(observations_limit).expect("The observations limit must be at least 1.")
+    }
+
+    /// Creates a new oracle storing up to `DEFAULT_OBSERVATIONS_LIMIT` observations, i.e. one day.
+    pub fn with_default_limit() -> Self {
+        Self::new(DEFAULT_OBSERVATIONS_LIMIT)
+    }
+
+    /// Same as `new`, but returns `OracleError::CapacityTooSmall` instead of panicking if
+    /// `observations_limit` is zero.
+    pub fn try_new(observations_limit: u16) -> Result<Self, OracleError> {
+        if observations_limit == 0 {
+            return Err(OracleError::CapacityTooSmall);
+        }
+        Ok(Oracle {
             observations: KeyValueStore::new(),
             observations_stored: 0,
             last_observation_index: None,
             sub_observations: None,
             observations_limit,
-        }
+            max_extrapolation_seconds: None,
+            max_interpolation_gap_minutes: None,
+            max_interval_minutes: None,
+            price_sqrt_band: None,
+            price_sqrt_floor: None,
+            min_spacing_minutes: None,
+            min_retained_minutes: None,
+            max_observation_gap_minutes: None,
+            emit_spot_events: false,
+            token_pair: None,
+            price_sqrt_log_acc_offset: Decimal::ZERO,
+            twap_cache: None,
+            interval_cache: Vec::new(),
+        })
     }
 
     /// Receives and updates the SubObservations object accordingly.
@@ -43,94 +126,407 @@ impl Oracle {
     ///   a) It generates a new observation and stores it in the `observations` field.
     ///   b) Similarly to (2), a new sub-observation is inserted in the SubObservations object.
     ///
+    /// The ledger clock is assumed to be monotonically non-decreasing across calls. Should an
+    /// instant earlier than the last update ever be delivered, it is treated as the last update
+    /// instant, i.e. the price replaces the last one without any time being accounted for.
+    ///
     /// # Arguments
     ///
     /// * `price_sqrt` - A `PreciseDecimal` representing the square root of the price at the end
     /// of the swap.
-    pub fn observe(&mut self, price_sqrt: PreciseDecimal) {
-        let current_instant = Clock::instant();
+    /// * `active_liquidity` - A `PreciseDecimal` representing the active liquidity of the pool at
+    /// the end of the swap.
+    /// * `volume` - A `Decimal` representing the volume traded by the swap. The oracle does not
+    /// impose a denomination, but it has to be consistent across all calls (e.g. always in token x).
+    /// * `input_fee_rate` - A `Decimal` representing the input fee rate of the pool during the
+    /// swap.
+    pub fn observe(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        input_fee_rate: Decimal,
+    ) {
+        let now = Clock::instant();
+        self.observe_internal(
+            price_sqrt,
+            active_liquidity,
+            volume,
+            input_fee_rate,
+            true,
+            now,
+        );
+
+        if self.emit_spot_events {
+            Runtime::emit_event(SpotPriceEvent {
+                timestamp_seconds: now.seconds_since_unix_epoch as u64,
+                price_sqrt: self.sub_observations.as_ref().unwrap().price_sqrt_last,
+            });
+        }
+    }
+
+    /// Informs the oracle about the pool price at the start of a swap.
+    ///
+    /// The price before a swap is the price left by the previous swap, so it does not alter the
+    /// time-weighted average of the current minute. It does however finalize the last active
+    /// minute and store its observation if a new minute has started, exactly as `observe` would.
+    /// This way, the observation is stored before the swap changes the price. Neither volume nor
+    /// a swap is recorded, since the swap has not been performed yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `price_sqrt` - A `PreciseDecimal` representing the square root of the price at the start
+    /// of the swap.
+    /// * `active_liquidity` - A `PreciseDecimal` representing the active liquidity of the pool at
+    /// the start of the swap.
+    /// * `input_fee_rate` - A `Decimal` representing the input fee rate of the pool at the start
+    /// of the swap.
+    pub fn observe_before_swap(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        input_fee_rate: Decimal,
+    ) {
+        self.observe_internal(
+            price_sqrt,
+            active_liquidity,
+            dec!(0),
+            input_fee_rate,
+            false,
+            Clock::instant(),
+        );
+    }
+
+    /// Records the current price of the pool without a swap, e.g. pushed by an external keeper.
+    ///
+    /// Without swaps, the oracle assumes the price of the last swap held flat, which is wrong if
+    /// the pool price moved in the meantime (e.g. by a liquidity change). A refresh closes the
+    /// stale minutes with the last known price and continues with `current_price_sqrt` through
+    /// the same sub-minute accumulation as `observe`. The active liquidity and the input fee rate
+    /// of the last observation are kept, no volume is added and no swap is counted.
+    ///
+    /// # Arguments
+    ///
+    /// * `current_price_sqrt` - The square root of the current price of the pool.
+    ///
+    /// # Panics
+    /// Panics if the oracle has not observed a swap yet.
+    pub fn refresh_from_pool(&mut self, current_price_sqrt: PreciseDecimal) {
+        let sub_observations = self
+            .sub_observations
+            .as_ref()
+            .expect("The oracle has not observed a swap yet.");
+        let active_liquidity = sub_observations.liquidity_last;
+        let input_fee_rate = sub_observations.fee_rate_last;
+        self.observe_internal(
+            current_price_sqrt,
+            active_liquidity,
+            dec!(0),
+            input_fee_rate,
+            false,
+            Clock::instant(),
+        );
+    }
+
+    /// Performs the same accumulation as `observe`, but against a supplied instant instead of the
+    /// current ledger time.
+    ///
+    /// This allows to deterministically seed an oracle with historical prices, e.g. during a
+    /// migration. It is meant to be exposed only to privileged roles by the hook, since it
+    /// bypasses the ledger clock. Historical fee rates are rarely at hand, so the input fee rate
+    /// of the last observation is kept (zero for a new oracle).
+    ///
+    /// # Arguments
+    ///
+    /// * `price_sqrt` - A `PreciseDecimal` representing the square root of the price.
+    /// * `active_liquidity` - A `PreciseDecimal` representing the active liquidity of the pool.
+    /// * `volume` - A `Decimal` representing the volume traded, see `observe`.
+    /// * `instant` - The `Instant` at which the price was observed.
+    ///
+    /// # Panics
+    /// Panics if `instant` is not strictly after the last update of the oracle, or if it is later
+    /// than the current ledger time, which would leave the live `observe` calls of the earlier
+    /// minutes ignored.
+    pub fn observe_at(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        instant: Instant,
+    ) {
+        if let Some(sub_observations) = &self.sub_observations {
+            assert!(
+                instant > sub_observations.last_updated,
+                "Instant {} is not after the last update of the oracle at {}.",
+                instant.seconds_since_unix_epoch,
+                sub_observations.last_updated.seconds_since_unix_epoch
+            );
+        }
+        let now = Clock::instant();
+        assert!(
+            instant <= now,
+            "Instant {} is later than the current ledger time {}.",
+            instant.seconds_since_unix_epoch,
+            now.seconds_since_unix_epoch
+        );
+        let input_fee_rate = self
+            .sub_observations
+            .as_ref()
+            .map_or(dec!(0), |sub_observations| sub_observations.fee_rate_last);
+        self.observe_internal(
+            price_sqrt,
+            active_liquidity,
+            volume,
+            input_fee_rate,
+            true,
+            instant,
+        );
+    }
+
+    /// Applies a batch of historical observations in order, see `observe_at`.
+    ///
+    /// This allows to catch up on prices missed while swaps were not routed through the hook,
+    /// e.g. after a misconfiguration or an upgrade, in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - A vector of `(instant, price_sqrt, active_liquidity, volume)` tuples with
+    /// strictly increasing instants.
+    ///
+    /// # Panics
+    /// Panics if the instants are not strictly increasing, if the first one is not after the last
+    /// update of the oracle, or if the last one is later than the current ledger time.
+    pub fn observe_many(
+        &mut self,
+        entries: Vec<(Instant, PreciseDecimal, PreciseDecimal, Decimal)>,
+    ) {
+        for (instant, price_sqrt, active_liquidity, volume) in entries {
+            self.observe_at(price_sqrt, active_liquidity, volume, instant);
+        }
+    }
 
+    /// Shared implementation of `observe` and `observe_at`, see `observe` for details.
+    /// `is_swap` tells whether the call reports a performed swap, which is counted.
+    fn observe_internal(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        input_fee_rate: Decimal,
+        is_swap: bool,
+        current_instant: Instant,
+    ) {
         match &self.sub_observations {
             None => {
-                self.sub_observations = Some(SubObservations::new());
+                self.sub_observations = Some(SubObservations::new_at(current_instant));
             }
             Some(sub_observations) => {
-                if current_instant.minutes() != sub_observations.last_updated.minutes() {
-                    let observation = self.create_observation();
-                    self.insert_observation(observation);
+                // An earlier minute can only be caused by a non-monotonic clock and is ignored
+                if current_instant.minutes() > sub_observations.last_updated.minutes() {
+                    for observation in self.create_observations(current_instant) {
+                        self.insert_observation(observation);
+                    }
                 }
             }
         }
 
-        self.sub_observations
-            .as_mut()
-            .unwrap()
-            .new_subobservation(price_sqrt);
+        let price_sqrt = self.price_sqrt_above_floor(self.price_sqrt_within_band(price_sqrt));
+        let sub_observations = self.sub_observations.as_mut().unwrap();
+        sub_observations.new_subobservation_at(
+            price_sqrt,
+            active_liquidity,
+            volume,
+            input_fee_rate,
+            current_instant,
+        );
+        if is_swap {
+            sub_observations.count_swap();
+        }
     }
 
-    /// Creates a new `AccumulatedObservation` instance.
+    /// Whether the last observation is closer than `min_spacing_minutes` to the observation
+    /// before it, i.e. whether it still has to be replaced by the next one.
+    fn last_observation_within_min_spacing(&self) -> bool {
+        let min_spacing_minutes = match self.min_spacing_minutes {
+            Some(min_spacing_minutes) if self.observations_stored >= 2 => min_spacing_minutes,
+            _ => return false,
+        };
+        let last_index = self.last_observation_index.unwrap();
+        let previous_index = self.index_at_offset(last_index, self.observations_stored - 1);
+        let last_timestamp = self.observations.get(&last_index).unwrap().timestamp;
+        let previous_timestamp = self.observations.get(&previous_index).unwrap().timestamp;
+
+        last_timestamp - previous_timestamp < min_spacing_minutes
+    }
+
+    /// Whether storing an observation for `timestamp` in a new slot would evict history the ring
+    /// has to retain, i.e. whether the ring is full and the observation after the oldest one is
+    /// less than `min_retained_minutes` before `timestamp`.
+    fn exceeds_observation_rate(&self, timestamp: u64) -> bool {
+        let min_retained_minutes = match self.min_retained_minutes {
+            Some(min_retained_minutes)
+                if self.observations_stored >= 2
+                    && self.observations_stored == self.observations_limit =>
+            {
+                min_retained_minutes
+            }
+            _ => return false,
+        };
+        // Storing in a new slot evicts the oldest observation, leaving the next one as the oldest
+        let next_oldest_index = self.index_at_offset(self.oldest_index().unwrap(), 1);
+        let next_oldest_timestamp = self.observations.get(&next_oldest_index).unwrap().timestamp;
+
+        timestamp - next_oldest_timestamp < min_retained_minutes
+    }
+
+    /// Clamps `price_sqrt` to the configured band around the average of the last finalized
+    /// minute, see `set_price_sqrt_band`.
+    fn price_sqrt_within_band(&self, price_sqrt: PreciseDecimal) -> PreciseDecimal {
+        let reference = self
+            .sub_observations
+            .as_ref()
+            .and_then(|sub_observations| sub_observations.last_finalized_price_sqrt());
+        match (self.price_sqrt_band, reference) {
+            (Some(band), Some(reference)) => {
+                let lower = reference / band;
+                let upper = reference * band;
+                if price_sqrt < lower {
+                    lower
+                } else if price_sqrt > upper {
+                    upper
+                } else {
+                    price_sqrt
+                }
+            }
+            _ => price_sqrt,
+        }
+    }
+
+    /// Clamps `price_sqrt` to the configured floor, see `set_price_sqrt_floor`.
+    fn price_sqrt_above_floor(&self, price_sqrt: PreciseDecimal) -> PreciseDecimal {
+        match self.price_sqrt_floor {
+            Some(floor) => max(price_sqrt, floor),
+            None => price_sqrt,
+        }
+    }
+
+    /// Creates the new `AccumulatedObservation` instances to store.
     ///
-    /// This function calculates the accumulated log of the price square root over a period of time
-    /// and creates a new observation with the current timestamp and the calculated value. It
-    /// handles two scenarios:
+    /// This function calculates the accumulated log of the price square root (and the accumulated
+    /// liquidity and volume) over a period of time and creates a new observation with the current timestamp
+    /// and the calculated values. It handles two scenarios:
     /// 1) If no observations have been stored yet, it initializes the first observation with the
-    /// current price square root log accumulation.
-    /// 2) If there are existing observations, it calculates the new accumulated value based on the
-    /// last observation and the time elapsed since then.
+    /// current accumulation, starting from an empty observation.
+    /// 2) If there are existing observations, it calculates the new accumulated values based on
+    /// the last observation and the time elapsed since then.
+    ///
+    /// With `max_observation_gap_minutes` configured, the synthetic observations filling the gap
+    /// since the last active minute are created before it, see `set_max_observation_gap_minutes`.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - The `Instant` at which the observation is created.
     ///
     /// # Returns
     ///
-    /// An `AccumulatedObservation` instance containing the current timestamp and the accumulated
-    /// log of the price square root.
-    fn create_observation(&mut self) -> AccumulatedObservation {
-        let now_minutes = Clock::time_in_minutes();
+    /// A vector of `AccumulatedObservation` instances in chronological order, the last one
+    /// containing the current timestamp and the accumulated values.
+    fn create_observations(&mut self, now: Instant) -> Vec<AccumulatedObservation> {
+        let now_minutes = now.minutes();
 
         let sub_observations = self.sub_observations.as_mut().unwrap();
-        let minutes_since_last = now_minutes - sub_observations.last_updated.minutes();
-        let finalized = sub_observations.finalize();
-
-        // Case 1: If no observations have been stored yet, the first observation is generated and
-        // returned.
-        if self.observations_stored == 0 {
-            let initial_observation = AccumulatedObservation {
-                timestamp: now_minutes,
-                price_sqrt_log_acc: accumulated_log(
-                    dec!(0),
-                    finalized,
-                    sub_observations.price_sqrt_last,
-                    minutes_since_last,
-                ),
-            };
+        let last_active_minutes = sub_observations.last_updated.minutes();
+        let finalized = sub_observations.finalize_at(now);
 
-            return initial_observation;
-        }
+        let last_observation = match self.last_observation_index {
+            // Case 1: If no observations have been stored yet, the first observation is
+            // accumulated from an empty one.
+            None => AccumulatedObservation::empty(),
+            // Case 2: A new observation is accumulated from the last stored observation.
+            Some(last_observation_index) => self
+                .observations
+                .get(&last_observation_index)
+                .unwrap()
+                .clone(),
+        };
 
-        // Case 2: A new observation is generated based on the last stored observation, and returned
-        let last_observation = self
-            .observations
-            .get(&self.last_observation_index.unwrap())
-            .unwrap();
+        self.synthetic_timestamps(last_active_minutes, now_minutes)
+            .into_iter()
+            .chain(std::iter::once(now_minutes))
+            .map(|timestamp| {
+                last_observation.accumulate(timestamp, &finalized, timestamp - last_active_minutes)
+            })
+            .collect()
+    }
 
-        let price_sqrt_log_acc = accumulated_log(
-            last_observation.price_sqrt_log_acc,
-            finalized,
-            sub_observations.price_sqrt_last,
-            minutes_since_last,
-        );
+    /// Returns the minutes of the synthetic observations filling the gap between the last active
+    /// minute and `now_minutes`, every `max_observation_gap_minutes` after the last active minute.
+    /// Only the synthetic observations the ring can still hold together with the observation for
+    /// `now_minutes` are returned, which bounds the work of a single call by the observations
+    /// limit.
+    fn synthetic_timestamps(&self, last_active_minutes: u64, now_minutes: u64) -> Vec<u64> {
+        let max_observation_gap_minutes = match self.max_observation_gap_minutes {
+            Some(max_observation_gap_minutes) => max_observation_gap_minutes,
+            None => return vec![],
+        };
+        let count = (now_minutes - last_active_minutes - 1) / max_observation_gap_minutes;
+        let first = count.saturating_sub(self.observations_limit as u64 - 1) + 1;
 
-        AccumulatedObservation {
-            timestamp: now_minutes,
-            price_sqrt_log_acc,
-        }
+        (first..=count)
+            .map(|k| last_active_minutes + k * max_observation_gap_minutes)
+            .collect()
     }
 
     /// Inserts a given `AccumulatedObservation` into the oracle's observation list.
     ///
+    /// With a minimum spacing configured, the last observation is replaced instead as long as it
+    /// is closer than the spacing to the observation before it, see `set_min_spacing_minutes`.
+    /// The same applies while a new slot would evict history the ring has to retain, see
+    /// `set_min_retained_minutes`.
+    ///
     /// # Arguments
     ///
     /// * `observation`: The `AccumulatedObservation` instance to be inserted into the observation
     /// list.
+    ///
+    /// # Panics
+    /// Panics if the observation is not more recent than the last stored observation, which would
+    /// break the chronological order the binary search relies on.
     fn insert_observation(&mut self, observation: AccumulatedObservation) {
+        self.assert_after_last_observation(observation.timestamp);
+
+        if self.last_observation_within_min_spacing()
+            || self.exceeds_observation_rate(observation.timestamp)
+        {
+            self.observations
+                .insert(self.last_observation_index.unwrap(), observation);
+            self.twap_cache = None;
+            self.interval_cache.clear();
+            return;
+        }
+
+        self.append_observation(observation);
+    }
+
+    /// Asserts that an observation for the given minute would be more recent than the last one.
+    fn assert_after_last_observation(&self, timestamp: u64) {
+        if let Some(last_observation_index) = self.last_observation_index {
+            let last_timestamp = self
+                .observations
+                .get(&last_observation_index)
+                .unwrap()
+                .timestamp;
+            assert!(
+                timestamp > last_timestamp,
+                "An observation for minute {} is already stored.",
+                last_timestamp
+            );
+        }
+    }
+
+    /// Stores an observation in the slot after the last one, overwriting the oldest observation
+    /// if the ring buffer is full.
+    fn append_observation(&mut self, observation: AccumulatedObservation) {
         // Update the `last_observation_index` to point to the newly inserted observation's index,
         // ensuring it wraps around
         // based on the `OBSERVATIONS_LIMIT` to mimic a circular queue and avoid out-of-bounds
@@ -145,6 +541,8 @@ impl Oracle {
         };
         self.observations
             .insert(self.last_observation_index.unwrap(), observation);
+        self.twap_cache = None;
+        self.interval_cache.clear();
 
         // The `observations_stored` count is also incrementing, ensuring it
         // does not exceed the `OBSERVATIONS_LIMIT`.
@@ -156,17 +554,184 @@ impl Oracle {
     ///
     /// # Arguments
     ///
-    /// * `seconds`: The timestamp in seconds for which an observation is sought.
+    /// * `seconds`: The timestamp in seconds for which an observation is sought, either as
+    /// `UnixSeconds` or as a plain `u64`. A `UnixMinutes` is rejected by the compiler.
     ///
     /// # Returns
     ///
     /// An `AccumulatedObservation` corresponding to the given timestamp.
-    pub fn observation(&self, seconds: u64) -> AccumulatedObservation {
-        let mut observation = self.observation_internal(seconds / 60);
+    pub fn observation(&self, seconds: impl Into<UnixSeconds>) -> AccumulatedObservation {
+        let mut observation = self.observation_internal(seconds.into().to_minutes().0);
         observation.timestamp *= 60;
         observation
     }
 
+    /// Retrieves an `AccumulatedObservation` for a given timestamp in seconds like `observation`,
+    /// but with `price_sqrt_log_acc` accumulating the logarithm of the inverse price square root
+    /// (x per y), i.e. negated, see `observation_intervals_inverse`.
+    ///
+    /// `price_sqrt_log_sq_acc` is the same for both directions, since the square of the negated
+    /// logarithm is unchanged. All other accumulators still refer to the price of y per x.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds`: The timestamp in seconds for which an observation is sought.
+    ///
+    /// # Returns
+    ///
+    /// An `AccumulatedObservation` corresponding to the given timestamp.
+    pub fn observation_inverse(&self, seconds: u64) -> AccumulatedObservation {
+        let mut observation = self.observation(seconds);
+        observation.price_sqrt_log_acc = -observation.price_sqrt_log_acc;
+        observation
+    }
+
+    /// Retrieves the `price_sqrt_log_acc` for a given timestamp in seconds like `observation`,
+    /// but derives an interpolated or extrapolated accumulator at full `PreciseDecimal` precision
+    /// instead of truncating it to a `Decimal`.
+    ///
+    /// `observation` truncates the interpolation slope and the logarithms of an extrapolation,
+    /// which is negligible for a single read, but compounds for consumers combining many reads,
+    /// e.g. summing accumulators at every minute of a long gap. Prefer this variant in that case.
+    /// The stored accumulators are `Decimal`s themselves, so a timestamp matching a stored
+    /// observation returns the same value as `observation`.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds`: The timestamp in seconds, automatically rounded to the minute.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the timestamp rounded to the minute in seconds and the accumulator.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation`.
+    pub fn observation_precise(&self, seconds: u64) -> (u64, PreciseDecimal) {
+        let target_minutes = seconds / 60;
+        let observation = self.observation_internal(target_minutes);
+
+        let last_observation = self
+            .observations
+            .get(&self.last_observation_index.unwrap())
+            .unwrap()
+            .clone();
+        let price_sqrt_log_acc = if target_minutes > last_observation.timestamp {
+            // `observation_internal` already made sure the current minute is available
+            let summary = self.sub_observations.as_ref().unwrap().finalize_preview();
+            let minutes_since_last = target_minutes - last_observation.timestamp;
+            PreciseDecimal::from(last_observation.price_sqrt_log_acc)
+                + summary.price_sqrt_avg.ln().unwrap()
+                + summary.price_sqrt_last.ln().unwrap() * (minutes_since_last - 1)
+        } else if target_minutes == last_observation.timestamp {
+            PreciseDecimal::from(observation.price_sqrt_log_acc)
+        } else {
+            let (o_left, o_right) = binary_search_neighbors(
+                &self.observations,
+                self.oldest_index().unwrap(),
+                self.observations_stored,
+                target_minutes,
+            );
+            precise_linear_interpolation(
+                o_left.timestamp,
+                o_right.timestamp,
+                o_left.price_sqrt_log_acc,
+                o_right.price_sqrt_log_acc,
+                target_minutes,
+            )
+        };
+
+        (target_minutes * 60, price_sqrt_log_acc)
+    }
+
+    /// Retrieves an `AccumulatedObservation` for a timestamp relative to the current time, e.g.
+    /// the price one hour ago, without the caller having to read a clock of its own.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds_ago`: The number of seconds before the current time, where zero refers to the
+    /// current time.
+    ///
+    /// # Returns
+    ///
+    /// The same `AccumulatedObservation` as `observation` for `now - seconds_ago`.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation`, e.g. if the timestamp is older than the
+    /// oldest observation.
+    pub fn observation_ago(&self, seconds_ago: u64) -> AccumulatedObservation {
+        let now_seconds = Clock::instant().seconds_since_unix_epoch as u64;
+        self.observation(now_seconds.saturating_sub(seconds_ago))
+    }
+
+    /// Returns the spot price at a given timestamp, derived from two observations instead of
+    /// returning an accumulator, so that a single call suffices.
+    ///
+    /// The oracle only knows the average price of each minute, so the spot price is the geometric
+    /// mean of the price over the one minute window ending at the timestamp (rounded to the
+    /// minute), i.e. the price of the minute before it. This way the spot price is also available
+    /// for the current minute, which has not ended yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds`: The timestamp in seconds, automatically rounded to the minute.
+    ///
+    /// # Returns
+    ///
+    /// The `price` of `observation_interval(minute - 60, minute)`, where `minute` is `seconds`
+    /// rounded down to the minute.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`, e.g. if the minute before the
+    /// timestamp is older than the oldest observation.
+    pub fn spot_price(&self, seconds: u64) -> Decimal {
+        let end_minutes = seconds / 60;
+        self.observation_interval(end_minutes.saturating_sub(1) * 60, end_minutes * 60)
+            .price
+    }
+
+    /// Returns the observation at the midpoint of the available range, i.e. halfway between the
+    /// oldest observation and the current time (rounded down to the minute), e.g. as a quick
+    /// health check or to sample a representative historical price.
+    ///
+    /// # Returns
+    ///
+    /// The `AccumulatedObservation` at `(oldest_minute + now_minute) / 2` with the timestamp in
+    /// seconds like `observation`, or `None` if no observations exist yet.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation` for the midpoint, e.g. if it lies in a
+    /// gap wider than `max_interpolation_gap_minutes`.
+    pub fn midpoint_observation(&self) -> Option<AccumulatedObservation> {
+        let oldest_minutes = self.oldest_observation_at_minutes()?;
+        let now_minutes = Clock::time_in_minutes();
+        Some(self.observation((oldest_minutes + now_minutes) / 2 * 60))
+    }
+
+    /// Returns the prices at both ends of the available range, e.g. for dashboards comparing the
+    /// price "then vs now" without differencing the accumulators themselves.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the price (y per x) of the oldest stored minute, i.e. of
+    /// `observation_interval(oldest, oldest + 60)`, and the current spot price, i.e.
+    /// `spot_price(now)`. `None` if no observations exist yet or less than one minute of history
+    /// is available.
+    ///
+    /// # Panics
+    /// Panics if a price does not fit into a `Decimal`.
+    pub fn range_prices(&self) -> Option<(Decimal, Decimal)> {
+        let oldest_minutes = self.oldest_observation_at_minutes()?;
+        let now_minutes = Clock::time_in_minutes();
+        if now_minutes <= oldest_minutes {
+            return None;
+        }
+
+        let oldest_price = self
+            .observation_interval(oldest_minutes * 60, (oldest_minutes + 1) * 60)
+            .price;
+        Some((oldest_price, self.spot_price(now_minutes * 60)))
+    }
+
     /// Returns an `AccumulatedObservation` for a given timestamp.
     ///
     /// # Arguments
@@ -188,21 +753,7 @@ impl Oracle {
     /// Panics if the `target_minutes` is not within the range of the oldest timestamp and the
     /// current time.
     fn observation_internal(&self, target_minutes: u64) -> AccumulatedObservation {
-        // Unix minutes
-        let now_minutes = Clock::time_in_minutes();
-
-        // Assert that the target timestamp is in the allowed range
-        // (oldest_timestamp <= target_minutes <= now)
-        let oldest_timestamp = self
-            .oldest_observation_at_minutes()
-            .expect("No observations exist yet.");
-        assert!(
-            target_minutes >= oldest_timestamp && target_minutes <= now_minutes,
-            "Timestamp {} (rounded to the minute) not in range. The available range is [{}, {}]",
-            target_minutes * 60,
-            oldest_timestamp * 60,
-            now_minutes * 60
-        );
+        self.assert_target_in_range(target_minutes);
 
         let last_observation = self
             .observations
@@ -213,122 +764,2019 @@ impl Oracle {
             return last_observation.clone();
         }
 
-        let sub_observations = self.sub_observations.as_ref().unwrap();
-
         if target_minutes > last_observation.timestamp {
+            // Only reachable in states which did not observe a swap since being set up, e.g.
+            // observations seeded or restored without the current minute
+            let sub_observations = self.sub_observations.as_ref().unwrap_or_else(|| {
+                panic!(
+                    "Timestamp {} (rounded to the minute) is after the last observation at {}, \
+                    but the state of the current minute needed to extrapolate is missing.",
+                    target_minutes * 60,
+                    last_observation.timestamp * 60
+                )
+            });
             let minutes_since_last = target_minutes - last_observation.timestamp;
+            assert!(
+                !self.exceeds_max_extrapolation(minutes_since_last),
+                "Timestamp {} (rounded to the minute) is more than {} seconds after the last \
+                observation at {}. The oracle does not extrapolate stale prices that far.",
+                target_minutes * 60,
+                self.max_extrapolation_seconds.unwrap(),
+                last_observation.timestamp * 60
+            );
 
-            let price_sqrt_log_acc = accumulated_log(
-                last_observation.price_sqrt_log_acc,
-                sub_observations.finalize_preview(),
-                sub_observations.price_sqrt_last,
+            return last_observation.accumulate(
+                target_minutes,
+                &sub_observations.finalize_preview(),
                 minutes_since_last,
             );
-
-            return AccumulatedObservation {
-                timestamp: target_minutes,
-                price_sqrt_log_acc,
-            };
         }
 
-        binary_search_and_interpolation(
+        // A target in range older than the last observation implies at least two stored
+        // observations, e.g. an oracle with a limit of one only serves exact and extrapolated
+        // targets.
+        let (o_left, o_right) = binary_search_neighbors(
             &self.observations,
             self.oldest_index().unwrap(),
             self.observations_stored,
             target_minutes,
-        )
+        );
+        assert!(
+            !self.exceeds_max_interpolation_gap(&o_left, &o_right),
+            "Timestamp {} (rounded to the minute) lies between the observations at {} and {}, \
+            which are more than {} minutes apart. The oracle does not interpolate that far.",
+            target_minutes * 60,
+            o_left.timestamp * 60,
+            o_right.timestamp * 60,
+            self.max_interpolation_gap_minutes.unwrap()
+        );
+
+        interpolate_neighbors(o_left, o_right, target_minutes)
     }
 
-    /// Calculates the geometric mean of the price square root over specified intervals.
-    /// The timestamps are automatically rounded to the minute.
+    /// Returns the two stored observations bracketing a given timestamp in seconds, which are the
+    /// ones `observation` interpolates between. The timestamp is automatically rounded to the
+    /// minute.
     ///
     /// # Arguments
-    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
-    /// representing the start and end of an interval in Unix seconds.
+    /// * `seconds`: The timestamp in seconds for which the neighbors are sought.
     ///
     /// # Returns
-    /// A vector of `ObservationInterval` structs. Each `ObservationInterval` contains:
-    /// * `start`: The start of the interval in Unix seconds.
-    /// * `end`: The end of the interval in Unix seconds.
-    /// * `price_sqrt`: The calculated geometric mean of the price square root for the interval.
-    pub fn observation_intervals(
+    /// A tuple with the left and right neighbors (with timestamps in seconds). Both are equal to
+    /// the stored observation if one exists for the timestamp, or to the last stored observation
+    /// if the timestamp is more recent than it.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation`.
+    pub fn observation_neighbors(
         &self,
-        intervals_in_seconds: Vec<(u64, u64)>,
-    ) -> Vec<ObservationInterval> {
-        let mut averages = Vec::with_capacity(intervals_in_seconds.len());
-
-        for (t_left_seconds, t_right_seconds) in intervals_in_seconds {
-            let t_left_minutes = t_left_seconds / 60;
-            let t_right_minutes = t_right_seconds / 60;
-
-            assert!(
-                t_left_minutes < t_right_minutes,
-                "Provided intervals in seconds must be of the type [a, b], where a/60 < b/60,
-                i.e. they must round down to different minutes. Interval [{}, {}] does not obey this
-                condition.",
-                t_left_seconds,
-                t_right_seconds
-            );
+        seconds: u64,
+    ) -> (AccumulatedObservation, AccumulatedObservation) {
+        let target_minutes = seconds / 60;
+        self.assert_target_in_range(target_minutes);
 
-            let o_l = self.observation_internal(t_left_minutes);
-            let o_r = self.observation_internal(t_right_minutes);
-
-            let price_sqrt = geometric_mean(
-                t_left_minutes,
-                t_right_minutes,
-                o_l.price_sqrt_log_acc,
-                o_r.price_sqrt_log_acc,
-            );
+        let last_observation = self
+            .observations
+            .get(&self.last_observation_index.unwrap())
+            .unwrap()
+            .clone();
 
-            averages.push(ObservationInterval {
-                start: t_left_minutes * 60,
-                end: t_right_minutes * 60,
-                price_sqrt,
-            });
-        }
+        let (mut left, mut right) = if target_minutes >= last_observation.timestamp {
+            (last_observation.clone(), last_observation)
+        } else {
+            binary_search_neighbors(
+                &self.observations,
+                self.oldest_index().unwrap(),
+                self.observations_stored,
+                target_minutes,
+            )
+        };
+        left.timestamp *= 60;
+        right.timestamp *= 60;
 
-        averages
+        (left, right)
     }
 
-    /// Returns the limit of observations that can be stored.
+    /// Returns a conservative bound on how far the interpolated `price_sqrt_log_acc` of a given
+    /// timestamp could be from the accumulator of the true price path, e.g. to size the safety
+    /// margin of a lending protocol. The timestamp is automatically rounded to the minute.
+    ///
+    /// The estimator assumes that the accumulator adds one log sample per minute, and takes the
+    /// dispersion of these samples between the neighbors from the second moment stored in
+    /// `price_sqrt_log_sq_acc`. Interpolating `k` minutes into a gap of `n` minutes with a
+    /// per-minute variance `v` misses at most `sqrt(v * k * (n - k))`, which is reached if the
+    /// samples before and after the target deviate from the mean in opposite directions. The
+    /// truncation of the `Decimal` interpolation is not included.
+    ///
+    /// # Arguments
+    /// * `seconds`: The timestamp in seconds of the interpolated observation.
     ///
     /// # Returns
+    /// The bound in units of `price_sqrt_log_acc`, which is zero for stored and extrapolated
+    /// observations, as well as for gaps over which the price did not move.
     ///
-    /// A `u16` value representing the maximum number of observations that can be stored in the oracle.
-    pub fn observations_limit(&self) -> u16 {
-        self.observations_limit
+    /// # Panics
+    /// Panics under the same conditions as `observation_neighbors`.
+    pub fn interpolation_error_bound(&self, seconds: u64) -> Decimal {
+        let (o_left, o_right) = self.observation_neighbors(seconds);
+        if o_left.timestamp == o_right.timestamp {
+            return dec!(0);
+        }
+
+        let left_minutes = o_left.timestamp / 60;
+        let right_minutes = o_right.timestamp / 60;
+        let log_mean = arithmetic_mean(
+            left_minutes,
+            right_minutes,
+            o_left.price_sqrt_log_acc,
+            o_right.price_sqrt_log_acc,
+        );
+        let log_sq_mean = arithmetic_mean(
+            left_minutes,
+            right_minutes,
+            o_left.price_sqrt_log_sq_acc,
+            o_right.price_sqrt_log_sq_acc,
+        );
+        let variance = (log_sq_mean - log_mean * log_mean).max(dec!(0));
+
+        let minutes_before = seconds / 60 - left_minutes;
+        let minutes_after = right_minutes - seconds / 60;
+        (variance * minutes_before * minutes_after)
+            .checked_sqrt()
+            .unwrap()
     }
 
-    /// Returns the number of observations currently stored.
+    /// Estimates the number of `KeyValueStore` reads `observation` performs for a given timestamp,
+    /// allowing integrators to size the fee budget of a batch of queries without a dry run. The
+    /// timestamp is automatically rounded to the minute.
     ///
-    /// # Returns
+    /// Every lookup reads the oldest and the last observation. Targets at or after the last
+    /// observation are served from it (exactly or extrapolated), while older targets are found by
+    /// a binary search over the `observations_stored` slots, reading one slot per halving and the
+    /// two neighbors at the end.
+    ///
+    /// # Arguments
+    /// * `seconds`: The timestamp in seconds of the lookup.
+    ///
+    /// # Returns
+    /// An upper bound of the reads, i.e. `2` for the last and extrapolated observations and
+    /// `4 + ceil(log2(observations_stored - 1))` for older ones, or `0` if no observations are
+    /// stored. Targets out of range are estimated like targets in range, even though the lookup
+    /// fails.
+    pub fn estimate_query_reads(&self, seconds: u64) -> u16 {
+        let last_index = match self.last_observation_index {
+            Some(index) => index,
+            None => return 0,
+        };
+        let last_timestamp = self.observations.get(&last_index).unwrap().timestamp;
+        if seconds / 60 >= last_timestamp {
+            return 2;
+        }
+
+        // ceil(log2(observations_stored - 1)) halvings until both neighbors are adjacent
+        let halvings =
+            (u16::BITS - self.observations_stored.saturating_sub(2).leading_zeros()) as u16;
+        4 + halvings
+    }
+
+    /// Same as `assert_target_in_range`, but names the failing bound of the interval
+    /// `[start_seconds, end_seconds]` in the panic message.
+    fn assert_interval_bound_in_range(
+        &self,
+        bound: &str,
+        target_minutes: u64,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) {
+        // Unix minutes
+        let now_minutes = Clock::time_in_minutes();
+
+        let oldest_timestamp = self
+            .oldest_observation_at_minutes()
+            .expect("No observations exist yet.");
+        assert!(
+            target_minutes >= oldest_timestamp,
+            "The {} bound of interval [{}, {}] is older than the oldest observation at {}.",
+            bound,
+            start_seconds,
+            end_seconds,
+            oldest_timestamp * 60
+        );
+        assert!(
+            target_minutes <= now_minutes,
+            "The {} bound of interval [{}, {}] is in the future, the current time is {}.",
+            bound,
+            start_seconds,
+            end_seconds,
+            now_minutes * 60
+        );
+    }
+
+    /// Asserts that the target timestamp is in the allowed range
+    /// (oldest_timestamp <= target_minutes <= now).
+    fn assert_target_in_range(&self, target_minutes: u64) {
+        // Unix minutes
+        let now_minutes = Clock::time_in_minutes();
+
+        let oldest_timestamp = self
+            .oldest_observation_at_minutes()
+            .expect("No observations exist yet.");
+        assert!(
+            target_minutes >= oldest_timestamp && target_minutes <= now_minutes,
+            "Timestamp {} (rounded to the minute) not in range. The available range is [{}, {}]",
+            target_minutes * 60,
+            oldest_timestamp * 60,
+            now_minutes * 60
+        );
+    }
+
+    /// Calculates the geometric mean of the price square root over specified intervals.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds. An end of
+    /// `INTERVAL_END_NOW` stands for the current time.
+    ///
+    /// # Returns
+    /// A vector of `ObservationInterval` structs. Each `ObservationInterval` contains:
+    /// * `start`: The start of the interval in Unix seconds.
+    /// * `end`: The end of the interval in Unix seconds.
+    /// * `price_sqrt`: The calculated geometric mean of the price square root for the interval.
+    ///
+    /// An empty vector of intervals returns an empty vector. Use `try_observation_intervals` to
+    /// reject it instead.
+    pub fn observation_intervals(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationInterval> {
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.observation_interval(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    /// Same as `observation_intervals`, but takes the bounds as `UnixSeconds`, so that the
+    /// compiler rejects intervals in minutes.
+    pub fn observation_intervals_typed(
+        &self,
+        intervals: Vec<(UnixSeconds, UnixSeconds)>,
+    ) -> Vec<ObservationInterval> {
+        self.observation_intervals(
+            intervals
+                .into_iter()
+                .map(|(start, end)| (start.0, end.0))
+                .collect(),
+        )
+    }
+
+    /// Same as `observation_intervals`, but calculates repeated intervals only once.
+    ///
+    /// Intervals are considered identical if their start and end in Unix seconds are equal.
+    /// The result of each unique interval is copied to all of its positions, so that the
+    /// returned vector has the same order and length as `intervals_in_seconds`.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds.
+    ///
+    /// # Returns
+    /// A vector of `ObservationInterval` structs, one for each given interval.
+    pub fn observation_intervals_dedup(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationInterval> {
+        let mut unique_intervals: Vec<(u64, u64)> = Vec::new();
+        let positions: Vec<usize> = intervals_in_seconds
+            .iter()
+            .map(|interval| {
+                unique_intervals
+                    .iter()
+                    .position(|unique_interval| unique_interval == interval)
+                    .unwrap_or_else(|| {
+                        unique_intervals.push(*interval);
+                        unique_intervals.len() - 1
+                    })
+            })
+            .collect();
+
+        let unique_results = self.observation_intervals(unique_intervals);
+        positions
+            .into_iter()
+            .map(|position| unique_results[position].clone())
+            .collect()
+    }
+
+    /// Same as `observation_intervals`, but memoizes the results of recently requested intervals.
+    ///
+    /// Up to `INTERVAL_CACHE_SIZE` intervals are kept, keyed by their bounds (rounded to the
+    /// minute) and the `last_observation_index` they were computed at, evicting the least
+    /// recently used one. Only intervals ending at or before the last stored observation are
+    /// cached, since later ends are extrapolated from the current minute, which changes with every
+    /// update. The cache is cleared whenever a new observation is inserted. Since storing the
+    /// results writes component state, this requires `&mut self` and only pays off for intervals
+    /// requested repeatedly, e.g. popular TWAP windows served to many consumers.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - See `observation_intervals`.
+    ///
+    /// # Returns
+    /// A vector of `ObservationInterval` structs equal to the result of `observation_intervals`.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn observation_intervals_cached(
+        &mut self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationInterval> {
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.observation_interval_cached(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    /// Looks up a single interval in the cache of `observation_intervals_cached`, computing and
+    /// storing it on a miss.
+    fn observation_interval_cached(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> ObservationInterval {
+        let last_observation_index = match self.last_observation_index {
+            Some(last_observation_index) => last_observation_index,
+            None => return self.observation_interval(start_seconds, end_seconds),
+        };
+        let last_timestamp = self
+            .observations
+            .get(&last_observation_index)
+            .unwrap()
+            .timestamp;
+        if end_seconds == INTERVAL_END_NOW || end_seconds / 60 > last_timestamp {
+            return self.observation_interval(start_seconds, end_seconds);
+        }
+
+        // Entries computed before the ring was rearranged (e.g. by `increase_capacity`)
+        self.interval_cache
+            .retain(|entry| entry.last_observation_index == last_observation_index);
+
+        let (start_minutes, end_minutes) = (start_seconds / 60, end_seconds / 60);
+        if let Some(position) = self.interval_cache.iter().position(|entry| {
+            entry.start_minutes == start_minutes && entry.end_minutes == end_minutes
+        }) {
+            let entry = self.interval_cache.remove(position);
+            let interval = entry.interval.clone();
+            self.interval_cache.push(entry);
+            return interval;
+        }
+
+        let interval = self.observation_interval(start_seconds, end_seconds);
+        if self.interval_cache.len() == INTERVAL_CACHE_SIZE {
+            self.interval_cache.remove(0);
+        }
+        self.interval_cache.push(IntervalCacheEntry {
+            start_minutes,
+            end_minutes,
+            last_observation_index,
+            interval: interval.clone(),
+        });
+        interval
+    }
+
+    /// Calculates the geometric mean of the price square root over a single interval.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// This is the single interval counterpart of `observation_intervals`, avoiding the need to
+    /// wrap and unwrap a vector when only one average is needed.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// An `ObservationInterval` containing the start and end of the interval (rounded to the
+    /// minute) in Unix seconds, and the geometric mean of the price square root for the interval.
+    ///
+    /// # Panics
+    /// Panics if `start_seconds` and `end_seconds` do not round down to increasing minutes, or if
+    /// any of them is not within the range of the oldest timestamp and the current time.
+    pub fn observation_interval(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> ObservationInterval {
+        self.checked_observation_interval(start_seconds, end_seconds)
+            .unwrap_or_else(|_| panic_price_overflow(start_seconds, end_seconds))
+    }
+
+    /// Calculates the geometric mean of the price square root over specified intervals, like
+    /// `observation_intervals`, together with a confidence score for each of them.
+    ///
+    /// Every observation accumulates the minutes in which the oracle observed the pool
+    /// (`observed_minutes_acc`), while the other minutes carry the last price forward. The score
+    /// is the share of observed minutes in the interval:
+    ///
+    /// `confidence = observed minutes in [start, end) / duration_minutes`
+    ///
+    /// A score of 1 means every minute of the interval was observed, while a score close to 0
+    /// means the average is mostly extrapolated, e.g. over a long period without swaps. The
+    /// synthetic observations filling such a period (see `set_max_observation_gap_minutes`) do
+    /// not count as observed, while minutes merged by `min_spacing_minutes` still do.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - See `observation_intervals`.
+    ///
+    /// # Returns
+    /// A vector of `(ObservationInterval, Decimal)` tuples, with the confidence between 0 and 1.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn observation_intervals_with_confidence(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<(ObservationInterval, Decimal)> {
+        self.observation_intervals(intervals_in_seconds)
+            .into_iter()
+            .map(|interval| {
+                let (o_l, o_r) = self.interval_observations(interval.start, interval.end);
+                let observed_minutes = o_r.observed_minutes_acc - o_l.observed_minutes_acc;
+                let confidence =
+                    Decimal::from(observed_minutes) / Decimal::from(interval.duration_minutes);
+                (interval, confidence)
+            })
+            .collect()
+    }
+
+    /// Same as `observation_interval`, but returns `OracleError::PriceOverflow` instead of
+    /// panicking if the average price does not fit into a `Decimal`.
+    fn checked_observation_interval(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Result<ObservationInterval, OracleError> {
+        let detailed = self
+            .checked_observation_interval_detailed(start_seconds, end_seconds)
+            .ok_or(OracleError::PriceOverflow)?;
+        let price = checked_price(detailed.price_sqrt).ok_or(OracleError::PriceOverflow)?;
+
+        Ok(ObservationInterval {
+            start: detailed.start,
+            end: detailed.end,
+            duration_minutes: (detailed.end - detailed.start) / 60,
+            price_sqrt: detailed.price_sqrt,
+            price,
+        })
+    }
+
+    /// Calculates the geometric mean of the price square root over the last `window_seconds`,
+    /// i.e. over the interval ending at the current time. A window exceeding the stored history is
+    /// clamped to start at the oldest stored observation.
+    ///
+    /// # Arguments
+    /// * `window_seconds` - The length of the window in seconds.
+    ///
+    /// # Returns
+    /// An `ObservationInterval` for `[max(now - window_seconds, oldest_observation_at), now]`, see
+    /// `observation_interval`.
+    ///
+    /// # Panics
+    /// Panics if the (clamped) window spans less than one minute, e.g. because no observations
+    /// exist yet, and otherwise under the same conditions as `observation_interval`.
+    pub fn twap_last(&self, window_seconds: u64) -> ObservationInterval {
+        let (start_seconds, end_seconds) =
+            self.twap_last_bounds(window_seconds).unwrap_or_else(|_| {
+                panic!(
+                    "Less than one minute of history is available for a window of {} seconds.",
+                    window_seconds
+                )
+            });
+        self.observation_interval(start_seconds, end_seconds)
+    }
+
+    /// Same as `twap_last`, but returns an `OracleError` instead of panicking.
+    pub fn try_twap_last(&self, window_seconds: u64) -> Result<ObservationInterval, OracleError> {
+        let (start_seconds, end_seconds) = self.twap_last_bounds(window_seconds)?;
+        self.try_observation_interval(start_seconds, end_seconds)
+    }
+
+    /// Returns the bounds in seconds of the window of `twap_last`, with the start clamped to the
+    /// oldest stored observation, or `OracleError::InsufficientHistory` if the clamped window
+    /// does not span at least one minute.
+    fn twap_last_bounds(&self, window_seconds: u64) -> Result<(u64, u64), OracleError> {
+        let now_seconds = Clock::time_in_minutes() * 60;
+        let oldest_seconds = self
+            .oldest_observation_at()
+            .ok_or(OracleError::InsufficientHistory)?;
+        let start_seconds = max(now_seconds.saturating_sub(window_seconds), oldest_seconds);
+        if start_seconds / 60 >= now_seconds / 60 {
+            return Err(OracleError::InsufficientHistory);
+        }
+        Ok((start_seconds, now_seconds))
+    }
+
+    /// Calculates the geometric mean of the price square root over the entire stored history,
+    /// i.e. from the oldest stored observation until the current time.
+    ///
+    /// # Returns
+    /// An `ObservationInterval` for `[oldest_observation_at, now]`, see `observation_interval`.
+    ///
+    /// # Panics
+    /// Panics if no observations exist yet, or if the oldest observation and the current time are
+    /// in the same minute, i.e. less than two distinct minutes of history are available.
+    pub fn twap_full_history(&self) -> ObservationInterval {
+        let start_seconds = self
+            .oldest_observation_at()
+            .expect("No observations exist yet.");
+        self.observation_interval(start_seconds, Clock::time_in_minutes() * 60)
+    }
+
+    /// Same as `twap_full_history`, but returns an `OracleError` instead of panicking.
+    pub fn try_twap_full_history(&self) -> Result<ObservationInterval, OracleError> {
+        let start_seconds = self
+            .oldest_observation_at()
+            .ok_or(OracleError::NoObservations)?;
+        self.try_observation_interval(start_seconds, Clock::time_in_minutes() * 60)
+    }
+
+    /// Same as `twap_last`, but memoizes the last result for the current minute.
+    ///
+    /// Repeated requests for the same window within a minute return the cached interval instead
+    /// of searching and interpolating again. The cache is invalidated when the minute advances or
+    /// a new observation is inserted. Since storing the result writes component state, this
+    /// requires `&mut self` and only pays off if the same window is requested several times.
+    ///
+    /// # Arguments
+    /// * `window_seconds` - The length of the window in seconds.
+    pub fn twap_last_cached(&mut self, window_seconds: u64) -> ObservationInterval {
+        let now_minutes = Clock::time_in_minutes();
+        if let Some(cache) = &self.twap_cache {
+            if cache.window_seconds == window_seconds && cache.minute == now_minutes {
+                return cache.interval.clone();
+            }
+        }
+
+        let interval = self.twap_last(window_seconds);
+        self.twap_cache = Some(TwapCache {
+            window_seconds,
+            minute: now_minutes,
+            interval: interval.clone(),
+        });
+        interval
+    }
+
+    /// Same as `observation_intervals`, but additionally returns the accumulated logarithms the
+    /// geometric mean is derived from, allowing to reproduce the computation off-chain.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds.
+    ///
+    /// # Returns
+    /// A vector of `ObservationIntervalDetailed` where `price_sqrt` equals
+    /// `geometric_mean(start / 60, end / 60, acc_left, acc_right)`.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn observation_intervals_detailed(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationIntervalDetailed> {
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.observation_interval_detailed(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    fn observation_interval_detailed(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> ObservationIntervalDetailed {
+        self.checked_observation_interval_detailed(start_seconds, end_seconds)
+            .unwrap_or_else(|| panic_price_overflow(start_seconds, end_seconds))
+    }
+
+    /// Same as `observation_interval_detailed`, but returns `None` instead of panicking if the
+    /// geometric mean does not fit into a `PreciseDecimal`.
+    fn checked_observation_interval_detailed(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Option<ObservationIntervalDetailed> {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        let price_sqrt = checked_geometric_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_sqrt_log_acc,
+            o_r.price_sqrt_log_acc,
+        )?;
+
+        Some(ObservationIntervalDetailed {
+            start: o_l.timestamp * 60,
+            end: o_r.timestamp * 60,
+            price_sqrt,
+            acc_left: o_l.price_sqrt_log_acc,
+            acc_right: o_r.price_sqrt_log_acc,
+        })
+    }
+
+    /// Returns the (possibly interpolated or extrapolated) observations at both bounds of an
+    /// interval, i.e. the raw accumulators any average over the interval is derived from. This
+    /// allows integrators to verify an average off-chain without trusting the oracle's
+    /// computation, e.g. the geometric mean is `exp((acc_right - acc_left) / minutes)`.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds, or `INTERVAL_END_NOW`.
+    ///
+    /// # Returns
+    /// The observations at the start and at the end of the interval, with the timestamps in
+    /// seconds like `observation`.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn interval_endpoints(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> (AccumulatedObservation, AccumulatedObservation) {
+        let (mut o_l, mut o_r) = self.interval_observations(start_seconds, end_seconds);
+        o_l.timestamp *= 60;
+        o_r.timestamp *= 60;
+        (o_l, o_r)
+    }
+
+    /// Returns the difference of the accumulators over an interval together with its length, the
+    /// fundamental quantity every average over the interval is derived from. Consumers can apply
+    /// any mean with a single call, e.g. the geometric mean of the price square root is
+    /// `exp(delta / minutes)` as returned by `observation_intervals`.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds, or `INTERVAL_END_NOW`.
+    ///
+    /// # Returns
+    /// A tuple of `acc_right - acc_left` of `price_sqrt_log_acc` and the length of the interval
+    /// in minutes.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`, and if the difference of the
+    /// accumulators does not fit into a `Decimal`, e.g. for saturated accumulators.
+    pub fn accumulator_delta(&self, start_seconds: u64, end_seconds: u64) -> (Decimal, u64) {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+        let delta = o_r
+            .price_sqrt_log_acc
+            .checked_sub(o_l.price_sqrt_log_acc)
+            .unwrap_or_else(|| {
+                panic!(
+                    "The difference of the accumulators over interval [{}, {}] does not fit into \
+                    a Decimal.",
+                    start_seconds, end_seconds
+                )
+            });
+        (delta, o_r.timestamp - o_l.timestamp)
+    }
+
+    /// Calculates the time-weighted (geometric) average price over specified intervals, i.e. the
+    /// square of the `price_sqrt` returned by `observation_intervals`.
+    ///
+    /// On Radix, resource amounts are `Decimal`s regardless of the divisibility of the resource,
+    /// so the pool price is already expressed in whole tokens of y per whole token of x. Unlike on
+    /// ledgers with integer amounts, no adjustment for the token decimals is required.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds.
+    ///
+    /// # Returns
+    /// A vector with the average price (y per x) of each interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn twap_prices(&self, intervals_in_seconds: Vec<(u64, u64)>) -> Vec<Decimal> {
+        self.observation_intervals(intervals_in_seconds)
+            .into_iter()
+            .map(|interval| interval.price)
+            .collect()
+    }
+
+    /// Calculates the time-weighted (geometric) average price over several intervals combined,
+    /// e.g. to stitch a TWAP across intervals of differing lengths. The average log price of each
+    /// interval is weighted by its length in minutes, so for non-overlapping intervals this is the
+    /// TWAP over their union. Overlapping minutes are weighted once per interval covering them.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The duration-weighted average price (y per x) across all intervals.
+    ///
+    /// # Panics
+    /// Panics if no interval is given, if the average price does not fit into a `Decimal`, and
+    /// otherwise under the same conditions as `observation_intervals`.
+    pub fn composite_twap(&self, intervals_in_seconds: Vec<(u64, u64)>) -> Decimal {
+        assert!(
+            !intervals_in_seconds.is_empty(),
+            "At least one interval is required."
+        );
+        let mut log_sum = Some(Decimal::ZERO);
+        let mut total_minutes = 0;
+        for (start_seconds, end_seconds) in intervals_in_seconds {
+            let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+            log_sum = log_sum.and_then(|log_sum| {
+                o_r.price_sqrt_log_acc
+                    .checked_sub(o_l.price_sqrt_log_acc)
+                    .and_then(|interval_log_sum| log_sum.checked_add(interval_log_sum))
+            });
+            total_minutes += o_r.timestamp - o_l.timestamp;
+        }
+
+        log_sum
+            .and_then(|log_sum| checked_geometric_mean(0, total_minutes, Decimal::ZERO, log_sum))
+            .and_then(checked_price)
+            .expect("The composite average price does not fit into a Decimal.")
+    }
+
+    /// Samples the time-weighted average price (y per x) at a fixed step, e.g. to draw a chart.
+    ///
+    /// Every sample covers the step starting at its timestamp, i.e. the interval
+    /// `[timestamp, timestamp + step_seconds]`, with its accumulators interpolated between the
+    /// stored observations. Like for `twap_prices`, the intervals are rounded to the minute.
+    /// A trailing remainder shorter than a step is not sampled.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The timestamp of the first sample in Unix seconds.
+    /// * `end_seconds` - The end of the last sampled step in Unix seconds.
+    /// * `step_seconds` - The distance between two samples in seconds, at least one minute.
+    ///
+    /// # Returns
+    /// A vector of `(timestamp, price)` tuples in chronological order, with the timestamp in Unix
+    /// seconds.
+    ///
+    /// # Panics
+    /// Panics if `step_seconds` is shorter than a minute, or under the same conditions as
+    /// `observation_intervals`.
+    pub fn chart_series(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+        step_seconds: u64,
+    ) -> Vec<(u64, Decimal)> {
+        assert!(
+            step_seconds >= 60,
+            "The step of {} seconds is shorter than a minute.",
+            step_seconds
+        );
+        // The samples are computed lazily and with checked arithmetic, so that bounds close to
+        // `u64::MAX` fail at the first sample in the future instead of overflowing or allocating
+        // an enormous series first.
+        std::iter::successors(Some(start_seconds), |timestamp| {
+            timestamp.checked_add(step_seconds)
+        })
+        .map_while(|timestamp| {
+            timestamp
+                .checked_add(step_seconds)
+                .filter(|&sample_end| sample_end <= end_seconds)
+                .map(|sample_end| (timestamp, sample_end))
+        })
+        .map(|(timestamp, sample_end)| {
+            (
+                timestamp,
+                self.observation_interval(timestamp, sample_end).price,
+            )
+        })
+        .collect()
+    }
+
+    /// Resamples the stored observations onto a uniform grid, e.g. to export a compact history
+    /// with a fixed cadence regardless of the irregular times of the real observations.
+    ///
+    /// The grid starts at the oldest stored observation and advances by `step_minutes` up to the
+    /// last stored one, which is always included as the final point even if it is not on the
+    /// grid. The points in between are interpolated like in `observation`.
+    ///
+    /// # Arguments
+    /// * `step_minutes` - The distance between two grid points in minutes.
+    ///
+    /// # Returns
+    /// A vector of `AccumulatedObservation`s in chronological order, with the timestamps in Unix
+    /// seconds. It only contains the oldest and the last observation if `step_minutes` exceeds
+    /// their distance, and is empty if no observations are stored.
+    ///
+    /// # Panics
+    /// Panics if `step_minutes` is zero.
+    pub fn downsample(&self, step_minutes: u64) -> Vec<AccumulatedObservation> {
+        assert!(step_minutes > 0, "The step must be at least one minute.");
+
+        let (oldest_minutes, last_minutes) = match (
+            self.oldest_observation_at_minutes(),
+            self.last_finalized_timestamp(),
+        ) {
+            (Some(oldest_minutes), Some(last_seconds)) => (oldest_minutes, last_seconds / 60),
+            _ => return vec![],
+        };
+
+        let mut grid: Vec<u64> = std::iter::successors(Some(oldest_minutes), |minutes| {
+            minutes.checked_add(step_minutes)
+        })
+        .take_while(|&minutes| minutes < last_minutes)
+        .collect();
+        grid.push(last_minutes);
+
+        grid.into_iter()
+            .map(|minutes| {
+                let mut observation = self.observation_internal(minutes);
+                observation.timestamp *= 60;
+                observation
+            })
+            .collect()
+    }
+
+    /// Calculates the harmonic mean of the price over specified intervals.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// The harmonic mean cannot be derived from the logarithmic accumulator, so it is computed
+    /// from a dedicated accumulator of `1 / price` (weighted by time within each minute as well).
+    /// The tradeoff is one more `PreciseDecimal` stored per observation. The `price` is the mean
+    /// itself and `price_sqrt` its square root, both derived from the mean of the inverse, since
+    /// the square of the harmonic mean of `price_sqrt` would overstate the harmonic mean of a
+    /// volatile price. The accumulator saturates early for very low prices and loses precision
+    /// for very high ones, see `accumulated_inverse`.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - A vector of tuples where each tuple contains two `u64` values
+    /// representing the start and end of an interval in Unix seconds.
+    ///
+    /// # Returns
+    /// A vector of `ObservationInterval`s containing the harmonic mean of the price (and its
+    /// square root) for each interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`, or if the harmonic mean does
+    /// not fit into a `Decimal` or is derived from a saturated or non-increasing accumulator, see
+    /// `try_observation_interval_harmonic`.
+    pub fn observation_intervals_harmonic(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationInterval> {
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.observation_interval_harmonic(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    /// Calculates the harmonic mean of the price over a single interval, see
+    /// `observation_intervals_harmonic`.
+    pub fn observation_interval_harmonic(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> ObservationInterval {
+        self.checked_observation_interval_harmonic(start_seconds, end_seconds)
+            .unwrap_or_else(|_| panic_price_overflow(start_seconds, end_seconds))
+    }
+
+    /// Same as `observation_interval_harmonic`, but returns an `OracleError` instead of
+    /// panicking, in particular `OracleError::PriceOverflow` if the accumulated inverse price is
+    /// saturated or does not increase over the interval, see `accumulated_inverse`.
+    pub fn try_observation_interval_harmonic(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Result<ObservationInterval, OracleError> {
+        self.validate_interval(start_seconds, end_seconds)?;
+        self.checked_observation_interval_harmonic(start_seconds, end_seconds)
+    }
+
+    /// Same as `observation_interval_harmonic`, but returns `OracleError::PriceOverflow` instead
+    /// of panicking if the harmonic mean cannot be derived.
+    fn checked_observation_interval_harmonic(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Result<ObservationInterval, OracleError> {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        let price_inverse_mean = checked_inverse_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_inverse_acc,
+            o_r.price_inverse_acc,
+        )
+        .ok_or(OracleError::PriceOverflow)?;
+        let price_sqrt = price_inverse_mean
+            .checked_sqrt()
+            .and_then(|price_sqrt_inverse| pdec!(1).checked_div(price_sqrt_inverse))
+            .ok_or(OracleError::PriceOverflow)?;
+        let price = pdec!(1)
+            .checked_div(price_inverse_mean)
+            .and_then(|price| price.checked_truncate(RoundingMode::ToNegativeInfinity))
+            .ok_or(OracleError::PriceOverflow)?;
+
+        Ok(ObservationInterval {
+            start: o_l.timestamp * 60,
+            end: o_r.timestamp * 60,
+            duration_minutes: o_r.timestamp - o_l.timestamp,
+            price_sqrt,
+            price,
+        })
+    }
+
+    /// Calculates the geometric mean of the inverse price square root (x per y) over specified
+    /// intervals, i.e. the TWAP for consumers quoting the pair in the opposite direction.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// Since `log(1 / price_sqrt) = -log(price_sqrt)`, the accumulator of the inverse direction
+    /// is the negated `price_sqrt_log_acc`, so no dedicated accumulator has to be stored. The
+    /// result is computed from the negated accumulators instead of inverting the result of
+    /// `observation_intervals`, and is mathematically its exact reciprocal: unlike for the
+    /// arithmetic or harmonic mean, the geometric mean of the inverse is the inverse of the
+    /// geometric mean, even for a volatile price.
+    ///
+    /// # Arguments
+    /// * `intervals_in_seconds` - See `observation_intervals`.
+    ///
+    /// # Returns
+    /// A vector of `ObservationInterval`s containing the geometric mean of `1 / price_sqrt` (and
+    /// its square as `price`) for each interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_intervals`.
+    pub fn observation_intervals_inverse(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Vec<ObservationInterval> {
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.observation_interval_inverse(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    /// Calculates the geometric mean of the inverse price square root over a single interval,
+    /// see `observation_intervals_inverse`.
+    pub fn observation_interval_inverse(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> ObservationInterval {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        let price_sqrt = checked_geometric_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            -o_l.price_sqrt_log_acc,
+            -o_r.price_sqrt_log_acc,
+        )
+        .unwrap_or_else(|| panic_price_overflow(start_seconds, end_seconds));
+        let price = checked_price(price_sqrt)
+            .unwrap_or_else(|| panic_price_overflow(start_seconds, end_seconds));
+
+        ObservationInterval {
+            start: o_l.timestamp * 60,
+            end: o_r.timestamp * 60,
+            duration_minutes: o_r.timestamp - o_l.timestamp,
+            price_sqrt,
+            price,
+        }
+    }
+
+    /// Calculates the time-weighted average of the pool's active liquidity over an interval.
+    /// The timestamps are automatically rounded to the minute.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The time-weighted arithmetic mean of the active liquidity for the interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`.
+    pub fn average_liquidity(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        arithmetic_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.liquidity_acc,
+            o_r.liquidity_acc,
+        )
+    }
+
+    /// Calculates the time-weighted average of the pool's input fee rate over an interval, e.g.
+    /// for routing or fee analytics. The timestamps are automatically rounded to the minute.
+    ///
+    /// The fee rate is averaged per minute like the active liquidity, i.e. every rate is weighted
+    /// by the seconds it was in effect, and the last rate of an active minute holds for the
+    /// following idle minutes.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The time-weighted arithmetic mean of the input fee rate for the interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`.
+    pub fn average_fee_rate(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        arithmetic_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.fee_rate_acc,
+            o_r.fee_rate_acc,
+        )
+    }
+
+    /// Calculates the time-weighted variance of the log price over an interval, e.g. to estimate
+    /// the volatility of the pool. The timestamps are automatically rounded to the minute.
+    ///
+    /// Every minute of the interval contributes one sample, the logarithm of its average
+    /// `price_sqrt` (or of the last one for idle minutes), so the estimator is the population
+    /// variance `E[x^2] - E[x]^2` of these samples, scaled by 4 to convert from `price_sqrt` to
+    /// price. It is derived from the accumulators at both bounds without iterating the minutes.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The variance of the natural logarithm of the price per minute, which is never negative,
+    /// even if rounding would make it so.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`.
+    pub fn variance(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        let log_mean = arithmetic_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_sqrt_log_acc,
+            o_r.price_sqrt_log_acc,
+        );
+        let log_sq_mean = arithmetic_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_sqrt_log_sq_acc,
+            o_r.price_sqrt_log_sq_acc,
+        );
+
+        (dec!(4) * (log_sq_mean - log_mean * log_mean)).max(dec!(0))
+    }
+
+    /// Returns the per-minute log returns of the price over an interval, e.g. as the input of
+    /// volatility or correlation models. The timestamps are automatically rounded to the minute.
+    ///
+    /// The log price of a minute is the logarithm of its average `price_sqrt`, scaled by 2, and is
+    /// derived from the accumulators at the start and the end of the minute. The log return of a
+    /// minute is the difference between its log price and the one of the minute before, so the
+    /// returns add up to the change of the log price from the first to the last minute.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// A vector of `(timestamp, log_return)` tuples in chronological order, one for every minute
+    /// of the interval except the first, with the timestamp of the minute in Unix seconds.
+    ///
+    /// # Panics
+    /// Panics if the interval spans more than `LOG_RETURNS_MINUTES_MAX` minutes, if a log price or
+    /// return does not fit into a `Decimal`, or under the same conditions as
+    /// `observation_interval`.
+    pub fn log_returns(&self, start_seconds: u64, end_seconds: u64) -> Vec<(u64, Decimal)> {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+        assert!(
+            o_r.timestamp - o_l.timestamp <= LOG_RETURNS_MINUTES_MAX,
+            "The interval [{}, {}] spans more than {} minutes.",
+            start_seconds,
+            end_seconds,
+            LOG_RETURNS_MINUTES_MAX
+        );
+
+        let accumulators: Vec<Decimal> = std::iter::once(o_l.price_sqrt_log_acc)
+            .chain(
+                (o_l.timestamp + 1..o_r.timestamp)
+                    .map(|minutes| self.observation_internal(minutes).price_sqrt_log_acc),
+            )
+            .chain(std::iter::once(o_r.price_sqrt_log_acc))
+            .collect();
+        let log_prices: Vec<Decimal> = accumulators
+            .windows(2)
+            .map(|bounds| {
+                bounds[1]
+                    .checked_sub(bounds[0])
+                    .and_then(|log_price_sqrt| log_price_sqrt.checked_mul(dec!(2)))
+                    .expect("The log price of a minute does not fit into a Decimal.")
+            })
+            .collect();
+
+        (o_l.timestamp + 1..)
+            .zip(log_prices.windows(2))
+            .map(|(minutes, log_prices)| {
+                let log_return = log_prices[1]
+                    .checked_sub(log_prices[0])
+                    .expect("The log return of a minute does not fit into a Decimal.");
+                (minutes * 60, log_return)
+            })
+            .collect()
+    }
+
+    /// Calculates the total volume traded over an interval, in the denomination reported to
+    /// `observe`. The timestamps are automatically rounded to the minute, i.e. the volume of the
+    /// swaps in the minutes `[start_seconds / 60, end_seconds / 60)` is returned.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The volume traded during the interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`.
+    pub fn volume(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        o_r.volume_acc - o_l.volume_acc
+    }
+
+    /// Returns the number of swaps performed over an interval. The timestamps are automatically
+    /// rounded to the minute, i.e. the swaps in the minutes `[start_seconds / 60, end_seconds / 60)`
+    /// are counted.
+    ///
+    /// A minute built from a single swap is easier to manipulate than one built from many, so the
+    /// count allows weighting the trust in an average.
+    ///
+    /// # Arguments
+    /// * `start_seconds` - The start of the interval in Unix seconds.
+    /// * `end_seconds` - The end of the interval in Unix seconds.
+    ///
+    /// # Returns
+    /// The number of swaps performed during the interval.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `observation_interval`.
+    pub fn swap_count_in_range(&self, start_seconds: u64, end_seconds: u64) -> u64 {
+        let (o_l, o_r) = self.interval_observations(start_seconds, end_seconds);
+
+        o_r.swap_count_acc - o_l.swap_count_acc
+    }
+
+    /// Same as `observation`, but returns an `OracleError` instead of panicking if the timestamp
+    /// is not in range.
+    pub fn try_observation(&self, seconds: u64) -> Result<AccumulatedObservation, OracleError> {
+        self.validate_target(seconds / 60)?;
+        Ok(self.observation(seconds))
+    }
+
+    /// Same as `observation_intervals`, but returns the `OracleError` of the first invalid
+    /// interval instead of panicking. Unlike `observation_intervals`, an empty list of intervals
+    /// is rejected with `OracleError::EmptyIntervals`, since it usually hints at a bug of the
+    /// caller.
+    pub fn try_observation_intervals(
+        &self,
+        intervals_in_seconds: Vec<(u64, u64)>,
+    ) -> Result<Vec<ObservationInterval>, OracleError> {
+        if intervals_in_seconds.is_empty() {
+            return Err(OracleError::EmptyIntervals);
+        }
+        intervals_in_seconds
+            .into_iter()
+            .map(|(start_seconds, end_seconds)| {
+                self.try_observation_interval(start_seconds, end_seconds)
+            })
+            .collect()
+    }
+
+    /// Same as `observation_interval`, but returns an `OracleError` instead of panicking.
+    pub fn try_observation_interval(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Result<ObservationInterval, OracleError> {
+        self.validate_interval(start_seconds, end_seconds)?;
+        self.checked_observation_interval(start_seconds, end_seconds)
+    }
+
+    /// Same as `average_liquidity`, but returns an `OracleError` instead of panicking.
+    pub fn try_average_liquidity(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> Result<Decimal, OracleError> {
+        self.validate_interval(start_seconds, end_seconds)?;
+        Ok(self.average_liquidity(start_seconds, end_seconds))
+    }
+
+    /// Same as `volume`, but returns an `OracleError` instead of panicking.
+    pub fn try_volume(&self, start_seconds: u64, end_seconds: u64) -> Result<Decimal, OracleError> {
+        self.validate_interval(start_seconds, end_seconds)?;
+        Ok(self.volume(start_seconds, end_seconds))
+    }
+
+    /// Checks the conditions under which `observation_internal` panics.
+    fn validate_target(&self, target_minutes: u64) -> Result<(), OracleError> {
+        let oldest_timestamp = self
+            .oldest_observation_at_minutes()
+            .ok_or(OracleError::NoObservations)?;
+        if target_minutes < oldest_timestamp {
+            return Err(OracleError::TimestampTooOld);
+        }
+        if target_minutes > Clock::time_in_minutes() {
+            return Err(OracleError::TimestampInFuture);
+        }
+        let last_timestamp = self
+            .observations
+            .get(&self.last_observation_index.unwrap())
+            .unwrap()
+            .timestamp;
+        if target_minutes > last_timestamp && self.sub_observations.is_none() {
+            return Err(OracleError::ExtrapolationUnavailable);
+        }
+        if target_minutes > last_timestamp
+            && self.exceeds_max_extrapolation(target_minutes - last_timestamp)
+        {
+            return Err(OracleError::ExtrapolationTooStale);
+        }
+        // The neighbors are only searched for if a gap is configured
+        if target_minutes < last_timestamp && self.max_interpolation_gap_minutes.is_some() {
+            let (o_left, o_right) = binary_search_neighbors(
+                &self.observations,
+                self.oldest_index().unwrap(),
+                self.observations_stored,
+                target_minutes,
+            );
+            if self.exceeds_max_interpolation_gap(&o_left, &o_right) {
+                return Err(OracleError::InterpolationGapTooWide);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether extrapolating `minutes_since_last` minutes past the last observation
+    /// exceeds `max_extrapolation_seconds`.
+    fn exceeds_max_extrapolation(&self, minutes_since_last: u64) -> bool {
+        self.max_extrapolation_seconds
+            .is_some_and(|max_seconds| minutes_since_last * 60 > max_seconds)
+    }
+
+    /// Checks whether the two neighbors of an interpolated target are further apart than
+    /// `max_interpolation_gap_minutes`.
+    fn exceeds_max_interpolation_gap(
+        &self,
+        o_left: &AccumulatedObservation,
+        o_right: &AccumulatedObservation,
+    ) -> bool {
+        self.max_interpolation_gap_minutes
+            .is_some_and(|max_minutes| o_right.timestamp - o_left.timestamp > max_minutes)
+    }
+
+    /// Checks the conditions under which `interval_observations` panics.
+    fn validate_interval(&self, start_seconds: u64, end_seconds: u64) -> Result<(), OracleError> {
+        let end_seconds = resolve_interval_end(end_seconds);
+        if start_seconds / 60 >= end_seconds / 60 {
+            return Err(OracleError::IntervalNotIncreasing);
+        }
+        if self.exceeds_max_interval(end_seconds / 60 - start_seconds / 60) {
+            return Err(OracleError::IntervalTooLong);
+        }
+        self.validate_target(start_seconds / 60)?;
+        self.validate_target(end_seconds / 60)
+    }
+
+    /// Checks whether an interval spanning `duration_minutes` exceeds `max_interval_minutes`.
+    fn exceeds_max_interval(&self, duration_minutes: u64) -> bool {
+        self.max_interval_minutes
+            .is_some_and(|max_minutes| duration_minutes > max_minutes)
+    }
+
+    /// Returns the observations (timestamps in minutes) at both bounds of an interval given in
+    /// seconds, after asserting that the bounds round down to increasing minutes, do not exceed
+    /// `max_interval_minutes` and are in range.
+    /// An interval lying entirely before the oldest observation is reported as a whole rather
+    /// than by its left bound. An end of `INTERVAL_END_NOW` is resolved to the current time.
+    fn interval_observations(
+        &self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> (AccumulatedObservation, AccumulatedObservation) {
+        let end_seconds = resolve_interval_end(end_seconds);
+        let t_left_minutes = start_seconds / 60;
+        let t_right_minutes = end_seconds / 60;
+
+        assert!(
+            t_left_minutes < t_right_minutes,
+            "Provided intervals in seconds must be of the type [a, b], where a/60 < b/60,
+            i.e. they must round down to different minutes. Interval [{}, {}] does not obey this
+            condition.",
+            start_seconds,
+            end_seconds
+        );
+        assert!(
+            !self.exceeds_max_interval(t_right_minutes - t_left_minutes),
+            "Interval [{}, {}] spans {} minutes, more than the maximum of {} minutes.",
+            start_seconds,
+            end_seconds,
+            t_right_minutes - t_left_minutes,
+            self.max_interval_minutes.unwrap()
+        );
+        if let Some(oldest_timestamp) = self.oldest_observation_at_minutes() {
+            assert!(
+                t_right_minutes >= oldest_timestamp,
+                "Interval [{}, {}] lies entirely before the oldest observation at {}.",
+                start_seconds,
+                end_seconds,
+                oldest_timestamp * 60
+            );
+        }
+        self.assert_interval_bound_in_range("left", t_left_minutes, start_seconds, end_seconds);
+        self.assert_interval_bound_in_range("right", t_right_minutes, start_seconds, end_seconds);
+
+        (
+            self.observation_internal(t_left_minutes),
+            self.observation_internal(t_right_minutes),
+        )
+    }
+
+    /// Returns the limit of observations that can be stored.
+    ///
+    /// # Returns
+    ///
+    /// A `u16` value representing the maximum number of observations that can be stored in the oracle.
+    pub fn observations_limit(&self) -> u16 {
+        self.observations_limit
+    }
+
+    /// Increases the number of observations that can be stored, keeping the stored history.
+    ///
+    /// If the ring buffer has already wrapped around, the stored observations are moved to the
+    /// indices `0..observations_stored` in chronological order, so that new observations are
+    /// appended after the last one instead of overwriting the oldest ones. The state of the
+    /// current minute (`sub_observations`) is left untouched, so the next observation accumulates
+    /// from the last observed price.
+    ///
+    /// # Arguments
+    ///
+    /// * `observations_limit` - The new limit, which must be larger than the current one.
+    ///
+    /// # Panics
+    /// Panics if `observations_limit` is not larger than the current limit, see
+    /// `try_increase_capacity`.
+    pub fn increase_capacity(&mut self, observations_limit: u16) {
+        if self.try_increase_capacity(observations_limit).is_err() {
+            panic!(
+                "The observations limit can only be increased, from {} to {} requested.",
+                self.observations_limit, observations_limit
+            );
+        }
+    }
+
+    /// Same as `increase_capacity`, but returns `OracleError::CapacityTooSmall` instead of
+    /// panicking if `observations_limit` is not larger than the current limit, leaving the oracle
+    /// unchanged. Passing the current limit is rejected as well, since it would only reorder the
+    /// stored observations.
+    pub fn try_increase_capacity(&mut self, observations_limit: u16) -> Result<(), OracleError> {
+        if observations_limit <= self.observations_limit {
+            return Err(OracleError::CapacityTooSmall);
+        }
+
+        if let Some(oldest_index) = self.oldest_index().filter(|&index| index != 0) {
+            let observations: Vec<AccumulatedObservation> = (0..self.observations_stored)
+                .map(|offset| {
+                    let index = self.index_at_offset(oldest_index, offset);
+                    self.observations.get(&index).unwrap().clone()
+                })
+                .collect();
+            for (index, observation) in observations.into_iter().enumerate() {
+                self.observations.insert(index as u16, observation);
+            }
+            self.last_observation_index = Some(self.observations_stored - 1);
+        }
+
+        self.observations_limit = observations_limit;
+        Ok(())
+    }
+
+    /// Increases the number of observations that can be stored like `increase_capacity`, and
+    /// writes a placeholder (`AccumulatedObservation::empty()`) into every free slot, so that the
+    /// storage cost of the slots is paid upfront instead of by the swaps filling them.
+    ///
+    /// The placeholders are not observations: `observations_stored` is left unchanged, and since
+    /// all queries only read the `observations_stored` slots, the placeholders are never seen
+    /// before being overwritten by a new observation.
+    ///
+    /// # Arguments
+    ///
+    /// * `observations_limit` - The new limit, which must be larger than the current one.
+    ///
+    /// # Panics
+    /// Panics if `observations_limit` is not larger than the current limit.
+    pub fn reserve_capacity(&mut self, observations_limit: u16) {
+        self.increase_capacity(observations_limit);
+
+        for index in self.observations_stored..self.observations_limit {
+            if self.observations.get(&index).is_none() {
+                self.observations
+                    .insert(index, AccumulatedObservation::empty());
+            }
+        }
+    }
+
+    /// Decreases the number of observations that can be stored, keeping the most recent ones.
+    ///
+    /// The retained observations are moved to the indices `0..observations_stored` in
+    /// chronological order and `last_observation_index` is normalized accordingly, so that it is
+    /// within the new limit right away instead of only after the next insert wraps it around.
+    /// The slots beyond the new limit, including placeholders written by `reserve_capacity`, are
+    /// cleared. The state of the current minute (`sub_observations`) is left untouched, so the
+    /// next observation accumulates from the last observed price.
+    ///
+    /// # Arguments
+    ///
+    /// * `observations_limit` - The new limit, which must be at least 1 and not larger than the
+    /// current one.
+    ///
+    /// # Panics
+    /// Panics if `observations_limit` is zero or larger than the current limit.
+    pub fn decrease_capacity(&mut self, observations_limit: u16) {
+        assert!(
+            observations_limit >= 1 && observations_limit <= self.observations_limit,
+            "The observations limit can only be decreased to at least 1, from {} to {} requested.",
+            self.observations_limit,
+            observations_limit
+        );
+
+        let retained = min(self.observations_stored, observations_limit);
+        if let Some(oldest_index) = self.oldest_index() {
+            let observations: Vec<AccumulatedObservation> = (self.observations_stored - retained
+                ..self.observations_stored)
+                .map(|offset| {
+                    let index = self.index_at_offset(oldest_index, offset);
+                    self.observations.get(&index).unwrap().clone()
+                })
+                .collect();
+            for (index, observation) in observations.into_iter().enumerate() {
+                self.observations.insert(index as u16, observation);
+            }
+            self.last_observation_index = Some(retained - 1);
+        }
+        for index in retained..self.observations_limit {
+            self.observations.remove(&index);
+        }
+
+        self.observations_stored = retained;
+        self.observations_limit = observations_limit;
+        self.twap_cache = None;
+        self.interval_cache.clear();
+    }
+
+    /// Sets the number of observations that can be stored to the number currently stored, e.g. to
+    /// lock the ring at its size after a period of growth and start a stable window.
+    ///
+    /// Like `decrease_capacity`, the stored observations are moved to the indices
+    /// `0..observations_stored` in chronological order, so that the ring is exactly full and
+    /// ordered. From then on, every new observation evicts the oldest one at this fixed size.
+    ///
+    /// # Panics
+    /// Panics if less than 2 observations are stored.
+    pub fn freeze_capacity_at_current(&mut self) {
+        assert!(
+            self.observations_stored >= 2,
+            "The capacity can only be frozen with at least 2 observations stored, {} stored.",
+            self.observations_stored
+        );
+        self.decrease_capacity(self.observations_stored);
+    }
+
+    /// Subtracts the `price_sqrt_log_acc` of the oldest stored observation from the
+    /// `price_sqrt_log_acc` of every stored observation, so that the accumulator of the oldest
+    /// observation becomes zero and the magnitude of the accumulators stays small.
+    ///
+    /// The subtracted value is added to `price_sqrt_log_acc_offset`, so that the accumulators
+    /// without any rebase can still be recovered by adding the offset. All queries only depend on
+    /// the difference of two accumulators, which is not affected by a rebase, so the offset does
+    /// not have to be added back for them. Observations returned before a rebase must however not
+    /// be combined with observations returned after it.
+    ///
+    /// Does nothing if no observations have been stored yet.
+    pub fn rebase(&mut self) {
+        let oldest_index = match self.oldest_index() {
+            None => return,
+            Some(oldest_index) => oldest_index,
+        };
+        let offset = self
+            .observations
+            .get(&oldest_index)
+            .unwrap()
+            .price_sqrt_log_acc;
+
+        for position in 0..self.observations_stored {
+            let index = self.index_at_offset(oldest_index, position);
+            let mut observation = self.observations.get(&index).unwrap().clone();
+            observation.price_sqrt_log_acc = observation.price_sqrt_log_acc - offset;
+            self.observations.insert(index, observation);
+        }
+
+        self.price_sqrt_log_acc_offset = self.price_sqrt_log_acc_offset + offset;
+    }
+
+    /// Returns the total value subtracted from the `price_sqrt_log_acc` of the stored
+    /// observations by `rebase`.
+    ///
+    /// # Returns
+    ///
+    /// A `Decimal` which is zero if the oracle has never been rebased.
+    pub fn price_sqrt_log_acc_offset(&self) -> Decimal {
+        self.price_sqrt_log_acc_offset
+    }
+
+    /// Returns the number of observations currently stored.
+    ///
+    /// # Returns
     ///
     /// A `u16` value representing the number of observations currently stored in the oracle.
     pub fn observations_stored(&self) -> u16 {
         self.observations_stored
     }
 
-    /// Returns the timestamp of the oldest observation in minutes, if any.
+    /// Returns the timestamp of the oldest observation in minutes, if any.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<u64>` containing the timestamp of the oldest observation in minutes,
+    /// or `None` if there are no observations.
+    fn oldest_observation_at_minutes(&self) -> Option<u64> {
+        self.oldest_index()
+            .and_then(|index| self.observations.get(&index))
+            .map(|obs| obs.timestamp)
+    }
+
+    /// Returns the timestamp of the oldest observation in seconds, if any.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<u64>` containing the timestamp of the oldest observation in seconds,
+    /// or `None` if there are no observations.
+    pub fn oldest_observation_at(&self) -> Option<u64> {
+        self.oldest_observation_at_minutes()
+            .map(|timestamp| timestamp * 60)
+    }
+
+    /// Same as `oldest_observation_at`, but returns the timestamp as `UnixSeconds`.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<UnixSeconds>` containing the timestamp of the oldest observation,
+    /// or `None` if there are no observations.
+    pub fn oldest_observation_timestamp(&self) -> Option<UnixSeconds> {
+        self.oldest_observation_at_minutes()
+            .map(|timestamp| UnixMinutes(timestamp).to_seconds())
+    }
+
+    /// Returns the timestamp of the last stored observation in seconds, if any.
+    ///
+    /// Data up to this timestamp is finalized. Later timestamps are extrapolated from the minute in
+    /// progress, which may still change until the next observation is stored.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<u64>` containing the timestamp of the last observation in seconds,
+    /// or `None` if there are no observations.
+    pub fn last_finalized_timestamp(&self) -> Option<u64> {
+        self.last_observation_index
+            .map(|index| self.observations.get(&index).unwrap().timestamp * 60)
+    }
+
+    /// Returns how far a timestamp is past the last stored observation, i.e. for how long
+    /// `observation` assumes the last price held flat when extrapolating to it. Consumers can
+    /// discount their trust in an extrapolated observation by this age.
+    ///
+    /// Like `observation`, the timestamp is rounded down to the minute, so the age is a multiple
+    /// of 60 and directly comparable to `max_extrapolation_seconds`. The timestamp is not checked
+    /// against the available range.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds`: The timestamp in seconds, automatically rounded to the minute.
+    ///
+    /// # Returns
+    ///
+    /// The age in seconds, which is zero for timestamps served by a stored observation or by an
+    /// interpolation between two of them.
+    ///
+    /// # Panics
+    /// Panics if no observations exist yet.
+    pub fn extrapolation_age_seconds(&self, seconds: u64) -> u64 {
+        let last_seconds = self
+            .last_finalized_timestamp()
+            .expect("No observations exist yet.");
+        (seconds / 60 * 60).saturating_sub(last_seconds)
+    }
+
+    /// Returns the timestamps of all stored observations, e.g. for indexers tracking which
+    /// minutes have been observed without reading the accumulators.
+    ///
+    /// # Returns
+    ///
+    /// A vector of timestamps in seconds in chronological order, which is empty if there are no
+    /// observations.
+    pub fn observation_timestamps(&self) -> Vec<u64> {
+        let oldest_index = match self.oldest_index() {
+            Some(oldest_index) => oldest_index,
+            None => return vec![],
+        };
+        (0..self.observations_stored)
+            .map(|offset| {
+                let index = self.index_at_offset(oldest_index, offset);
+                self.observations.get(&index).unwrap().timestamp * 60
+            })
+            .collect()
+    }
+
+    /// Returns the raw state of the minute in progress, for debugging the sub-minute averaging
+    /// without waiting for the minute to be finalized.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing the tuple `(price_sqrt_sum, price_sqrt_last, last_updated_seconds,
+    /// is_initializing)`, or `None` before the first observed price. The `price_sqrt_sum` is the
+    /// time-weighted sum of the prices up to `last_updated_seconds`, and `is_initializing` is
+    /// true until the first minute has been finalized.
+    pub fn sub_observation_state(&self) -> Option<(PreciseDecimal, PreciseDecimal, u64, bool)> {
+        self.sub_observations.as_ref().map(|sub_observations| {
+            (
+                sub_observations.price_sqrt_sum,
+                sub_observations.price_sqrt_last,
+                sub_observations.last_updated.seconds_since_unix_epoch as u64,
+                sub_observations.initialization.is_some(),
+            )
+        })
+    }
+
+    /// Returns the range of timestamps accepted by `observation` without panicking.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<(u64, u64)>` containing the timestamp of the oldest observation and the current
+    /// time (rounded down to the minute), both in seconds, or `None` if there are no observations.
+    pub fn observable_range_seconds(&self) -> Option<(u64, u64)> {
+        self.oldest_observation_at()
+            .map(|oldest_seconds| (oldest_seconds, Clock::time_in_minutes() * 60))
+    }
+
+    /// Returns the gap to the previous observation for every stored observation, e.g. to score
+    /// the trust in an average by how much of it was extrapolated over minutes without swaps.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(timestamp, gap_minutes)` tuples in chronological order, with the timestamp in
+    /// seconds and the number of minutes since the previous stored observation. The gap of the
+    /// oldest observation is zero.
+    pub fn observation_gaps(&self) -> Vec<(u64, u64)> {
+        let oldest_index = match self.oldest_index() {
+            Some(index) => index,
+            None => return vec![],
+        };
+        let timestamps: Vec<u64> = (0..self.observations_stored)
+            .map(|offset| {
+                let index = self.index_at_offset(oldest_index, offset);
+                self.observations.get(&index).unwrap().timestamp
+            })
+            .collect();
+
+        timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, &timestamp)| {
+                let gap_minutes = if i == 0 {
+                    0
+                } else {
+                    timestamp - timestamps[i - 1]
+                };
+                (timestamp * 60, gap_minutes)
+            })
+            .collect()
+    }
+
+    /// Returns the instant of the last update of the current minute, i.e. of the last `observe`
+    /// call or of the beginning of the minute when it was finalized.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<u64>` containing the timestamp in seconds, or `None` if the oracle has never been
+    /// updated.
+    pub fn last_update_at(&self) -> Option<u64> {
+        self.sub_observations
+            .as_ref()
+            .map(|sub_observations| sub_observations.last_updated.seconds_since_unix_epoch as u64)
+    }
+
+    /// Bundles the status of the oracle, saving integrators from calling each getter separately.
+    pub fn metadata(&self) -> OracleMetadata {
+        OracleMetadata {
+            observations_limit: self.observations_limit,
+            observations_stored: self.observations_stored,
+            last_observation_index: self.last_observation_index,
+            oldest_observation_at: self.oldest_observation_at(),
+            last_update_timestamp: self.last_update_at(),
+        }
+    }
+
+    /// Returns the maximum number of seconds an observation can be extrapolated past the last
+    /// stored one, or `None` if extrapolation is unlimited.
+    pub fn max_extrapolation_seconds(&self) -> Option<u64> {
+        self.max_extrapolation_seconds
+    }
+
+    /// Bounds (or unbounds with `None`) the extrapolation past the last stored observation.
+    ///
+    /// Without swaps, the price of the last active minute is carried forward indefinitely. With
+    /// a bound, `observation` (and the queries based on it) panics for timestamps more than
+    /// `max_extrapolation_seconds` after the last observation, instead of returning a stale price.
+    /// The try_* variants return `OracleError::ExtrapolationTooStale` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_extrapolation_seconds` - The maximum extrapolation in seconds, or `None` for
+    /// unlimited extrapolation (the default).
+    pub fn set_max_extrapolation_seconds(&mut self, max_extrapolation_seconds: Option<u64>) {
+        self.max_extrapolation_seconds = max_extrapolation_seconds;
+    }
+
+    /// Returns the maximum number of minutes between the two stored observations an observation
+    /// is interpolated between, or `None` if interpolation is unlimited.
+    pub fn max_interpolation_gap_minutes(&self) -> Option<u64> {
+        self.max_interpolation_gap_minutes
+    }
+
+    /// Bounds (or unbounds with `None`) the interpolation between two distant observations.
+    ///
+    /// Between two stored observations the accumulators are interpolated linearly, i.e. the
+    /// price is assumed to be constant, which can differ materially from the true path if the
+    /// observations are far apart (e.g. after deferring observations with `min_spacing_minutes`).
+    /// With a bound, `observation` (and the queries based on it) panics for timestamps between
+    /// two observations more than `max_interpolation_gap_minutes` apart. Exact matches are not
+    /// affected. The try_* variants return `OracleError::InterpolationGapTooWide` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_interpolation_gap_minutes` - The maximum distance of the neighbors in minutes, or
+    /// `None` for unlimited interpolation (the default).
+    pub fn set_max_interpolation_gap_minutes(
+        &mut self,
+        max_interpolation_gap_minutes: Option<u64>,
+    ) {
+        self.max_interpolation_gap_minutes = max_interpolation_gap_minutes;
+        // Cached intervals may no longer be allowed
+        self.interval_cache.clear();
+    }
+
+    /// Returns the maximum number of minutes an interval may span, or `None` if intervals are
+    /// unlimited.
+    pub fn max_interval_minutes(&self) -> Option<u64> {
+        self.max_interval_minutes
+    }
+
+    /// Bounds (or unbounds with `None`) the length of the intervals accepted by
+    /// `observation_intervals` and the other interval queries.
+    ///
+    /// Any interval within the stored history is accepted by default, including windows far
+    /// longer than the observations can meaningfully represent, e.g. mostly interpolated between
+    /// sparse observations. With a bound, intervals spanning more than `max_interval_minutes`
+    /// (after rounding both bounds to the minute) are rejected, so that consumers do not
+    /// unknowingly request an average the data cannot support. The try_* variants return
+    /// `OracleError::IntervalTooLong` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_interval_minutes` - The maximum length of an interval in minutes, or `None` for
+    /// unlimited intervals (the default).
+    ///
+    /// # Panics
+    /// Panics if `max_interval_minutes` is zero, which would reject every interval.
+    pub fn set_max_interval_minutes(&mut self, max_interval_minutes: Option<u64>) {
+        assert!(
+            max_interval_minutes != Some(0),
+            "The maximum interval length must be at least one minute."
+        );
+        self.max_interval_minutes = max_interval_minutes;
+        // Cached intervals may no longer be allowed
+        self.interval_cache.clear();
+    }
+
+    /// Returns the maximum factor by which an observed `price_sqrt` may deviate from the average
+    /// of the last finalized minute, or `None` if prices are not bounded.
+    pub fn price_sqrt_band(&self) -> Option<PreciseDecimal> {
+        self.price_sqrt_band
+    }
+
+    /// Bounds (or unbounds with `None`) the observed `price_sqrt` relative to the average of the
+    /// last finalized minute.
+    ///
+    /// With a band of e.g. 2, every observed `price_sqrt` is clamped to
+    /// `[average / 2, average * 2]` before being time-weighted. This limits the impact of a single
+    /// manipulative swap pushing the price to an absurd value right before a minute boundary. It
+    /// is only a mitigation: a price moved within the band, or moved gradually over several
+    /// minutes, is still recorded. Legitimate price moves beyond the band are followed with a
+    /// delay of one minute per factor of `band`.
+    ///
+    /// # Arguments
+    ///
+    /// * `price_sqrt_band` - The maximum deviation factor, or `None` for unbounded prices
+    /// (the default).
+    ///
+    /// # Panics
+    /// Panics if `price_sqrt_band` is smaller than one.
+    pub fn set_price_sqrt_band(&mut self, price_sqrt_band: Option<PreciseDecimal>) {
+        if let Some(band) = price_sqrt_band {
+            assert!(
+                band >= pdec!(1),
+                "The price_sqrt band must be at least 1, {} provided.",
+                band
+            );
+        }
+        self.price_sqrt_band = price_sqrt_band;
+    }
+
+    /// Returns the minimum `price_sqrt` recorded, or `None` if prices are not floored.
+    pub fn price_sqrt_floor(&self) -> Option<PreciseDecimal> {
+        self.price_sqrt_floor
+    }
+
+    /// Floors (or unfloors with `None`) the observed `price_sqrt`.
+    ///
+    /// For a pool at deep out-of-range ticks, `price_sqrt` can be tiny, so that its logarithm is
+    /// a large negative number, which is truncated further towards negative infinity and brings
+    /// the accumulators closer to saturation. With a floor, every observed `price_sqrt` below it
+    /// is recorded as the floor instead, after applying the `price_sqrt_band`. The tradeoff is
+    /// that the oracle reports the floor for extreme prices below it, so it should be set well
+    /// below any price the pool is expected to trade at.
+    ///
+    /// # Arguments
+    ///
+    /// * `price_sqrt_floor` - The minimum `price_sqrt`, or `None` for unfloored prices
+    /// (the default).
+    ///
+    /// # Panics
+    /// Panics if `price_sqrt_floor` is not positive.
+    pub fn set_price_sqrt_floor(&mut self, price_sqrt_floor: Option<PreciseDecimal>) {
+        if let Some(floor) = price_sqrt_floor {
+            assert!(
+                floor > pdec!(0),
+                "The price_sqrt floor must be positive, {} provided.",
+                floor
+            );
+        }
+        self.price_sqrt_floor = price_sqrt_floor;
+    }
+
+    /// Returns the minimum number of minutes between two stored observations, or `None` if every
+    /// active minute is stored.
+    pub fn min_spacing_minutes(&self) -> Option<u64> {
+        self.min_spacing_minutes
+    }
+
+    /// Sets (or unsets with `None`) the minimum number of minutes between two stored observations.
+    ///
+    /// High-frequency pools store an observation in nearly every minute, cycling through the
+    /// observations limit quickly. With a minimum spacing, the last stored observation is moved
+    /// forward to every new active minute until it is `min_spacing_minutes` after the observation
+    /// before it, and only then a new one is appended. The accumulators stay exact at every
+    /// stored observation, so the history covers up to `min_spacing_minutes` times more wall-clock
+    /// time with the same storage.
+    ///
+    /// The tradeoff is precision: between two stored observations the accumulators are
+    /// interpolated linearly, i.e. averages over intervals not aligned to stored observations
+    /// assume a constant price in between. Likewise, the volume and swap count between two
+    /// stored observations are attributed to the minute of the left one.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_spacing_minutes` - The minimum spacing in minutes, or `None` to store every active
+    /// minute (the default).
+    pub fn set_min_spacing_minutes(&mut self, min_spacing_minutes: Option<u64>) {
+        self.min_spacing_minutes = min_spacing_minutes;
+    }
+
+    /// Returns the minimum number of minutes a full ring keeps covering, or `None` if observations
+    /// are stored regardless of the retained history.
+    pub fn min_retained_minutes(&self) -> Option<u64> {
+        self.min_retained_minutes
+    }
+
+    /// Sets (or unsets with `None`) the minimum number of minutes a full ring has to keep
+    /// covering, capping the rate at which observations are stored to `observations_limit`
+    /// observations per `min_retained_minutes`.
+    ///
+    /// Without a cap, an actor swapping in every minute cycles the ring every `observations_limit`
+    /// minutes, evicting older history. With a cap, a new observation which would evict an
+    /// observation needed to cover `min_retained_minutes` is deferred: like with
+    /// `min_spacing_minutes`, the last stored observation is moved forward to the new minute
+    /// instead of appending one, so its accumulators stay exact and no price is dropped. Storing
+    /// in a new slot resumes once the retained history is long enough again.
+    ///
+    /// Freshness is not affected, since the last observation always reflects the last active
+    /// minute. The tradeoff is the resolution of recent history: while observations are deferred
+    /// the accumulators between the last two stored observations are interpolated linearly.
+    ///
+    /// # Arguments
+    ///
+    /// * `min_retained_minutes` - The minimum number of minutes to retain, or `None` to store
+    /// every observation regardless of the retained history (the default).
+    pub fn set_min_retained_minutes(&mut self, min_retained_minutes: Option<u64>) {
+        self.min_retained_minutes = min_retained_minutes;
+    }
+
+    /// Returns the maximum number of minutes between two stored observations before the gap is
+    /// filled with synthetic observations, or `None` if gaps are not filled.
+    pub fn max_observation_gap_minutes(&self) -> Option<u64> {
+        self.max_observation_gap_minutes
+    }
+
+    /// Sets (or unsets with `None`) the maximum number of minutes between two stored observations
+    /// before the gap is filled with synthetic observations.
     ///
-    /// # Returns
+    /// Without swaps, no observation is stored and the accumulators between the last active
+    /// minute and the next update are interpolated linearly, which mixes the average of the last
+    /// active minute into the whole gap. With a maximum gap, the next update after a longer gap
+    /// first stores a synthetic observation every `max_observation_gap_minutes` after the last
+    /// active minute. Synthetic observations are flat: they carry the last price (liquidity and
+    /// fee rate) before the gap, which is what the oracle assumes for idle minutes anyway, and add
+    /// no volume or swaps.
     ///
-    /// An `Option<u64>` containing the timestamp of the oldest observation in minutes,
-    /// or `None` if there are no observations.
-    fn oldest_observation_at_minutes(&self) -> Option<u64> {
-        self.oldest_index()
-            .and_then(|index| self.observations.get(&index))
-            .map(|obs| obs.timestamp)
+    /// The synthetic observations take slots of the ring like observed ones. At most
+    /// `observations_limit - 1` are stored per update, since older ones would be evicted by the
+    /// same update. They do not add observed minutes (`observed_minutes_acc`), so the idle minutes
+    /// still lower the score of `observation_intervals_with_confidence`.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_observation_gap_minutes` - The maximum gap in minutes, or `None` to not fill gaps
+    /// (the default).
+    ///
+    /// # Panics
+    /// Panics if `max_observation_gap_minutes` is zero.
+    pub fn set_max_observation_gap_minutes(&mut self, max_observation_gap_minutes: Option<u64>) {
+        assert!(
+            max_observation_gap_minutes != Some(0),
+            "The maximum observation gap must be at least one minute."
+        );
+        self.max_observation_gap_minutes = max_observation_gap_minutes;
     }
 
-    /// Returns the timestamp of the oldest observation in seconds, if any.
+    /// Returns whether `observe` emits a `SpotPriceEvent` on every call.
+    pub fn emit_spot_events(&self) -> bool {
+        self.emit_spot_events
+    }
+
+    /// Enables or disables emitting a `SpotPriceEvent` with the recorded `price_sqrt` on every
+    /// `observe` call, for consumers which need the spot price of every swap instead of the
+    /// averages per minute.
     ///
-    /// # Returns
+    /// Emitting events costs fees on every swap, so it is disabled by default. The blueprint
+    /// embedding the oracle has to register the event via `#[events(SpotPriceEvent)]`.
     ///
-    /// An `Option<u64>` containing the timestamp of the oldest observation in seconds,
-    /// or `None` if there are no observations.
-    pub fn oldest_observation_at(&self) -> Option<u64> {
-        self.oldest_observation_at_minutes()
-            .map(|timestamp| timestamp * 60)
+    /// # Arguments
+    ///
+    /// * `emit_spot_events` - Whether to emit the events.
+    pub fn set_emit_spot_events(&mut self, emit_spot_events: bool) {
+        self.emit_spot_events = emit_spot_events;
+    }
+
+    /// Returns the addresses of the tokens x and y of the observed pool, if set.
+    ///
+    /// All prices are quoted as y per x, so a consumer serving several pairs can verify which pair
+    /// and direction an `ObservationInterval` refers to.
+    pub fn token_pair(&self) -> Option<(ResourceAddress, ResourceAddress)> {
+        self.token_pair
+    }
+
+    /// Records the addresses of the tokens x and y of the observed pool, e.g. when the hook
+    /// embedding the oracle is informed about the instantiation of its pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `x_address` - The address of the token x.
+    /// * `y_address` - The address of the token y, i.e. the token the price is denominated in.
+    pub fn set_token_pair(&mut self, x_address: ResourceAddress, y_address: ResourceAddress) {
+        self.token_pair = Some((x_address, y_address));
     }
 
     /// Returns the index of the last observation for testing purposes.
@@ -341,6 +2789,355 @@ impl Oracle {
         self.last_observation_index
     }
 
+    /// Asserts the consistency of the ring buffer for testing purposes.
+    ///
+    /// The following invariants are checked:
+    /// - `observations_stored` does not exceed `observations_limit`.
+    /// - The last (and thereby the oldest) index lies within the `observations_stored` slots, and
+    /// is only `None` if no observations are stored.
+    /// - The timestamps are strictly increasing from the oldest to the last observation.
+    /// - The slots beyond `observations_stored` are either empty or hold the placeholders written
+    /// by `reserve_capacity`.
+    ///
+    /// # Panics
+    /// Panics if any invariant is violated. This reads every slot up to the limit and is thereby
+    /// only available with the `test-utils` feature.
+    #[cfg(feature = "test-utils")]
+    pub fn assert_invariants(&self) {
+        assert!(
+            self.observations_stored <= self.observations_limit,
+            "{} observations stored, exceeding the limit of {}.",
+            self.observations_stored,
+            self.observations_limit
+        );
+
+        let last_index = match self.last_observation_index {
+            Some(last_index) => last_index,
+            None => {
+                assert_eq!(
+                    self.observations_stored, 0,
+                    "Observations are stored without a last index."
+                );
+                return;
+            }
+        };
+        assert!(
+            last_index < self.observations_stored,
+            "The last index {} is beyond the {} observations stored.",
+            last_index,
+            self.observations_stored
+        );
+
+        let oldest_index = self.oldest_index().unwrap();
+        let timestamps: Vec<u64> = (0..self.observations_stored)
+            .map(|offset| {
+                let index = self.index_at_offset(oldest_index, offset);
+                self.observations
+                    .get(&index)
+                    .unwrap_or_else(|| panic!("The stored slot {} is empty.", index))
+                    .timestamp
+            })
+            .collect();
+        assert_eq!(
+            self.index_at_offset(oldest_index, self.observations_stored - 1),
+            last_index,
+            "The oldest and last index do not bracket the observations stored."
+        );
+        assert!(
+            timestamps.windows(2).all(|pair| pair[0] < pair[1]),
+            "The timestamps are not strictly increasing: {:?}.",
+            timestamps
+        );
+
+        for index in self.observations_stored..self.observations_limit {
+            assert!(
+                self.observations
+                    .get(&index)
+                    .map_or(true, |observation| *observation
+                        == AccumulatedObservation::empty()),
+                "The slot {} beyond the observations stored is not empty.",
+                index
+            );
+        }
+    }
+
+    /// Checks that every slot counted by `observations_stored` holds an observation and that
+    /// their timestamps strictly increase from the oldest to the last one. Unlike
+    /// `assert_invariants` it only reads the stored slots and does not panic, so it can be used
+    /// on-chain after a migration via `increase_capacity` or `Oracle::restore`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the stored observations are consistent, `false` if a slot is missing or the
+    /// timestamps are not strictly increasing.
+    pub fn verify_storage(&self) -> bool {
+        let oldest_index = match self.oldest_index() {
+            Some(oldest_index) => oldest_index,
+            None => return true,
+        };
+
+        let mut previous_timestamp: Option<u64> = None;
+        for offset in 0..self.observations_stored {
+            let index = self.index_at_offset(oldest_index, offset);
+            let timestamp = match self.observations.get(&index) {
+                Some(observation) => observation.timestamp,
+                None => return false,
+            };
+            if previous_timestamp.is_some_and(|previous| previous >= timestamp) {
+                return false;
+            }
+            previous_timestamp = Some(timestamp);
+        }
+
+        true
+    }
+
+    /// Removes the observation stored at a raw ring buffer index without updating the
+    /// bookkeeping, for testing purposes. This corrupts the oracle state and is thereby only
+    /// available with the `test-utils` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The ring buffer index to clear.
+    #[cfg(feature = "test-utils")]
+    pub fn clear_observation_at_index(&mut self, index: u16) {
+        self.observations.remove(&index);
+    }
+
+    /// Clears the state of the current minute (`sub_observations`) while keeping the stored
+    /// observations, for testing purposes. This loses the price of the current minute and is
+    /// thereby only available with the `test-utils` feature.
+    #[cfg(feature = "test-utils")]
+    pub fn clear_sub_observations(&mut self) {
+        self.sub_observations = None;
+    }
+
+    /// Appends pre-computed observations to the ring buffer without observing any price, for
+    /// testing purposes, e.g. to set up a long history in a few transactions. The bookkeeping is
+    /// updated like for observed prices, but the accumulators are stored as given and the state of
+    /// the current minute (`sub_observations`) is left untouched. Since this bypasses observing,
+    /// it is only available with the `test-utils` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `observations` - The observations in chronological order, with the timestamps in Unix
+    /// seconds (rounded to the minute) like returned by `observation`.
+    ///
+    /// # Panics
+    /// Panics if the timestamps are not strictly increasing minutes after the last observation.
+    #[cfg(feature = "test-utils")]
+    pub fn seed_observations(&mut self, observations: Vec<AccumulatedObservation>) {
+        for mut observation in observations {
+            observation.timestamp /= 60;
+            self.assert_after_last_observation(observation.timestamp);
+            self.append_observation(observation);
+        }
+    }
+
+    /// Returns the observation stored `n` slots before the last one, e.g. for a moving average
+    /// over a fixed number of observations regardless of their timestamps. Like `observation`,
+    /// the timestamp is returned in Unix seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of observations to go back, where zero refers to the last observation.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<AccumulatedObservation>` containing the observation,
+    /// or `None` if `n` is not smaller than `observations_stored`.
+    pub fn observation_back(&self, n: u16) -> Option<AccumulatedObservation> {
+        if n >= self.observations_stored {
+            return None;
+        }
+        let index = self.index_at_offset(
+            self.last_observation_index.unwrap(),
+            self.observations_stored - n,
+        );
+        let mut observation = self.observations.get(&index).unwrap().clone();
+        observation.timestamp *= 60;
+        Some(observation)
+    }
+
+    /// Returns the observation stored for exactly the given minute, without any interpolation or
+    /// extrapolation, e.g. for consumers which need the exact accumulators at a minute boundary.
+    /// Like `observation`, the timestamp is given and returned in Unix seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_seconds` - The start of the sought minute in Unix seconds, rounded down to the
+    /// minute.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<AccumulatedObservation>` containing the observation,
+    /// or `None` if no observation is stored for the minute of `start_seconds`.
+    pub fn exact_observation(&self, start_seconds: u64) -> Option<AccumulatedObservation> {
+        let minute_timestamp = start_seconds / 60;
+        let offset = self.observations_before(minute_timestamp);
+        if offset >= self.observations_stored {
+            return None;
+        }
+        let index = self.index_at_offset(self.oldest_index().unwrap(), offset);
+        let mut observation = self.observations.get(&index).unwrap().clone();
+        if observation.timestamp != minute_timestamp {
+            return None;
+        }
+        observation.timestamp *= 60;
+        Some(observation)
+    }
+
+    /// Returns the number of stored observations with a timestamp within `[start, end]`, e.g. for
+    /// density checks which do not need the observations themselves. The count is found by two
+    /// binary searches, so it is cheaper than reading the observations.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_seconds` - The inclusive start of the window in Unix seconds.
+    /// * `end_seconds` - The inclusive end of the window in Unix seconds.
+    ///
+    /// # Returns
+    ///
+    /// The number of stored observations in the window, zero if `start_seconds` is after
+    /// `end_seconds`.
+    pub fn observation_count_in_range(&self, start_seconds: u64, end_seconds: u64) -> u16 {
+        // Observations are stored at the start of a minute, so only minutes starting within the
+        // window count
+        let first_minute = start_seconds.div_ceil(60);
+        let after_last_minute = (end_seconds / 60).saturating_add(1);
+        self.observations_before(after_last_minute)
+            .saturating_sub(self.observations_before(first_minute))
+    }
+
+    /// Returns the observation stored at a raw ring buffer index, e.g. for diagnostics or for
+    /// indexers enumerating the ring. The timestamp is returned as stored, in Unix minutes.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The ring buffer index, starting at zero.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<AccumulatedObservation>` containing the stored observation,
+    /// or `None` if no observation is stored at the index.
+    pub fn observation_at_index(&self, index: u16) -> Option<AccumulatedObservation> {
+        if index >= self.observations_stored {
+            return None;
+        }
+        self.observations
+            .get(&index)
+            .map(|observation| observation.clone())
+    }
+
+    /// Returns the per-minute average logarithmic value of the price square root between two
+    /// consecutive stored observations, i.e. the slope of `price_sqrt_log_acc` between them.
+    /// This allows consumers to reconstruct the log price minute by minute, e.g. for medians or
+    /// EMAs computed off-chain. Doubling the result yields the average log price.
+    ///
+    /// # Arguments
+    ///
+    /// * `index_left` - The ring buffer index of the older observation.
+    /// * `index_right` - The ring buffer index of the following observation.
+    ///
+    /// # Returns
+    ///
+    /// The `arithmetic_mean` of the logarithmic accumulators of both observations.
+    ///
+    /// # Panics
+    /// Panics if either index holds no observation, or if `index_right` does not directly follow
+    /// `index_left` in chronological order.
+    pub fn spot_log_price_between(&self, index_left: u16, index_right: u16) -> Decimal {
+        let o_l = self.observation_at_index(index_left);
+        let o_r = self.observation_at_index(index_right);
+        assert!(
+            o_l.is_some() && o_r.is_some(),
+            "No observation is stored at index {} or {}.",
+            index_left,
+            index_right
+        );
+        assert!(
+            Some(index_left) != self.last_observation_index
+                && self.index_at_offset(index_left, 1) == index_right,
+            "The observation at index {} does not directly follow the one at index {}.",
+            index_right,
+            index_left
+        );
+        let (o_l, o_r) = (o_l.unwrap(), o_r.unwrap());
+
+        arithmetic_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_sqrt_log_acc,
+            o_r.price_sqrt_log_acc,
+        )
+    }
+
+    /// Calculates the geometric mean of the price square root strictly between two stored
+    /// observations, given by their offsets from the oldest observation. Unlike
+    /// `observation_intervals`, the bounds are the exact stored timestamps, so no rounding or
+    /// interpolation is involved, e.g. for audits reproducing the average from raw observations.
+    ///
+    /// # Arguments
+    ///
+    /// * `oldest_offset` - The offset of the older observation from the oldest observation.
+    /// * `newest_offset` - The offset of the newer observation from the oldest observation.
+    ///
+    /// # Returns
+    ///
+    /// An `ObservationInterval` spanning the timestamps of both observations in Unix seconds.
+    ///
+    /// # Panics
+    /// Panics if `oldest_offset` is not smaller than `newest_offset`, if `newest_offset` is not
+    /// smaller than `observations_stored`, or if the average price does not fit into a `Decimal`.
+    pub fn twap_between_indices(
+        &self,
+        oldest_offset: u16,
+        newest_offset: u16,
+    ) -> ObservationInterval {
+        assert!(
+            oldest_offset < newest_offset,
+            "The oldest offset {} must be smaller than the newest offset {}.",
+            oldest_offset,
+            newest_offset
+        );
+        assert!(
+            newest_offset < self.observations_stored,
+            "The newest offset {} is out of range of the {} stored observations.",
+            newest_offset,
+            self.observations_stored
+        );
+        let oldest_index = self.oldest_index().unwrap();
+        let o_l = self
+            .observations
+            .get(&self.index_at_offset(oldest_index, oldest_offset))
+            .unwrap()
+            .clone();
+        let o_r = self
+            .observations
+            .get(&self.index_at_offset(oldest_index, newest_offset))
+            .unwrap()
+            .clone();
+        let (start_seconds, end_seconds) = (o_l.timestamp * 60, o_r.timestamp * 60);
+
+        let price_sqrt = checked_geometric_mean(
+            o_l.timestamp,
+            o_r.timestamp,
+            o_l.price_sqrt_log_acc,
+            o_r.price_sqrt_log_acc,
+        )
+        .unwrap_or_else(|| panic_price_overflow(start_seconds, end_seconds));
+        let price = checked_price(price_sqrt)
+            .unwrap_or_else(|| panic_price_overflow(start_seconds, end_seconds));
+
+        ObservationInterval {
+            start: start_seconds,
+            end: end_seconds,
+            duration_minutes: o_r.timestamp - o_l.timestamp,
+            price_sqrt,
+            price,
+        }
+    }
+
     /// Returns the index of the oldest observation.
     ///
     /// # Returns
@@ -351,19 +3148,432 @@ impl Oracle {
         self.last_observation_index
             .map(|index| (index + 1) % self.observations_stored)
     }
+
+    /// Returns the number of stored observations with a timestamp before `target_minutes`, found
+    /// by a binary search over the stored observations in chronological order.
+    fn observations_before(&self, target_minutes: u64) -> u16 {
+        let oldest_index = match self.oldest_index() {
+            Some(index) => index,
+            None => return 0,
+        };
+
+        let (mut low, mut high) = (0, self.observations_stored);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let index = self.index_at_offset(oldest_index, mid);
+            if self.observations.get(&index).unwrap().timestamp < target_minutes {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        low
+    }
+
+    /// Returns the ring buffer index located `offset` slots after `index`, wrapping around the
+    /// stored observations. The sum is performed in `u32` to avoid overflowing for large rings.
+    fn index_at_offset(&self, index: u16, offset: u16) -> u16 {
+        ((index as u32 + offset as u32) % self.observations_stored as u32) as u16
+    }
+
+    /// Returns a serializable copy of the complete oracle state.
+    ///
+    /// This is meant to migrate the accumulated history to a new component, e.g. when upgrading
+    /// the hook blueprint. The snapshot can be turned back into an oracle via `Oracle::restore`.
+    ///
+    /// # Returns
+    ///
+    /// An `OracleSnapshot` containing all stored observations in chronological order, together
+    /// with the ring buffer bookkeeping and the state of the current minute.
+    pub fn snapshot(&self) -> OracleSnapshot {
+        let observations = match self.oldest_index() {
+            None => vec![],
+            Some(oldest_index) => (0..self.observations_stored)
+                .map(|offset| {
+                    let index = self.index_at_offset(oldest_index, offset);
+                    self.observations.get(&index).unwrap().clone()
+                })
+                .collect(),
+        };
+
+        OracleSnapshot {
+            observations,
+            observations_limit: self.observations_limit,
+            last_observation_index: self.last_observation_index,
+            observations_stored: self.observations_stored,
+            sub_observations: self.sub_observations.clone(),
+            max_extrapolation_seconds: self.max_extrapolation_seconds,
+            max_interpolation_gap_minutes: self.max_interpolation_gap_minutes,
+            max_interval_minutes: self.max_interval_minutes,
+            price_sqrt_band: self.price_sqrt_band,
+            price_sqrt_floor: self.price_sqrt_floor,
+            min_spacing_minutes: self.min_spacing_minutes,
+            min_retained_minutes: self.min_retained_minutes,
+            max_observation_gap_minutes: self.max_observation_gap_minutes,
+            emit_spot_events: self.emit_spot_events,
+            token_pair: self.token_pair,
+            price_sqrt_log_acc_offset: self.price_sqrt_log_acc_offset,
+        }
+    }
+
+    /// Creates a new oracle from a snapshot previously taken with `Oracle::snapshot`.
+    ///
+    /// The observations are stored at the same ring buffer indexes they had in the original
+    /// oracle, so that `last_observation_index` stays valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The `OracleSnapshot` to restore the oracle from.
+    ///
+    /// # Panics
+    /// Panics if the snapshot is inconsistent, i.e. the number of observations does not match
+    /// `observations_stored`, the bookkeeping exceeds `observations_limit`, the last index lies
+    /// beyond the observations stored or the timestamps are not strictly increasing.
+    pub fn restore(snapshot: OracleSnapshot) -> Self {
+        assert_eq!(
+            snapshot.observations.len(),
+            snapshot.observations_stored as usize,
+            "The number of observations in the snapshot does not match `observations_stored`."
+        );
+        assert!(
+            snapshot.observations_stored <= snapshot.observations_limit,
+            "The snapshot stores more observations than its `observations_limit`."
+        );
+        assert_eq!(
+            snapshot.last_observation_index.is_some(),
+            snapshot.observations_stored > 0,
+            "The snapshot `last_observation_index` does not match its `observations_stored`."
+        );
+        if let Some(last_observation_index) = snapshot.last_observation_index {
+            assert!(
+                last_observation_index < snapshot.observations_stored,
+                "The snapshot `last_observation_index` {} is beyond its {} observations stored.",
+                last_observation_index,
+                snapshot.observations_stored
+            );
+        }
+
+        let mut oracle = Oracle::new(snapshot.observations_limit);
+        oracle.observations_stored = snapshot.observations_stored;
+        oracle.last_observation_index = snapshot.last_observation_index;
+        oracle.sub_observations = snapshot.sub_observations;
+        oracle.max_extrapolation_seconds = snapshot.max_extrapolation_seconds;
+        oracle.max_interpolation_gap_minutes = snapshot.max_interpolation_gap_minutes;
+        oracle.max_interval_minutes = snapshot.max_interval_minutes;
+        oracle.price_sqrt_band = snapshot.price_sqrt_band;
+        oracle.price_sqrt_floor = snapshot.price_sqrt_floor;
+        oracle.min_spacing_minutes = snapshot.min_spacing_minutes;
+        oracle.min_retained_minutes = snapshot.min_retained_minutes;
+        oracle.max_observation_gap_minutes = snapshot.max_observation_gap_minutes;
+        oracle.emit_spot_events = snapshot.emit_spot_events;
+        oracle.token_pair = snapshot.token_pair;
+        oracle.price_sqrt_log_acc_offset = snapshot.price_sqrt_log_acc_offset;
+
+        if let Some(oldest_index) = oracle.oldest_index() {
+            for (offset, observation) in snapshot.observations.into_iter().enumerate() {
+                let index = oracle.index_at_offset(oldest_index, offset as u16);
+                oracle.observations.insert(index, observation);
+            }
+        }
+        assert!(
+            oracle.verify_storage(),
+            "The snapshot timestamps are not strictly increasing."
+        );
+
+        oracle
+    }
+
+    /// Returns a copy of the complete oracle state like `snapshot`, with the observations
+    /// delta-encoded to reduce the payload of a migration.
+    ///
+    /// The first observation is kept as is, while every following one only stores the minutes
+    /// since its predecessor and, for each accumulator, the XOR of its little-endian bytes with
+    /// the predecessor's with the trailing zero bytes (the unchanged high bytes) trimmed. Since
+    /// consecutive accumulators are close to each other, most of their bytes are equal. The
+    /// encoding is lossless for any values.
+    ///
+    /// # Returns
+    ///
+    /// A `CompressedOracleSnapshot`, which can be turned back into an oracle via
+    /// `Oracle::restore_compressed`.
+    pub fn compressed_snapshot(&self) -> CompressedOracleSnapshot {
+        let mut snapshot = self.snapshot();
+        let observations = mem::take(&mut snapshot.observations);
+
+        CompressedOracleSnapshot {
+            snapshot,
+            first_observation: observations.first().cloned(),
+            observations: observations
+                .windows(2)
+                .map(|pair| CompressedObservation::encode(&pair[0], &pair[1]))
+                .collect(),
+        }
+    }
+
+    /// Creates a new oracle from a snapshot previously taken with `Oracle::compressed_snapshot`,
+    /// restoring exactly the same state as `Oracle::restore` with the uncompressed snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `compressed` - The `CompressedOracleSnapshot` to restore the oracle from.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as `Oracle::restore`, or if a delta-encoded accumulator
+    /// is longer than the accumulator itself.
+    pub fn restore_compressed(compressed: CompressedOracleSnapshot) -> Self {
+        let mut snapshot = compressed.snapshot;
+        if let Some(mut previous) = compressed.first_observation {
+            snapshot.observations.push(previous.clone());
+            for observation in &compressed.observations {
+                previous = observation.decode(&previous);
+                snapshot.observations.push(previous.clone());
+            }
+        }
+
+        Oracle::restore(snapshot)
+    }
+}
+
+/// A serializable copy of the complete `Oracle` state, used to migrate the accumulated history
+/// between components.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
+pub struct OracleSnapshot {
+    /// All stored observations, in chronological order (oldest first).
+    pub observations: Vec<AccumulatedObservation>,
+    /// The maximum number of observations the oracle can store.
+    pub observations_limit: u16,
+    /// The ring buffer index of the last observation stored.
+    pub last_observation_index: Option<u16>,
+    /// The total number of observations that have been stored.
+    pub observations_stored: u16,
+    /// The state of the current minute.
+    pub sub_observations: Option<SubObservations>,
+    /// The maximum extrapolation past the last observation in seconds.
+    pub max_extrapolation_seconds: Option<u64>,
+    /// The maximum distance in minutes of two observations interpolated between.
+    pub max_interpolation_gap_minutes: Option<u64>,
+    /// The maximum number of minutes an interval may span.
+    pub max_interval_minutes: Option<u64>,
+    /// The maximum deviation factor of an observed `price_sqrt`.
+    pub price_sqrt_band: Option<PreciseDecimal>,
+    /// The minimum `price_sqrt` recorded.
+    pub price_sqrt_floor: Option<PreciseDecimal>,
+    /// The minimum number of minutes between two stored observations.
+    pub min_spacing_minutes: Option<u64>,
+    /// The minimum number of minutes a full ring has to keep covering.
+    pub min_retained_minutes: Option<u64>,
+    /// The maximum number of minutes between two stored observations before synthetic ones are
+    /// stored.
+    pub max_observation_gap_minutes: Option<u64>,
+    /// Whether `observe` emits a `SpotPriceEvent` on every call.
+    pub emit_spot_events: bool,
+    /// The addresses of the tokens x and y of the observed pool.
+    pub token_pair: Option<(ResourceAddress, ResourceAddress)>,
+    /// The total value subtracted from every stored `price_sqrt_log_acc` by `rebase`.
+    pub price_sqrt_log_acc_offset: Decimal,
+}
+
+/// An `OracleSnapshot` with delta-encoded observations, see `Oracle::compressed_snapshot`.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
+pub struct CompressedOracleSnapshot {
+    /// The snapshot without its observations.
+    pub snapshot: OracleSnapshot,
+    /// The oldest stored observation, if any, from which all others are decoded.
+    pub first_observation: Option<AccumulatedObservation>,
+    /// The observations after the first one, each encoded relative to its predecessor.
+    pub observations: Vec<CompressedObservation>,
+}
+
+/// An `AccumulatedObservation` encoded relative to the previous one.
+///
+/// Every accumulator is stored as the XOR of its little-endian bytes with the bytes of the
+/// previous accumulator, without the trailing zero bytes.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
+pub struct CompressedObservation {
+    /// The minutes since the previous observation.
+    pub minutes_delta: u32,
+    /// The delta-encoded `price_sqrt_log_acc`.
+    pub price_sqrt_log_acc: Vec<u8>,
+    /// The delta-encoded `price_sqrt_log_sq_acc`.
+    pub price_sqrt_log_sq_acc: Vec<u8>,
+    /// The delta-encoded `price_inverse_acc`.
+    pub price_inverse_acc: Vec<u8>,
+    /// The delta-encoded `liquidity_acc`.
+    pub liquidity_acc: Vec<u8>,
+    /// The delta-encoded `fee_rate_acc`.
+    pub fee_rate_acc: Vec<u8>,
+    /// The delta-encoded `volume_acc`.
+    pub volume_acc: Vec<u8>,
+    /// The delta-encoded `swap_count_acc`.
+    pub swap_count_acc: Vec<u8>,
+    /// The delta-encoded `observed_minutes_acc`.
+    pub observed_minutes_acc: Vec<u8>,
+}
+
+impl CompressedObservation {
+    /// Encodes `observation` relative to `previous`, which must be an earlier observation.
+    fn encode(previous: &AccumulatedObservation, observation: &AccumulatedObservation) -> Self {
+        let decimal_delta =
+            |previous: Decimal, value: Decimal| xor_delta(&previous.to_vec(), &value.to_vec());
+        let precise_decimal_delta = |previous: PreciseDecimal, value: PreciseDecimal| {
+            xor_delta(&previous.to_vec(), &value.to_vec())
+        };
+
+        CompressedObservation {
+            minutes_delta: u32::try_from(observation.timestamp - previous.timestamp)
+                .expect("The observations are too far apart to be compressed."),
+            price_sqrt_log_acc: decimal_delta(
+                previous.price_sqrt_log_acc,
+                observation.price_sqrt_log_acc,
+            ),
+            price_sqrt_log_sq_acc: decimal_delta(
+                previous.price_sqrt_log_sq_acc,
+                observation.price_sqrt_log_sq_acc,
+            ),
+            price_inverse_acc: precise_decimal_delta(
+                previous.price_inverse_acc,
+                observation.price_inverse_acc,
+            ),
+            liquidity_acc: decimal_delta(previous.liquidity_acc, observation.liquidity_acc),
+            fee_rate_acc: decimal_delta(previous.fee_rate_acc, observation.fee_rate_acc),
+            volume_acc: decimal_delta(previous.volume_acc, observation.volume_acc),
+            swap_count_acc: xor_delta(
+                &previous.swap_count_acc.to_le_bytes(),
+                &observation.swap_count_acc.to_le_bytes(),
+            ),
+            observed_minutes_acc: xor_delta(
+                &previous.observed_minutes_acc.to_le_bytes(),
+                &observation.observed_minutes_acc.to_le_bytes(),
+            ),
+        }
+    }
+
+    /// Decodes the observation following `previous`.
+    fn decode(&self, previous: &AccumulatedObservation) -> AccumulatedObservation {
+        let decimal = |previous: Decimal, delta: &[u8]| {
+            Decimal::try_from(xor_undelta(&previous.to_vec(), delta).as_slice()).unwrap()
+        };
+        let precise_decimal = |previous: PreciseDecimal, delta: &[u8]| {
+            PreciseDecimal::try_from(xor_undelta(&previous.to_vec(), delta).as_slice()).unwrap()
+        };
+        let swap_count_bytes: [u8; 8] =
+            xor_undelta(&previous.swap_count_acc.to_le_bytes(), &self.swap_count_acc)
+                .try_into()
+                .unwrap();
+        let observed_minutes_bytes: [u8; 8] = xor_undelta(
+            &previous.observed_minutes_acc.to_le_bytes(),
+            &self.observed_minutes_acc,
+        )
+        .try_into()
+        .unwrap();
+
+        AccumulatedObservation {
+            timestamp: previous.timestamp + self.minutes_delta as u64,
+            price_sqrt_log_acc: decimal(previous.price_sqrt_log_acc, &self.price_sqrt_log_acc),
+            price_sqrt_log_sq_acc: decimal(
+                previous.price_sqrt_log_sq_acc,
+                &self.price_sqrt_log_sq_acc,
+            ),
+            price_inverse_acc: precise_decimal(previous.price_inverse_acc, &self.price_inverse_acc),
+            liquidity_acc: decimal(previous.liquidity_acc, &self.liquidity_acc),
+            fee_rate_acc: decimal(previous.fee_rate_acc, &self.fee_rate_acc),
+            volume_acc: decimal(previous.volume_acc, &self.volume_acc),
+            swap_count_acc: u64::from_le_bytes(swap_count_bytes),
+            observed_minutes_acc: u64::from_le_bytes(observed_minutes_bytes),
+        }
+    }
+}
+
+/// Returns the XOR of two little-endian encoded values of the same length, without the trailing
+/// zero bytes, i.e. the high bytes both values share.
+fn xor_delta(previous: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut delta: Vec<u8> = previous
+        .iter()
+        .zip(value)
+        .map(|(previous, value)| previous ^ value)
+        .collect();
+    while delta.last() == Some(&0) {
+        delta.pop();
+    }
+    delta
+}
+
+/// Reverts `xor_delta`, returning the value encoded relative to `previous`.
+fn xor_undelta(previous: &[u8], delta: &[u8]) -> Vec<u8> {
+    assert!(
+        delta.len() <= previous.len(),
+        "The delta-encoded accumulator of {} bytes exceeds the {} bytes of the accumulator.",
+        delta.len(),
+        previous.len()
+    );
+    previous
+        .iter()
+        .enumerate()
+        .map(|(index, previous)| previous ^ delta.get(index).unwrap_or(&0))
+        .collect()
+}
+
+/// The spot price recorded by an `observe` call, emitted if enabled via
+/// `Oracle::set_emit_spot_events`.
+#[derive(ScryptoSbor, ScryptoEvent, Clone, Debug, PartialEq)]
+pub struct SpotPriceEvent {
+    /// The time of the call in Unix seconds.
+    pub timestamp_seconds: u64,
+    /// The recorded price square root, i.e. after clamping to the `price_sqrt_band` and the
+    /// `price_sqrt_floor` if set.
+    pub price_sqrt: PreciseDecimal,
+}
+
+/// The status of an oracle, see `Oracle::metadata`.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
+pub struct OracleMetadata {
+    /// The maximum number of observations the oracle can store.
+    pub observations_limit: u16,
+    /// The number of observations currently stored.
+    pub observations_stored: u16,
+    /// The ring buffer index of the last observation stored.
+    pub last_observation_index: Option<u16>,
+    /// The timestamp of the oldest observation in seconds.
+    pub oldest_observation_at: Option<u64>,
+    /// The timestamp of the last update of the oracle in seconds.
+    pub last_update_timestamp: Option<u64>,
 }
 
 /// The SubObservations object is used to accumulate and manage price square root states within
 /// a given minute.
 /// When a new minute is reached, it performs a time-weighted averaging of the minute's prices,
 /// so that the resulting value is used for logarithmic accumulation in the Oracle object.
-#[derive(ScryptoSbor)]
+/// The active liquidity and the input fee rate are averaged in the same way, alongside the price
+/// square root.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
 pub struct SubObservations {
     /// The sum of the product of the last price square root and the time elapsed since
     /// the last update.
     price_sqrt_sum: PreciseDecimal,
     /// The last observed price square root.
     price_sqrt_last: PreciseDecimal,
+    /// The sum of the product of the last inverse price and the time elapsed since the last
+    /// update.
+    price_inverse_sum: PreciseDecimal,
+    /// The inverse of the last observed price, i.e. `1 / price_sqrt²`.
+    price_inverse_last: PreciseDecimal,
+    /// The sum of the product of the last active liquidity and the time elapsed since
+    /// the last update.
+    liquidity_sum: PreciseDecimal,
+    /// The last observed active liquidity.
+    liquidity_last: PreciseDecimal,
+    /// The sum of the product of the last input fee rate and the time elapsed since the last
+    /// update.
+    fee_rate_sum: PreciseDecimal,
+    /// The last observed input fee rate.
+    fee_rate_last: Decimal,
+    /// The volume traded during the minute.
+    volume_sum: Decimal,
+    /// The number of swaps performed during the minute.
+    swap_count: u64,
+    /// The average `price_sqrt` of the last finalized minute. None before the first finalization.
+    price_sqrt_finalized_avg: Option<PreciseDecimal>,
     /// The instant when the last update (call to `new_subobservation` or `finalize`) occurred.
     last_updated: Instant,
     /// The instant when the sub-observations were initialized. After the initialization is
@@ -373,11 +3583,25 @@ pub struct SubObservations {
 
 impl SubObservations {
     pub fn new() -> Self {
+        Self::new_at(Clock::instant())
+    }
+
+    /// Creates a new SubObservations object, initialized at the given instant.
+    pub fn new_at(instant: Instant) -> Self {
         Self {
-            last_updated: Clock::instant(),
-            initialization: Some(Clock::instant()),
+            last_updated: instant,
+            initialization: Some(instant),
             price_sqrt_sum: pdec!(0),
             price_sqrt_last: pdec!(0),
+            price_inverse_sum: pdec!(0),
+            price_inverse_last: pdec!(0),
+            liquidity_sum: pdec!(0),
+            liquidity_last: pdec!(0),
+            fee_rate_sum: pdec!(0),
+            fee_rate_last: dec!(0),
+            volume_sum: dec!(0),
+            swap_count: 0,
+            price_sqrt_finalized_avg: None,
         }
     }
 
@@ -385,18 +3609,62 @@ impl SubObservations {
     ///
     /// This method is called at the end of every swap, to inform the oracle of the lastest
     /// pool `price_sqrt`. It performs the time-weighted accumulation of the `price_sqrt`s
-    /// during each minute.
+    /// (and of the active liquidity and input fee rate) during each minute, and sums up the
+    /// volume.
     ///
     /// # Arguments
     ///
     /// * `price_sqrt` - A `PreciseDecimal` representing the new price square root to be observed.
-    pub fn new_subobservation(&mut self, price_sqrt: PreciseDecimal) {
-        let current_instant = Clock::instant();
+    /// * `active_liquidity` - A `PreciseDecimal` representing the new active liquidity to be
+    /// observed.
+    /// * `volume` - A `Decimal` representing the volume traded by the swap.
+    /// * `input_fee_rate` - A `Decimal` representing the new input fee rate to be observed.
+    pub fn new_subobservation(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        input_fee_rate: Decimal,
+    ) {
+        self.new_subobservation_at(
+            price_sqrt,
+            active_liquidity,
+            volume,
+            input_fee_rate,
+            Clock::instant(),
+        );
+    }
 
-        if current_instant != self.last_updated {
+    /// Same as `new_subobservation`, but performed at the given instant instead of the current
+    /// ledger time.
+    pub fn new_subobservation_at(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        input_fee_rate: Decimal,
+        current_instant: Instant,
+    ) {
+        // Instants earlier than `last_updated` (non-monotonic clock) are handled like the same
+        // instant, instead of underflowing the elapsed seconds.
+        // Comparing the marginal seconds is sufficient, as a later minute is always finalized
+        // first, which resets `last_updated` to the start of that minute. The first swap of a
+        // minute is thereby weighted from the start of the minute, not from the previous swap.
+        if current_instant > self.last_updated {
             let delta_marginal_seconds =
                 current_instant.seconds_marginal() - self.last_updated.seconds_marginal();
             self.price_sqrt_sum += self.price_sqrt_last * delta_marginal_seconds;
+            // Unlike the other values, the inverse price may exceed a `PreciseDecimal` over a
+            // minute (for prices far below the tick range), so it saturates instead.
+            self.price_inverse_sum = saturating_add_precise(
+                self.price_inverse_sum,
+                saturating_mul_precise(
+                    self.price_inverse_last,
+                    PreciseDecimal::from(delta_marginal_seconds),
+                ),
+            );
+            self.liquidity_sum += self.liquidity_last * delta_marginal_seconds;
+            self.fee_rate_sum += PreciseDecimal::from(self.fee_rate_last) * delta_marginal_seconds;
             self.last_updated = current_instant;
         }
 
@@ -406,81 +3674,264 @@ impl SubObservations {
         // This mechanism is crucial as the code cannot predict if a swap will be the last within
         // a given second.
         self.price_sqrt_last = price_sqrt;
+        self.price_inverse_last = price_inverse(price_sqrt);
+        self.liquidity_last = active_liquidity;
+        self.fee_rate_last = input_fee_rate;
+        // Unlike the prices, the volume of every swap counts.
+        self.volume_sum = saturating_add(self.volume_sum, volume);
+    }
+
+    /// Returns the average `price_sqrt` of the last finalized minute, or `None` if no minute has
+    /// been finalized yet.
+    pub fn last_finalized_price_sqrt(&self) -> Option<PreciseDecimal> {
+        self.price_sqrt_finalized_avg
     }
 
-    /// Calculates the time-weighted average `price_sqrt` for the last active minute
+    /// Counts a swap performed during the current minute. This is kept apart from
+    /// `new_subobservation`, which is also informed of the price before a swap.
+    pub fn count_swap(&mut self) {
+        self.swap_count = self.swap_count.saturating_add(1);
+    }
+
+    /// Calculates the time-weighted averages for the last active minute
     /// (at which swaps took place) and resets the SubObservations object in order to
     /// prepare it for the new minute, by setting the time properly and resetting the
-    /// `price_sqrt_sum`.
+    /// `price_sqrt_sum`, `price_inverse_sum`, `liquidity_sum`, `fee_rate_sum`, `volume_sum`
+    /// and `swap_count`.
     ///
     /// # Returns
     ///
-    /// Returns the time-weighted average `price_sqrt` for the last active minute.
-    pub fn finalize(&mut self) -> PreciseDecimal {
-        // Below, the duration across which the `price_sqrt_sum` is averaged is conditionally
+    /// Returns a `MinuteSummary` with the time-weighted averages and the last values of the last
+    /// active minute.
+    ///
+    /// # Panics
+    /// Panics if the current time rounded down to the minute is before the last update, i.e. if
+    /// the clock went backward or the minute of the last update has not ended yet.
+    pub fn finalize(&mut self) -> MinuteSummary {
+        self.finalize_at(Clock::instant())
+    }
+
+    /// Same as `finalize`, but performed at the given instant instead of the current ledger time.
+    pub fn finalize_at(&mut self, now: Instant) -> MinuteSummary {
+        // Below, the duration across which the sums are averaged is conditionally
         // set to either:
         // 1) The number of seconds passed between the first swap and the end of the minute,
         //  if this is the first minute being recorded by the SubMinutes object
         // 2) 60 seconds otherwise.
         // This ensures that the first minute is averaged fairly, from the moment at which the
         // first swap took place.
+        // We set the instant rounded to the minute, as if the last transaction took place when the
+        // minute dawned.
+        // This is meant to allow the object to perform the accumulation correctly when the
+        // first swap in the minute takes place, i.e. that the new price is weighted by the number
+        // of seconds that passed since the beginning of the minute.
+        let last_updated = Instant::new((now.minutes() * 60) as i64);
+        // An earlier minute would silently break the continuity of the accumulators
+        assert!(
+            last_updated >= self.last_updated,
+            "Cannot finalize at {} (rounded to the minute) before the last update at {}.",
+            last_updated.seconds_since_unix_epoch,
+            self.last_updated.seconds_since_unix_epoch
+        );
+
         let duration = match self.initialization.take() {
             Some(instant) => 60 - instant.seconds_marginal(),
             None => 60,
         };
-        let price_sqrt_avg = self.price_sqrt_average(duration);
+        let summary = self.summary(duration);
 
         // Prepare SubObservations for new minute
-        // We set the instant rounded to the minute, as if the last transaction took place when the
-        // minute dawned.
-        // This is meant to allow the object to perform the accumulation correctly when the
-        // first swap in the minute takes place, i.e. that the new price is weighted by the number
-        // of seconds that passed since the beginning of the minute.
-        self.last_updated = Clock::current_time_rounded_to_minutes();
+        self.last_updated = last_updated;
         self.price_sqrt_sum = pdec!(0);
+        self.price_inverse_sum = pdec!(0);
+        self.liquidity_sum = pdec!(0);
+        self.fee_rate_sum = pdec!(0);
+        self.volume_sum = dec!(0);
+        self.swap_count = 0;
+        self.price_sqrt_finalized_avg = Some(summary.price_sqrt_avg);
 
-        price_sqrt_avg
+        summary
     }
 
-    /// Provides a preview of the time-weighted average `price_sqrt` during the last active minute,
+    /// Provides a preview of the time-weighted averages during the last active minute,
     /// without changing the state of SubObservations.
     ///
-    /// This method calculates a preview of the time-weighted average price square root without
+    /// This method calculates a preview of the time-weighted averages without
     /// finalizing the current observations. It is useful for getting an estimate before the minute
     /// ends. This method asserts that no initial observation has been set yet, ensuring it's only
     /// called under appropriate conditions.
     ///
     /// # Returns
     ///
-    /// Returns the time-weighted average price square root for the current minute based on the
-    /// observations so far.
-    pub fn finalize_preview(&self) -> PreciseDecimal {
+    /// Returns a `MinuteSummary` for the current minute based on the observations so far.
+    pub fn finalize_preview(&self) -> MinuteSummary {
         assert!(
             self.initialization.is_none(),
             "Not yet possible to retrieve this data. Please wait for a new observation to be
             stored."
         );
-        self.price_sqrt_average(60)
+        self.summary(60)
+    }
+
+    /// Summarizes the current minute, averaging the accumulated sums over the given duration.
+    fn summary(&self, duration: u64) -> MinuteSummary {
+        MinuteSummary {
+            price_sqrt_avg: self.time_weighted_average(
+                self.price_sqrt_sum,
+                self.price_sqrt_last,
+                duration,
+            ),
+            price_sqrt_last: self.price_sqrt_last,
+            price_inverse_avg: self.time_weighted_average(
+                self.price_inverse_sum,
+                self.price_inverse_last,
+                duration,
+            ),
+            price_inverse_last: self.price_inverse_last,
+            liquidity_avg: self.time_weighted_average(
+                self.liquidity_sum,
+                self.liquidity_last,
+                duration,
+            ),
+            liquidity_last: self.liquidity_last,
+            fee_rate_avg: self.time_weighted_average(
+                self.fee_rate_sum,
+                PreciseDecimal::from(self.fee_rate_last),
+                duration,
+            ),
+            fee_rate_last: PreciseDecimal::from(self.fee_rate_last),
+            volume: self.volume_sum,
+            swap_count: self.swap_count,
+        }
     }
 
-    /// Calculates the time-weighted average price square root over a given duration.
+    /// Calculates the time-weighted average of a value over a given duration.
     ///
-    /// This helper method computes the time-weighted average price square root by taking into
-    /// account the sum of price square roots observed and the last observed price square root,
-    /// adjusted for the time elapsed since the last observation.
+    /// This helper method computes the time-weighted average by taking into account the sum of
+    /// the values observed and the last observed value, adjusted for the time elapsed since the
+    /// last observation.
     ///
     /// # Arguments
     ///
-    /// * `duration` - The duration in seconds over which to average the price square roots.
+    /// * `sum` - The time-weighted sum of the value since the start of the minute.
+    /// * `last` - The last observed value.
+    /// * `duration` - The duration in seconds over which to average the value.
     ///
     /// # Returns
     ///
-    /// Returns the time-weighted average price square root over the specified duration.
-    fn price_sqrt_average(&self, duration: u64) -> PreciseDecimal {
+    /// Returns the time-weighted average of the value over the specified duration, or
+    /// `PreciseDecimal::MAX` if the sum saturated, so that the saturation carries over to the
+    /// accumulator.
+    fn time_weighted_average(
+        &self,
+        sum: PreciseDecimal,
+        last: PreciseDecimal,
+        duration: u64,
+    ) -> PreciseDecimal {
         let delta_marginal_seconds = 60 - self.last_updated.seconds_marginal();
-        let price_sqrt_sum = self.price_sqrt_sum + self.price_sqrt_last * delta_marginal_seconds;
+        let sum = saturating_add_precise(
+            sum,
+            saturating_mul_precise(last, PreciseDecimal::from(delta_marginal_seconds)),
+        );
+        if sum == PreciseDecimal::MAX {
+            return PreciseDecimal::MAX;
+        }
+
+        sum / duration
+    }
+}
+
+/// The summary of an active minute, produced by `SubObservations` and accumulated into the next
+/// `AccumulatedObservation`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinuteSummary {
+    /// The time-weighted average `price_sqrt` of the minute.
+    pub price_sqrt_avg: PreciseDecimal,
+    /// The last `price_sqrt` of the minute, which holds for the following idle minutes.
+    pub price_sqrt_last: PreciseDecimal,
+    /// The time-weighted average of `1 / price` of the minute.
+    pub price_inverse_avg: PreciseDecimal,
+    /// The inverse of the last price of the minute.
+    pub price_inverse_last: PreciseDecimal,
+    /// The time-weighted average active liquidity of the minute.
+    pub liquidity_avg: PreciseDecimal,
+    /// The last active liquidity of the minute, which holds for the following idle minutes.
+    pub liquidity_last: PreciseDecimal,
+    /// The time-weighted average input fee rate of the minute.
+    pub fee_rate_avg: PreciseDecimal,
+    /// The last input fee rate of the minute, which holds for the following idle minutes.
+    pub fee_rate_last: PreciseDecimal,
+    /// The volume traded during the minute.
+    pub volume: Decimal,
+    /// The number of swaps performed during the minute.
+    pub swap_count: u64,
+}
+
+/// The failure modes of the oracle, returned by the `try_*` methods so that callers can
+/// distinguish them instead of aborting the transaction.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleError {
+    /// The timestamp is older than the oldest stored observation.
+    TimestampTooOld,
+    /// The timestamp is later than the current time.
+    TimestampInFuture,
+    /// The bounds of an interval do not round down to increasing minutes.
+    IntervalNotIncreasing,
+    /// No observations have been stored yet.
+    NoObservations,
+    /// The timestamp is further after the last observation than `max_extrapolation_seconds`.
+    ExtrapolationTooStale,
+    /// The observations limit is too small.
+    CapacityTooSmall,
+    /// The average price over the interval does not fit into a `Decimal`, e.g. because of an
+    /// extreme price move or a saturated accumulator.
+    PriceOverflow,
+    /// Less than one minute of history is available for the requested window.
+    InsufficientHistory,
+    /// No intervals were given.
+    EmptyIntervals,
+    /// The timestamp lies between two observations further apart than
+    /// `max_interpolation_gap_minutes`.
+    InterpolationGapTooWide,
+    /// The timestamp is after the last observation, but the state of the current minute needed
+    /// to extrapolate is missing.
+    ExtrapolationUnavailable,
+    /// The interval spans more minutes than `max_interval_minutes`.
+    IntervalTooLong,
+}
+
+/// A Unix timestamp in seconds, e.g. as accepted by `Oracle::observation`.
+///
+/// Distinguishing it from `UnixMinutes`, which the oracle stores internally, lets the compiler
+/// reject minutes where seconds are expected. Plain `u64` values are still accepted by the
+/// public interfaces via `From<u64>`, keeping existing call sites working.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UnixSeconds(pub u64);
+
+impl UnixSeconds {
+    /// Rounds the timestamp down to the minute.
+    pub fn to_minutes(self) -> UnixMinutes {
+        UnixMinutes(self.0 / 60)
+    }
+}
+
+impl From<u64> for UnixSeconds {
+    fn from(seconds: u64) -> Self {
+        UnixSeconds(seconds)
+    }
+}
 
-        price_sqrt_sum / duration
+/// A Unix timestamp in minutes, i.e. the unit observations are stored in.
+///
+/// Unlike `UnixSeconds` it cannot be converted from a plain `u64` implicitly, so minutes have to
+/// be constructed explicitly.
+#[derive(ScryptoSbor, ManifestSbor, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UnixMinutes(pub u64);
+
+impl UnixMinutes {
+    /// Converts the timestamp to seconds, i.e. the start of the minute.
+    pub fn to_seconds(self) -> UnixSeconds {
+        UnixSeconds(self.0 * 60)
     }
 }
 
@@ -488,12 +3939,28 @@ impl SubObservations {
 ///
 /// This struct holds the timestamp of the observation and the accumulated
 /// logarithmic value of the price square root up to this point.
-#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
 pub struct AccumulatedObservation {
     /// The timestamp of the observation.
     pub timestamp: u64,
     /// The accumulated logarithmic value of the price square root.
     pub price_sqrt_log_acc: Decimal,
+    /// The accumulated square of the logarithmic value of the price square root.
+    pub price_sqrt_log_sq_acc: Decimal,
+    /// The accumulated inverse of the price, kept as a `PreciseDecimal` to retain the significant
+    /// digits of high prices, see `accumulated_inverse`.
+    pub price_inverse_acc: PreciseDecimal,
+    /// The accumulated active liquidity.
+    pub liquidity_acc: Decimal,
+    /// The accumulated input fee rate of the pool.
+    pub fee_rate_acc: Decimal,
+    /// The accumulated volume, i.e. the total volume traded before the timestamp.
+    pub volume_acc: Decimal,
+    /// The accumulated swap count, i.e. the total number of swaps before the timestamp.
+    pub swap_count_acc: u64,
+    /// The accumulated number of observed minutes, i.e. the minutes before the timestamp in which
+    /// the oracle observed the pool. The minutes of synthetic observations are not counted.
+    pub observed_minutes_acc: u64,
 }
 
 /// Represents an interval between two observations.
@@ -502,12 +3969,59 @@ pub struct AccumulatedObservation {
 /// calculated price square root for this interval.
 #[derive(ScryptoSbor, Clone, Debug, PartialEq)]
 pub struct ObservationInterval {
+    /// The start timestamp of the interval.
+    pub start: u64,
+    /// The end timestamp of the interval.
+    pub end: u64,
+    /// The number of minutes spanned by the interval, e.g. to weight it in a composite average.
+    pub duration_minutes: u64,
+    /// The calculated price square root for the interval. It is kept as a `PreciseDecimal` to
+    /// retain the significant digits of very small prices, e.g. of pairs with low-value tokens.
+    pub price_sqrt: PreciseDecimal,
+    /// The price (y per x) for the interval, i.e. the square of `price_sqrt` truncated to a
+    /// `Decimal`. The harmonic mean is instead taken from its own accumulator, like
+    /// `price_sqrt`.
+    pub price: Decimal,
+}
+
+/// The single entry cache of `Oracle::twap_last_cached`.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+struct TwapCache {
+    /// The window the interval was computed for, in seconds.
+    window_seconds: u64,
+    /// The Unix minute the interval was computed in.
+    minute: u64,
+    /// The computed interval.
+    interval: ObservationInterval,
+}
+
+/// An entry of the cache of `Oracle::observation_intervals_cached`.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+struct IntervalCacheEntry {
+    /// The start of the interval in Unix minutes.
+    start_minutes: u64,
+    /// The end of the interval in Unix minutes.
+    end_minutes: u64,
+    /// The index of the last observation when the interval was computed.
+    last_observation_index: u16,
+    /// The computed interval.
+    interval: ObservationInterval,
+}
+
+/// Represents an interval between two observations together with the inputs of its geometric
+/// mean.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq)]
+pub struct ObservationIntervalDetailed {
     /// The start timestamp of the interval.
     pub start: u64,
     /// The end timestamp of the interval.
     pub end: u64,
     /// The calculated price square root for the interval.
-    pub price_sqrt: Decimal,
+    pub price_sqrt: PreciseDecimal,
+    /// The accumulated logarithmic value of the price square root at the start.
+    pub acc_left: Decimal,
+    /// The accumulated logarithmic value of the price square root at the end.
+    pub acc_right: Decimal,
 }
 
 impl AccumulatedObservation {
@@ -515,6 +4029,69 @@ impl AccumulatedObservation {
         AccumulatedObservation {
             timestamp: 0,
             price_sqrt_log_acc: dec!(0),
+            price_sqrt_log_sq_acc: dec!(0),
+            price_inverse_acc: pdec!(0),
+            liquidity_acc: dec!(0),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: 0,
+            observed_minutes_acc: 0,
+        }
+    }
+
+    /// Accumulates the summary of the last active minute on top of this observation.
+    ///
+    /// # Arguments
+    /// * `timestamp`: The timestamp (in minutes) of the new observation.
+    /// * `summary`: The summary of the last active minute.
+    /// * `minutes_since_last`: The number of minutes since the last active minute started.
+    ///
+    /// # Returns
+    /// Returns the new `AccumulatedObservation`.
+    fn accumulate(
+        &self,
+        timestamp: u64,
+        summary: &MinuteSummary,
+        minutes_since_last: u64,
+    ) -> AccumulatedObservation {
+        AccumulatedObservation {
+            timestamp,
+            price_sqrt_log_acc: accumulated_log(
+                self.price_sqrt_log_acc,
+                summary.price_sqrt_avg,
+                summary.price_sqrt_last,
+                minutes_since_last,
+            ),
+            price_sqrt_log_sq_acc: accumulated_log_squared(
+                self.price_sqrt_log_sq_acc,
+                summary.price_sqrt_avg,
+                summary.price_sqrt_last,
+                minutes_since_last,
+            ),
+            price_inverse_acc: accumulated_inverse(
+                self.price_inverse_acc,
+                summary.price_inverse_avg,
+                summary.price_inverse_last,
+                minutes_since_last,
+            ),
+            liquidity_acc: accumulated_value(
+                self.liquidity_acc,
+                summary.liquidity_avg,
+                summary.liquidity_last,
+                minutes_since_last,
+            ),
+            fee_rate_acc: accumulated_value(
+                self.fee_rate_acc,
+                summary.fee_rate_avg,
+                summary.fee_rate_last,
+                minutes_since_last,
+            ),
+            // Volume is only traded in the active minute, not in the following idle ones
+            volume_acc: saturating_add(self.volume_acc, summary.volume),
+            swap_count_acc: self.swap_count_acc.saturating_add(summary.swap_count),
+            // The summarized minute is the only observed one since the last observation, for the
+            // synthetic observations filling the gap after it as well
+            observed_minutes_acc: self.observed_minutes_acc.saturating_add(1),
         }
     }
 }
@@ -522,6 +4099,14 @@ impl AccumulatedObservation {
 /// Calculates the accumulated logarithmic value, which will be used later as one of the points to
 /// calculate interval averages and returns it.
 ///
+/// Every minute contributes the logarithm of its own value: the finalized minute contributes
+/// `log(finalized)` and each of the following minutes without swaps contributes `log(last_value)`,
+/// i.e. the result is `acc_value + log(finalized) + (minutes_since_last - 1) * log(last_value)`.
+/// The logarithms are summed per minute rather than taken of summed values, so that the difference
+/// of two accumulators divided by their distance is the average log value, see `geometric_mean`.
+/// For the first observation, accumulated from an empty one one minute after the first swap, this
+/// yields exactly `log(finalized)`.
+///
 /// # Arguments
 /// * `acc_value`: The accumulated value so far.
 /// * `finalized`: The value registered during the last observation,
@@ -531,30 +4116,249 @@ impl AccumulatedObservation {
 ///
 /// # Returns
 /// Returns the new accumulated logarithmic value as a `Decimal`.
+///
+/// # Panics
+/// Panics if `minutes_since_last` is zero. Observations are only created in a later minute than
+/// the last update, so the finalized minute always counts as at least one minute.
+///
+/// # Overflow
+/// The accumulation saturates at `Decimal::MAX` (or `Decimal::MIN` for negative logarithms)
+/// instead of panicking. Since the logarithm of any `price_sqrt` representable by a
+/// `PreciseDecimal` is smaller than 94 in absolute value, the accumulator grows by less than 94
+/// per minute, so saturation is only reached after more than 10^37 minutes. Intervals involving
+/// a saturated accumulator yield meaningless averages. This horizon only holds for the logarithmic
+/// accumulators, the accumulated inverse price saturates far earlier for low prices, see
+/// `accumulated_inverse`.
 pub fn accumulated_log(
     acc_value: Decimal,
     finalized: PreciseDecimal,
     last_value: PreciseDecimal,
     minutes_since_last: u64,
 ) -> Decimal {
-    let finalized_log = finalized
+    let finalized_log = truncated_log(finalized);
+    let last_value_log = truncated_log(last_value);
+
+    let leaked_log = saturating_mul(last_value_log, Decimal::from(minutes_since_last - 1));
+
+    saturating_add(saturating_add(acc_value, finalized_log), leaked_log)
+}
+
+/// Calculates the accumulated square of the logarithmic value, the second moment counterpart of
+/// `accumulated_log`. Together they allow deriving the variance of the logarithmic value over an
+/// interval from its two endpoints, see `Oracle::variance`.
+///
+/// # Arguments
+/// See `accumulated_log`.
+///
+/// # Returns
+/// Returns the new accumulated squared logarithmic value as a `Decimal`, saturating on overflow.
+pub fn accumulated_log_squared(
+    acc_value: Decimal,
+    finalized: PreciseDecimal,
+    last_value: PreciseDecimal,
+    minutes_since_last: u64,
+) -> Decimal {
+    let finalized_log = truncated_log(finalized);
+    let last_value_log = truncated_log(last_value);
+
+    let finalized_log_sq = saturating_mul(finalized_log, finalized_log);
+    let leaked_log_sq = saturating_mul(
+        saturating_mul(last_value_log, last_value_log),
+        Decimal::from(minutes_since_last - 1),
+    );
+
+    saturating_add(saturating_add(acc_value, finalized_log_sq), leaked_log_sq)
+}
+
+/// Returns the natural logarithm of `value`, truncated towards negative infinity to a `Decimal`.
+fn truncated_log(value: PreciseDecimal) -> Decimal {
+    value
         .ln()
         .unwrap()
         .checked_truncate(RoundingMode::ToNegativeInfinity)
-        .unwrap();
-    let last_value_log = last_value
-        .ln()
         .unwrap()
+}
+
+/// Calculates the accumulated (linear) value of a quantity, which will be used later as one of the
+/// points to calculate interval averages and returns it. This is the linear counterpart of
+/// `accumulated_log`, used for quantities which are averaged arithmetically, e.g. liquidity.
+///
+/// # Arguments
+/// * `acc_value`: The accumulated value so far.
+/// * `finalized`: The average value of the last active minute.
+/// * `last_value`: The last value of the last active minute.
+/// * `minutes_since_last`: The number of minutes since the last observation.
+///
+/// # Returns
+/// Returns the new accumulated value as a `Decimal`, saturating on overflow.
+pub fn accumulated_value(
+    acc_value: Decimal,
+    finalized: PreciseDecimal,
+    last_value: PreciseDecimal,
+    minutes_since_last: u64,
+) -> Decimal {
+    let finalized = finalized
+        .checked_truncate(RoundingMode::ToNegativeInfinity)
+        .unwrap();
+    let last_value = last_value
         .checked_truncate(RoundingMode::ToNegativeInfinity)
         .unwrap();
 
-    acc_value + finalized_log + (minutes_since_last - 1) * last_value_log
+    let leaked = saturating_mul(last_value, Decimal::from(minutes_since_last - 1));
+
+    saturating_add(saturating_add(acc_value, finalized), leaked)
+}
+
+/// Calculates the accumulated inverse price, the counterpart of `accumulated_value` kept at
+/// `PreciseDecimal` precision for the harmonic mean, see `Oracle::observation_intervals_harmonic`.
+///
+/// # Arguments
+/// See `accumulated_value`, with the values being `1 / price`.
+///
+/// # Returns
+/// Returns the new accumulated inverse price as a `PreciseDecimal`, saturating on overflow.
+///
+/// # Overflow
+/// The inverse price spans the inverse of the price range, so unlike for the logarithmic
+/// accumulators neither end of the tick range is harmless:
+/// - The accumulator grows by `1 / price` per minute and saturates at `PreciseDecimal::MAX`
+/// (about 5.8 * 10^40), i.e. after about `5.8 * 10^40 * price` minutes. This is more than 10^10
+/// minutes for prices above 10^-30, but only about 170 minutes at the lowest price of the tick
+/// range (about 2.9 * 10^-39). `Oracle::set_price_sqrt_floor` extends the horizon accordingly.
+/// - The inverse is truncated to 36 decimal places, so it keeps fewer significant digits the
+/// higher the price, e.g. 12 digits for a price of 10^24, and truncates to zero for prices above
+/// 10^36, which includes the highest prices of the tick range.
+///
+/// Harmonic means over a saturated or non-increasing accumulator are rejected with
+/// `OracleError::PriceOverflow` instead of yielding meaningless averages.
+pub fn accumulated_inverse(
+    acc_value: PreciseDecimal,
+    finalized: PreciseDecimal,
+    last_value: PreciseDecimal,
+    minutes_since_last: u64,
+) -> PreciseDecimal {
+    let leaked = saturating_mul_precise(last_value, PreciseDecimal::from(minutes_since_last - 1));
+
+    saturating_add_precise(saturating_add_precise(acc_value, finalized), leaked)
+}
+
+/// Returns the inverse price `1 / price_sqrt²`, saturating at `PreciseDecimal::MAX` if it does not
+/// fit, e.g. for a zero `price_sqrt`. Inverting before squaring keeps the inverse from dividing by
+/// zero for a price square root whose square is below the precision of `PreciseDecimal`.
+fn price_inverse(price_sqrt: PreciseDecimal) -> PreciseDecimal {
+    pdec!(1)
+        .checked_div(price_sqrt)
+        .and_then(|price_sqrt_inverse| price_sqrt_inverse.checked_mul(price_sqrt_inverse))
+        .unwrap_or(PreciseDecimal::MAX)
+}
+
+/// Adds two non-negative `PreciseDecimal`s, saturating at `PreciseDecimal::MAX` on overflow.
+fn saturating_add_precise(a: PreciseDecimal, b: PreciseDecimal) -> PreciseDecimal {
+    a.checked_add(b).unwrap_or(PreciseDecimal::MAX)
+}
+
+/// Multiplies two non-negative `PreciseDecimal`s, saturating at `PreciseDecimal::MAX` on overflow.
+fn saturating_mul_precise(a: PreciseDecimal, b: PreciseDecimal) -> PreciseDecimal {
+    a.checked_mul(b).unwrap_or(PreciseDecimal::MAX)
+}
+
+/// Adds two `Decimal`s, saturating at `Decimal::MAX` or `Decimal::MIN` on overflow.
+fn saturating_add(a: Decimal, b: Decimal) -> Decimal {
+    a.checked_add(b).unwrap_or(if b.is_negative() {
+        Decimal::MIN
+    } else {
+        Decimal::MAX
+    })
+}
+
+/// Multiplies two `Decimal`s, saturating at `Decimal::MAX` or `Decimal::MIN` on overflow.
+fn saturating_mul(a: Decimal, b: Decimal) -> Decimal {
+    a.checked_mul(b)
+        .unwrap_or(if a.is_negative() != b.is_negative() {
+            Decimal::MIN
+        } else {
+            Decimal::MAX
+        })
+}
+
+/// Interpolates an observation for the `target_timestamp` between its two neighbors, as found by
+/// `binary_search_neighbors`.
+///
+/// # Arguments
+/// * `o_left` - The closest stored observation at or before the `target_timestamp`.
+/// * `o_right` - The closest stored observation at or after the `target_timestamp`.
+/// * `target_timestamp` - The specific timestamp for which an observation is sought.
+///
+/// # Returns
+/// Returns an AccumulatedObservation instance. This will either be the observation that exactly
+/// matches the `target_timestamp`, or an interpolated observation between the two closest
+/// timestamps.
+fn interpolate_neighbors(
+    o_left: AccumulatedObservation,
+    o_right: AccumulatedObservation,
+    target_timestamp: u64,
+) -> AccumulatedObservation {
+    // Both neighbors are the same observation if it matches the target or is the only one stored,
+    // which cannot be interpolated
+    if o_left.timestamp == target_timestamp || o_left.timestamp == o_right.timestamp {
+        return o_left;
+    }
+
+    // Interpolation
+    let price_sqrt_log_acc = linear_interpolation(
+        o_left.timestamp,
+        o_right.timestamp,
+        o_left.price_sqrt_log_acc,
+        o_right.price_sqrt_log_acc,
+        target_timestamp,
+    );
+    let price_sqrt_log_sq_acc = linear_interpolation(
+        o_left.timestamp,
+        o_right.timestamp,
+        o_left.price_sqrt_log_sq_acc,
+        o_right.price_sqrt_log_sq_acc,
+        target_timestamp,
+    );
+    let price_inverse_acc = inverse_linear_interpolation(
+        o_left.timestamp,
+        o_right.timestamp,
+        o_left.price_inverse_acc,
+        o_right.price_inverse_acc,
+        target_timestamp,
+    );
+    let liquidity_acc = linear_interpolation(
+        o_left.timestamp,
+        o_right.timestamp,
+        o_left.liquidity_acc,
+        o_right.liquidity_acc,
+        target_timestamp,
+    );
+    let fee_rate_acc = linear_interpolation(
+        o_left.timestamp,
+        o_right.timestamp,
+        o_left.fee_rate_acc,
+        o_right.fee_rate_acc,
+        target_timestamp,
+    );
+
+    // All the volume (and swaps) between two consecutive observations is traded in the minute of
+    // the left one, which is also the only observed one, so the accumulated volume (and observed
+    // minutes) of any later minute equals the right one.
+    AccumulatedObservation {
+        timestamp: target_timestamp,
+        price_sqrt_log_acc,
+        price_sqrt_log_sq_acc,
+        price_inverse_acc,
+        liquidity_acc,
+        fee_rate_acc,
+        volume_acc: o_right.volume_acc,
+        swap_count_acc: o_right.swap_count_acc,
+        observed_minutes_acc: o_right.observed_minutes_acc,
+    }
 }
 
-/// Performs binary search in the list of AccumulatedObservations in order to find the one matching
-/// the `target_timestamp``
-/// If no observation exists with the provided `target_timestamp`, then interpolation is performed
-/// with the two closest ones. `
+/// Performs binary search in the list of AccumulatedObservations in order to find the two
+/// adjacent ones bracketing the `target_timestamp`.
 ///
 /// # Arguments
 /// * `observations` - A reference to the KeyValueStore holding AccumulatedObservation instances,
@@ -564,26 +4368,32 @@ pub fn accumulated_log(
 /// * `target_timestamp` - The specific timestamp for which an observation is sought.
 ///
 /// # Returns
-/// Returns an AccumulatedObservation instance. This will either be the observation that exactly
-/// matches the `target_timestamp`, or an interpolated observation between the two closest
-/// timestamps.
-fn binary_search_and_interpolation(
+/// Returns the left and right neighbors of the `target_timestamp`. Both are the same observation
+/// if it exactly matches the `target_timestamp`, or if it is the only one stored. The latter
+/// is handled before the search, which would otherwise pair the observation with itself and
+/// interpolate over a zero width.
+fn binary_search_neighbors(
     observations: &KeyValueStore<u16, AccumulatedObservation>,
     oldest_index: u16,
     observations_stored: u16,
     target_timestamp: u64,
-) -> AccumulatedObservation {
+) -> (AccumulatedObservation, AccumulatedObservation) {
+    if observations_stored == 1 {
+        let observation = observations.get(&oldest_index).unwrap().clone();
+        return (observation.clone(), observation);
+    }
+
     // The binary search is always called with target_timestamp within the range [left, right]
     let mut left = oldest_index;
     let mut right = left + observations_stored - 1;
 
-    let (o_left, o_right) = loop {
+    loop {
         let mid = (left + right) / 2;
         let index_mid = mid % observations_stored;
         let observation_mid = observations.get(&index_mid).unwrap();
 
         if observation_mid.timestamp == target_timestamp {
-            return observation_mid.clone();
+            return (observation_mid.clone(), observation_mid.clone());
         }
 
         // This situation occurs only when left and right are adjacent (index distance < 2).
@@ -601,9 +4411,9 @@ fn binary_search_and_interpolation(
             // because the right was the previous mid, and we always check if mid is the target first.
             // Only in the first iteration can mid be left and the target be right simultaneously.
             if observation_right.timestamp == target_timestamp {
-                return observation_right.clone();
+                return (observation_right.clone(), observation_right.clone());
             }
-            break (observation_mid, observation_right);
+            break (observation_mid.clone(), observation_right.clone());
         }
 
         // In this binary search variant, we avoid adjusting mid by +1 or -1 because we will interpolate between the final two elements.
@@ -615,20 +4425,6 @@ fn binary_search_and_interpolation(
         } else {
             right = mid;
         }
-    };
-
-    // Interpolation
-    let price_sqrt_log_acc = linear_interpolation(
-        o_left.timestamp,
-        o_right.timestamp,
-        o_left.price_sqrt_log_acc,
-        o_right.price_sqrt_log_acc,
-        target_timestamp,
-    );
-
-    AccumulatedObservation {
-        timestamp: target_timestamp,
-        price_sqrt_log_acc,
     }
 }
 
@@ -655,6 +4451,47 @@ pub fn linear_interpolation(
     y_left + slope * (x_target - x_left)
 }
 
+/// Performs linear interpolation between two points like `linear_interpolation`, but multiplies
+/// before dividing and returns the result at full `PreciseDecimal` precision.
+///
+/// # Returns
+/// * `PreciseDecimal` - The interpolated y-coordinate (value) of the target point, or `y_left` if
+/// both points have the same x-coordinate.
+fn precise_linear_interpolation(
+    x_left: u64,
+    x_right: u64,
+    y_left: Decimal,
+    y_right: Decimal,
+    x_target: u64,
+) -> PreciseDecimal {
+    let y_left = PreciseDecimal::from(y_left);
+    if x_left == x_right {
+        return y_left;
+    }
+    y_left + (PreciseDecimal::from(y_right) - y_left) * (x_target - x_left) / (x_right - x_left)
+}
+
+/// Performs linear interpolation of the accumulated inverse price between two points like
+/// `linear_interpolation`. A saturated right accumulator stays saturated, so that averages
+/// involving it are rejected instead of being derived from a meaningless slope.
+///
+/// # Returns
+/// * `PreciseDecimal` - The interpolated accumulated inverse price of the target point.
+fn inverse_linear_interpolation(
+    x_left: u64,
+    x_right: u64,
+    y_left: PreciseDecimal,
+    y_right: PreciseDecimal,
+    x_target: u64,
+) -> PreciseDecimal {
+    if y_right == PreciseDecimal::MAX {
+        return PreciseDecimal::MAX;
+    }
+    // Dividing first keeps the product below the difference of the accumulators
+    let slope = (y_right - y_left) / (x_right - x_left);
+    y_left + slope * (x_target - x_left)
+}
+
 /// Calculates the arithmetic mean between two points.
 ///
 /// # Arguments
@@ -669,8 +4506,40 @@ pub fn arithmetic_mean(x_left: u64, x_right: u64, y_left: Decimal, y_right: Deci
     (y_right - y_left) / (x_right - x_left)
 }
 
+/// Calculates the arithmetic mean of the inverse of a value between two points, whose
+/// y-coordinates accumulate that inverse, i.e. the inverse of the harmonic mean of the value.
+///
+/// # Arguments
+/// * `x_left` - The x-coordinate of the left point.
+/// * `x_right` - The x-coordinate of the right point.
+/// * `y_left` - The y-coordinate (accumulated inverse) of the left point.
+/// * `y_right` - The y-coordinate (accumulated inverse) of the right point.
+///
+/// # Returns
+/// * `Option<PreciseDecimal>` - The mean of the inverse, or `None` if the right accumulator is
+/// saturated or the accumulated inverse does not increase between the two points, since the
+/// harmonic mean would then be meaningless or infinite.
+pub fn checked_inverse_mean(
+    x_left: u64,
+    x_right: u64,
+    y_left: PreciseDecimal,
+    y_right: PreciseDecimal,
+) -> Option<PreciseDecimal> {
+    if y_right == PreciseDecimal::MAX {
+        return None;
+    }
+    let inverse_delta = y_right.checked_sub(y_left)?;
+    if inverse_delta <= pdec!(0) {
+        return None;
+    }
+    inverse_delta.checked_div(PreciseDecimal::from(x_right - x_left))
+}
+
 /// Calculates the geometric mean between two points.
 ///
+/// The slope and its exponential are calculated with `PreciseDecimal`s, so that the result keeps
+/// its significant digits even for very small values.
+///
 /// # Arguments
 /// * `x_left` - The x-coordinate of the left point.
 /// * `x_right` - The x-coordinate of the right point.
@@ -678,8 +4547,60 @@ pub fn arithmetic_mean(x_left: u64, x_right: u64, y_left: Decimal, y_right: Deci
 /// * `y_right` - The y-coordinate (value) of the right point.
 ///
 /// # Returns
-/// * `Decimal` - The geometric mean of the slope between the two points.
-pub fn geometric_mean(x_left: u64, x_right: u64, y_left: Decimal, y_right: Decimal) -> Decimal {
-    let exponent = arithmetic_mean(x_left, x_right, y_left, y_right);
-    exponent.exp().unwrap()
+/// * `PreciseDecimal` - The geometric mean of the slope between the two points.
+///
+/// # Panics
+/// Panics if the geometric mean does not fit into a `PreciseDecimal`, see
+/// `checked_geometric_mean`.
+pub fn geometric_mean(
+    x_left: u64,
+    x_right: u64,
+    y_left: Decimal,
+    y_right: Decimal,
+) -> PreciseDecimal {
+    checked_geometric_mean(x_left, x_right, y_left, y_right)
+        .expect("The geometric mean does not fit into a PreciseDecimal.")
+}
+
+/// Calculates the geometric mean between two points, see `geometric_mean`.
+///
+/// # Returns
+/// * `Option<PreciseDecimal>` - The geometric mean of the slope between the two points, or `None`
+/// if the difference of the accumulators or its exponential overflows, e.g. for an extreme price
+/// move over a short interval.
+pub fn checked_geometric_mean(
+    x_left: u64,
+    x_right: u64,
+    y_left: Decimal,
+    y_right: Decimal,
+) -> Option<PreciseDecimal> {
+    let exponent = PreciseDecimal::from(y_right.checked_sub(y_left)?)
+        .checked_div(PreciseDecimal::from(x_right - x_left))?;
+    exponent.exp()
+}
+
+/// Returns the price, i.e. the square of `price_sqrt` truncated to a `Decimal`, or `None` if it
+/// does not fit into a `Decimal`.
+fn checked_price(price_sqrt: PreciseDecimal) -> Option<Decimal> {
+    price_sqrt
+        .checked_mul(price_sqrt)?
+        .checked_truncate(RoundingMode::ToNegativeInfinity)
+}
+
+/// Resolves an interval end of `INTERVAL_END_NOW` to the current time (rounded down to the
+/// minute) in seconds, leaving any other end unchanged.
+fn resolve_interval_end(end_seconds: u64) -> u64 {
+    if end_seconds == INTERVAL_END_NOW {
+        Clock::time_in_minutes() * 60
+    } else {
+        end_seconds
+    }
+}
+
+/// Aborts an interval query whose average price does not fit into a `Decimal`.
+fn panic_price_overflow(start_seconds: u64, end_seconds: u64) -> ! {
+    panic!(
+        "The average price over interval [{}, {}] does not fit into a Decimal.",
+        start_seconds, end_seconds
+    )
 }
@@ -1,11 +1,15 @@
 use common::pools::SwapType;
 use common::time::Time;
-use oracle::{AccumulatedObservation, ObservationInterval, Oracle};
+use oracle::{
+    AccumulatedObservation, CompressedOracleSnapshot, ObservationInterval,
+    ObservationIntervalDetailed, Oracle, OracleError, OracleMetadata, OracleSnapshot,
+    SpotPriceEvent, UnixSeconds,
+};
 use scrypto::prelude::*;
 
 pub const OBSERVATIONS_LIMIT_TEST: u16 = 10; // For testing
 
-// AfterInstantiateState, AfterSwapState and HookCall can be imported normally from flex_pool_hooks package.
+// AfterInstantiateState, BeforeSwapState, AfterSwapState and HookCall can be imported normally from flex_pool_hooks package.
 // They are copied in this case to avoid circular imports. A normal hook is not imported by the pool itself
 // which is the case for the oracle. This TestOracle is not a compatible hook but freely inspired by the interface.
 
@@ -19,6 +23,16 @@ pub struct AfterInstantiateState {
     pub flash_loan_fee_rate: Decimal,
 }
 
+#[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
+pub struct BeforeSwapState {
+    pub pool_address: ComponentAddress,
+    pub swap_type: SwapType,
+    pub price_sqrt: PreciseDecimal,
+    pub active_liquidity: PreciseDecimal,
+    pub input_fee_rate: Decimal,
+    pub fee_protocol_share: Decimal,
+}
+
 #[derive(ScryptoSbor, ManifestSbor, Clone, Debug, PartialEq)]
 pub struct AfterSwapState {
     pub pool_address: ComponentAddress,
@@ -47,11 +61,23 @@ pub enum HookCall {
     AfterRemoveLiquidity,
 }
 
+/// Asserts that a pool reported a positive `price_sqrt`, so that an invalid price fails at the hook
+/// call naming the pool instead of later in the logarithm of the accumulation.
+fn assert_price_sqrt_positive(pool_address: ComponentAddress, price_sqrt: PreciseDecimal) {
+    assert!(
+        price_sqrt > pdec!(0),
+        "The pool {:?} reported a non-positive price_sqrt of {}.",
+        pool_address,
+        price_sqrt
+    );
+}
+
 /*
 This is not a production grade hook, but is solely for testing the oracle
 */
 #[blueprint]
 #[types(u16, AccumulatedObservation)]
+#[events(SpotPriceEvent)]
 mod test_oracle {
     enable_method_auth! {
         roles {
@@ -61,12 +87,103 @@ mod test_oracle {
             get_calls => PUBLIC;
             observations_limit => PUBLIC;
             observation => PUBLIC;
+            observation_ago => PUBLIC;
+            spot_price => PUBLIC;
+            observation_neighbors => PUBLIC;
+            interpolation_error_bound => PUBLIC;
+            estimate_query_reads => PUBLIC;
             observation_intervals => PUBLIC;
+            observation_intervals_dedup => PUBLIC;
+            observation_intervals_cached => PUBLIC;
+            observation_interval => PUBLIC;
+            twap_last => PUBLIC;
+            try_twap_last => PUBLIC;
+            twap_last_cached => PUBLIC;
+            twap_full_history => PUBLIC;
+            try_twap_full_history => PUBLIC;
+            observation_intervals_detailed => PUBLIC;
+            interval_endpoints => PUBLIC;
+            accumulator_delta => PUBLIC;
+            observation_intervals_harmonic => PUBLIC;
+            try_observation_interval_harmonic => PUBLIC;
+            observation_intervals_inverse => PUBLIC;
+            observation_inverse => PUBLIC;
+            observation_precise => PUBLIC;
+            midpoint_observation => PUBLIC;
+            range_prices => PUBLIC;
+            observation_intervals_with_confidence => PUBLIC;
+            twap_prices => PUBLIC;
+            composite_twap => PUBLIC;
+            chart_series => PUBLIC;
+            downsample => PUBLIC;
+            try_observation => PUBLIC;
+            try_observation_intervals => PUBLIC;
+            try_observation_interval => PUBLIC;
+            average_liquidity => PUBLIC;
+            average_fee_rate => PUBLIC;
+            variance => PUBLIC;
+            log_returns => PUBLIC;
+            volume => PUBLIC;
+            swap_count_in_range => PUBLIC;
             observations_stored => PUBLIC;
             last_observation_index => PUBLIC;
+            assert_invariants => PUBLIC;
+            verify_storage => PUBLIC;
+            observation_at_index => PUBLIC;
+            spot_log_price_between => PUBLIC;
+            twap_between_indices => PUBLIC;
+            observation_back => PUBLIC;
+            exact_observation => PUBLIC;
+            observation_count_in_range => PUBLIC;
             oldest_observation_at => PUBLIC;
+            oldest_observation_timestamp => PUBLIC;
+            observation_typed => PUBLIC;
+            observation_intervals_typed => PUBLIC;
+            last_finalized_timestamp => PUBLIC;
+            extrapolation_age_seconds => PUBLIC;
+            observation_timestamps => PUBLIC;
+            observable_range_seconds => PUBLIC;
+            observation_gaps => PUBLIC;
+            max_extrapolation_seconds => PUBLIC;
+            max_interpolation_gap_minutes => PUBLIC;
+            max_interval_minutes => PUBLIC;
+            price_sqrt_band => PUBLIC;
+            price_sqrt_floor => PUBLIC;
+            min_spacing_minutes => PUBLIC;
+            min_retained_minutes => PUBLIC;
+            max_observation_gap_minutes => PUBLIC;
+            emit_spot_events => PUBLIC;
+            token_pair => PUBLIC;
+            price_sqrt_log_acc_offset => PUBLIC;
+            snapshot => PUBLIC;
+            compressed_snapshot => PUBLIC;
+            metadata => PUBLIC;
+            swap_price_sqrt => PUBLIC;
             after_instantiate => restrict_to: [hook_admin];
+            before_swap => restrict_to: [hook_admin];
             after_swap => restrict_to: [hook_admin];
+            observe_at => restrict_to: [hook_admin];
+            refresh_from_pool => restrict_to: [hook_admin];
+            observe_many => restrict_to: [hook_admin];
+            set_max_extrapolation_seconds => restrict_to: [hook_admin];
+            set_max_interpolation_gap_minutes => restrict_to: [hook_admin];
+            set_max_interval_minutes => restrict_to: [hook_admin];
+            set_price_sqrt_band => restrict_to: [hook_admin];
+            set_price_sqrt_floor => restrict_to: [hook_admin];
+            set_min_spacing_minutes => restrict_to: [hook_admin];
+            set_min_retained_minutes => restrict_to: [hook_admin];
+            set_max_observation_gap_minutes => restrict_to: [hook_admin];
+            set_emit_spot_events => restrict_to: [hook_admin];
+            increase_capacity => restrict_to: [hook_admin];
+            try_increase_capacity => restrict_to: [hook_admin];
+            reserve_capacity => restrict_to: [hook_admin];
+            decrease_capacity => restrict_to: [hook_admin];
+            freeze_capacity_at_current => restrict_to: [hook_admin];
+            rebase => restrict_to: [hook_admin];
+            clear_observation_at_index => restrict_to: [hook_admin];
+            clear_sub_observations => restrict_to: [hook_admin];
+            seed_observations => restrict_to: [hook_admin];
+            sub_observation_state => restrict_to: [hook_admin];
         }
     }
     struct TestOracle {
@@ -78,24 +195,57 @@ mod test_oracle {
 
         oracle: Oracle,
 
+        price_sqrt_before_swap: Option<PreciseDecimal>,
         last_price_sqrt: PreciseDecimal,
     }
 
     impl TestOracle {
         pub fn instantiate() -> (Global<TestOracle>, Bucket) {
+            Self::instantiate_with_oracle(Oracle::new(OBSERVATIONS_LIMIT_TEST))
+        }
+
+        /// Instantiates the hook with an oracle using the default observations limit.
+        pub fn instantiate_with_default_limit() -> (Global<TestOracle>, Bucket) {
+            Self::instantiate_with_oracle(Oracle::with_default_limit())
+        }
+
+        /// Instantiates the hook with an oracle using the given observations limit.
+        pub fn instantiate_with_limit(observations_limit: u16) -> (Global<TestOracle>, Bucket) {
+            Self::instantiate_with_oracle(Oracle::new(observations_limit))
+        }
+
+        /// Instantiates the hook with the oracle state migrated from another component.
+        pub fn instantiate_from_snapshot(snapshot: OracleSnapshot) -> (Global<TestOracle>, Bucket) {
+            Self::instantiate_with_oracle(Oracle::restore(snapshot))
+        }
+
+        /// Instantiates the hook with the oracle state migrated from another component via a
+        /// compressed snapshot.
+        pub fn instantiate_from_compressed_snapshot(
+            compressed: CompressedOracleSnapshot,
+        ) -> (Global<TestOracle>, Bucket) {
+            Self::instantiate_with_oracle(Oracle::restore_compressed(compressed))
+        }
+
+        fn instantiate_with_oracle(oracle: Oracle) -> (Global<TestOracle>, Bucket) {
             let hook_badge = ResourceBuilder::new_fungible(OwnerRole::None)
                 .divisibility(DIVISIBILITY_NONE)
                 .mint_initial_supply(1);
 
             let hook_global = (Self {
-                calls: vec![HookCall::AfterInstantiate, HookCall::AfterSwap],
+                calls: vec![
+                    HookCall::AfterInstantiate,
+                    HookCall::BeforeSwap,
+                    HookCall::AfterSwap,
+                ],
 
                 pool_address: None,
                 x_address: None,
                 y_address: None,
 
-                oracle: Oracle::new(OBSERVATIONS_LIMIT_TEST),
+                oracle,
 
+                price_sqrt_before_swap: None,
                 last_price_sqrt: pdec!(0),
             })
             .instantiate()
@@ -130,23 +280,94 @@ mod test_oracle {
             self.pool_address = Some(state.pool_address);
             self.x_address = Some(state.x_address);
             self.y_address = Some(state.y_address);
+            self.oracle.set_token_pair(state.x_address, state.y_address);
 
             (state,)
         }
 
+        pub fn before_swap(
+            &mut self,
+            swap_state: BeforeSwapState,
+            input_bucket: Bucket,
+        ) -> (BeforeSwapState, Bucket) {
+            self.assert_instantiated();
+            assert_price_sqrt_positive(swap_state.pool_address, swap_state.price_sqrt);
+            self.oracle.observe_before_swap(
+                swap_state.price_sqrt,
+                swap_state.active_liquidity,
+                swap_state.input_fee_rate,
+            );
+            self.price_sqrt_before_swap = Some(swap_state.price_sqrt);
+
+            (swap_state, input_bucket)
+        }
+
         pub fn after_swap(
             &mut self,
             swap_state: AfterSwapState,
             input_bucket: Bucket,
         ) -> (AfterSwapState, Bucket) {
+            self.assert_instantiated();
+            assert_price_sqrt_positive(swap_state.pool_address, swap_state.price_sqrt);
             debug!("STORED: {}", self.oracle.observations_stored());
             debug!("TIME CURRENT: {}", Clock::time_in_minutes());
 
-            self.oracle.observe(swap_state.price_sqrt);
+            // The volume is recorded in token x, i.e. the output of a buy and the input of a sell
+            let volume = match swap_state.swap_type {
+                SwapType::BuyX => swap_state.output_amount,
+                SwapType::SellX => swap_state.input_amount,
+            };
+            self.oracle.observe(
+                swap_state.price_sqrt,
+                swap_state.active_liquidity,
+                volume,
+                swap_state.input_fee_rate,
+            );
+            self.last_price_sqrt = swap_state.price_sqrt;
 
             (swap_state, input_bucket)
         }
 
+        /// Asserts that `after_instantiate` has been called, so that observations are never
+        /// recorded without knowing the pool and token pair they belong to.
+        fn assert_instantiated(&self) {
+            assert!(
+                self.pool_address.is_some(),
+                "The oracle hook cannot observe swaps before `after_instantiate` has been called."
+            );
+        }
+
+        /// Returns the `price_sqrt` before (if reported) and after the last swap, allowing to
+        /// compute its price impact.
+        pub fn swap_price_sqrt(&self) -> (Option<PreciseDecimal>, PreciseDecimal) {
+            (self.price_sqrt_before_swap, self.last_price_sqrt)
+        }
+
+        /// Records the current price of the pool without a swap, e.g. pushed by a keeper.
+        pub fn refresh_from_pool(&mut self, current_price_sqrt: PreciseDecimal) {
+            self.oracle.refresh_from_pool(current_price_sqrt);
+        }
+
+        /// Feeds a historical price into the oracle, e.g. to back-fill it during a migration.
+        pub fn observe_at(
+            &mut self,
+            price_sqrt: PreciseDecimal,
+            active_liquidity: PreciseDecimal,
+            volume: Decimal,
+            instant: Instant,
+        ) {
+            self.oracle
+                .observe_at(price_sqrt, active_liquidity, volume, instant);
+        }
+
+        /// Feeds a batch of historical prices into the oracle, see `observe_at`.
+        pub fn observe_many(
+            &mut self,
+            entries: Vec<(Instant, PreciseDecimal, PreciseDecimal, Decimal)>,
+        ) {
+            self.oracle.observe_many(entries);
+        }
+
         /// Returns an AccumulatedObservation for a given timestamp. A few scenarios can happen:
         /// - If an observation exists for the provided timestamp, it is returned
         /// - If no observation matches the timestamp, but the timestamp is within the range captured by the oracle,
@@ -158,6 +379,35 @@ mod test_oracle {
             self.oracle.observation(seconds)
         }
 
+        pub fn observation_typed(&self, seconds: UnixSeconds) -> AccumulatedObservation {
+            self.oracle.observation(seconds)
+        }
+
+        /// Returns the observation at the given number of seconds before the current time.
+        pub fn observation_ago(&self, seconds_ago: u64) -> AccumulatedObservation {
+            self.oracle.observation_ago(seconds_ago)
+        }
+
+        pub fn spot_price(&self, seconds: u64) -> Decimal {
+            self.oracle.spot_price(seconds)
+        }
+
+        /// Returns the two stored observations `observation` interpolates between.
+        pub fn observation_neighbors(
+            &self,
+            seconds: u64,
+        ) -> (AccumulatedObservation, AccumulatedObservation) {
+            self.oracle.observation_neighbors(seconds)
+        }
+
+        pub fn interpolation_error_bound(&self, seconds: u64) -> Decimal {
+            self.oracle.interpolation_error_bound(seconds)
+        }
+
+        pub fn estimate_query_reads(&self, seconds: u64) -> u16 {
+            self.oracle.estimate_query_reads(seconds)
+        }
+
         /// For a given timestamp pair tuple, calculates the average price_sqrt.
         /// Receives a vector of such pairs, and returns ObservationInterval's.
         pub fn observation_intervals(
@@ -167,6 +417,217 @@ mod test_oracle {
             self.oracle.observation_intervals(intervals)
         }
 
+        pub fn observation_intervals_typed(
+            &self,
+            intervals: Vec<(UnixSeconds, UnixSeconds)>,
+        ) -> Vec<ObservationInterval> {
+            self.oracle.observation_intervals_typed(intervals)
+        }
+
+        /// Same as `observation_intervals`, calculating repeated intervals only once.
+        pub fn observation_intervals_dedup(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<ObservationInterval> {
+            self.oracle.observation_intervals_dedup(intervals)
+        }
+
+        pub fn observation_intervals_cached(
+            &mut self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<ObservationInterval> {
+            self.oracle.observation_intervals_cached(intervals)
+        }
+
+        /// For a single timestamp pair, calculates the average price_sqrt.
+        pub fn observation_interval(
+            &self,
+            start_seconds: u64,
+            end_seconds: u64,
+        ) -> ObservationInterval {
+            self.oracle.observation_interval(start_seconds, end_seconds)
+        }
+
+        /// Calculates the average price_sqrt over the last `window_seconds`.
+        pub fn twap_last(&self, window_seconds: u64) -> ObservationInterval {
+            self.oracle.twap_last(window_seconds)
+        }
+
+        /// Same as `twap_last`, but returns an `OracleError` instead of panicking.
+        pub fn try_twap_last(
+            &self,
+            window_seconds: u64,
+        ) -> Result<ObservationInterval, OracleError> {
+            self.oracle.try_twap_last(window_seconds)
+        }
+
+        /// Calculates the average price_sqrt over the entire stored history.
+        pub fn twap_full_history(&self) -> ObservationInterval {
+            self.oracle.twap_full_history()
+        }
+
+        /// Same as `twap_full_history`, but returns an `OracleError` instead of panicking.
+        pub fn try_twap_full_history(&self) -> Result<ObservationInterval, OracleError> {
+            self.oracle.try_twap_full_history()
+        }
+
+        /// Same as `twap_last`, memoizing the result for the current minute.
+        pub fn twap_last_cached(&mut self, window_seconds: u64) -> ObservationInterval {
+            self.oracle.twap_last_cached(window_seconds)
+        }
+
+        /// Same as `observation_intervals`, additionally returning the accumulators used.
+        pub fn observation_intervals_detailed(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<ObservationIntervalDetailed> {
+            self.oracle.observation_intervals_detailed(intervals)
+        }
+
+        pub fn interval_endpoints(
+            &self,
+            start_seconds: u64,
+            end_seconds: u64,
+        ) -> (AccumulatedObservation, AccumulatedObservation) {
+            self.oracle.interval_endpoints(start_seconds, end_seconds)
+        }
+
+        pub fn accumulator_delta(&self, start_seconds: u64, end_seconds: u64) -> (Decimal, u64) {
+            self.oracle.accumulator_delta(start_seconds, end_seconds)
+        }
+
+        /// For a given timestamp pair tuple, calculates the average price (y per x).
+        pub fn twap_prices(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<Decimal> {
+            self.oracle.twap_prices(intervals)
+        }
+
+        /// For several timestamp pair tuples, calculates the duration-weighted average price.
+        pub fn composite_twap(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Decimal {
+            self.oracle.composite_twap(intervals)
+        }
+
+        pub fn chart_series(
+            &self,
+            start_seconds: u64,
+            end_seconds: u64,
+            step_seconds: u64,
+        ) -> Vec<(u64, Decimal)> {
+            self.oracle
+                .chart_series(start_seconds, end_seconds, step_seconds)
+        }
+
+        pub fn downsample(&self, step_minutes: u64) -> Vec<AccumulatedObservation> {
+            self.oracle.downsample(step_minutes)
+        }
+
+        /// For a given timestamp pair tuple, calculates the harmonic mean of the price.
+        pub fn observation_intervals_harmonic(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<ObservationInterval> {
+            self.oracle.observation_intervals_harmonic(intervals)
+        }
+
+        pub fn try_observation_interval_harmonic(
+            &self,
+            start_seconds: u64,
+            end_seconds: u64,
+        ) -> Result<ObservationInterval, OracleError> {
+            self.oracle
+                .try_observation_interval_harmonic(start_seconds, end_seconds)
+        }
+
+        pub fn observation_intervals_inverse(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<ObservationInterval> {
+            self.oracle.observation_intervals_inverse(intervals)
+        }
+
+        pub fn observation_inverse(&self, seconds: u64) -> AccumulatedObservation {
+            self.oracle.observation_inverse(seconds)
+        }
+
+        pub fn observation_precise(&self, seconds: u64) -> (u64, PreciseDecimal) {
+            self.oracle.observation_precise(seconds)
+        }
+
+        pub fn midpoint_observation(&self) -> Option<AccumulatedObservation> {
+            self.oracle.midpoint_observation()
+        }
+
+        pub fn range_prices(&self) -> Option<(Decimal, Decimal)> {
+            self.oracle.range_prices()
+        }
+
+        /// For a given timestamp pair tuple, calculates the average price_sqrt and the share of
+        /// observed minutes.
+        pub fn observation_intervals_with_confidence(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Vec<(ObservationInterval, Decimal)> {
+            self.oracle.observation_intervals_with_confidence(intervals)
+        }
+
+        /// Same as `observation`, but returns an `OracleError` instead of panicking.
+        pub fn try_observation(&self, seconds: u64) -> Result<AccumulatedObservation, OracleError> {
+            self.oracle.try_observation(seconds)
+        }
+
+        /// Same as `observation_intervals`, but returns an `OracleError` instead of panicking.
+        pub fn try_observation_intervals(
+            &self,
+            intervals: Vec<(u64, u64)>, // In Unix seconds
+        ) -> Result<Vec<ObservationInterval>, OracleError> {
+            self.oracle.try_observation_intervals(intervals)
+        }
+
+        /// Same as `observation_interval`, but returns an `OracleError` instead of panicking.
+        pub fn try_observation_interval(
+            &self,
+            start_seconds: u64,
+            end_seconds: u64,
+        ) -> Result<ObservationInterval, OracleError> {
+            self.oracle
+                .try_observation_interval(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, calculates the average active liquidity.
+        pub fn average_liquidity(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+            self.oracle.average_liquidity(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, calculates the average input fee rate.
+        pub fn average_fee_rate(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+            self.oracle.average_fee_rate(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, calculates the variance of the log price per minute.
+        pub fn variance(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+            self.oracle.variance(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, returns the log return of the price of every minute.
+        pub fn log_returns(&self, start_seconds: u64, end_seconds: u64) -> Vec<(u64, Decimal)> {
+            self.oracle.log_returns(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, calculates the volume traded in token x.
+        pub fn volume(&self, start_seconds: u64, end_seconds: u64) -> Decimal {
+            self.oracle.volume(start_seconds, end_seconds)
+        }
+
+        /// For a single timestamp pair, counts the swaps performed.
+        pub fn swap_count_in_range(&self, start_seconds: u64, end_seconds: u64) -> u64 {
+            self.oracle.swap_count_in_range(start_seconds, end_seconds)
+        }
+
         pub fn observations_limit(&self) -> u16 {
             self.oracle.observations_limit()
         }
@@ -179,9 +640,221 @@ mod test_oracle {
             self.oracle.oldest_observation_at()
         }
 
+        pub fn oldest_observation_timestamp(&self) -> Option<UnixSeconds> {
+            self.oracle.oldest_observation_timestamp()
+        }
+
+        pub fn last_finalized_timestamp(&self) -> Option<u64> {
+            self.oracle.last_finalized_timestamp()
+        }
+
+        pub fn extrapolation_age_seconds(&self, seconds: u64) -> u64 {
+            self.oracle.extrapolation_age_seconds(seconds)
+        }
+
+        pub fn observation_timestamps(&self) -> Vec<u64> {
+            self.oracle.observation_timestamps()
+        }
+
+        pub fn observable_range_seconds(&self) -> Option<(u64, u64)> {
+            self.oracle.observable_range_seconds()
+        }
+
+        pub fn observation_gaps(&self) -> Vec<(u64, u64)> {
+            self.oracle.observation_gaps()
+        }
+
+        pub fn max_extrapolation_seconds(&self) -> Option<u64> {
+            self.oracle.max_extrapolation_seconds()
+        }
+
+        pub fn set_max_extrapolation_seconds(&mut self, max_extrapolation_seconds: Option<u64>) {
+            self.oracle
+                .set_max_extrapolation_seconds(max_extrapolation_seconds);
+        }
+
+        pub fn max_interpolation_gap_minutes(&self) -> Option<u64> {
+            self.oracle.max_interpolation_gap_minutes()
+        }
+
+        pub fn set_max_interpolation_gap_minutes(
+            &mut self,
+            max_interpolation_gap_minutes: Option<u64>,
+        ) {
+            self.oracle
+                .set_max_interpolation_gap_minutes(max_interpolation_gap_minutes);
+        }
+
+        pub fn max_interval_minutes(&self) -> Option<u64> {
+            self.oracle.max_interval_minutes()
+        }
+
+        pub fn set_max_interval_minutes(&mut self, max_interval_minutes: Option<u64>) {
+            self.oracle.set_max_interval_minutes(max_interval_minutes);
+        }
+
+        pub fn price_sqrt_band(&self) -> Option<PreciseDecimal> {
+            self.oracle.price_sqrt_band()
+        }
+
+        pub fn set_price_sqrt_band(&mut self, price_sqrt_band: Option<PreciseDecimal>) {
+            self.oracle.set_price_sqrt_band(price_sqrt_band);
+        }
+
+        pub fn price_sqrt_floor(&self) -> Option<PreciseDecimal> {
+            self.oracle.price_sqrt_floor()
+        }
+
+        pub fn set_price_sqrt_floor(&mut self, price_sqrt_floor: Option<PreciseDecimal>) {
+            self.oracle.set_price_sqrt_floor(price_sqrt_floor);
+        }
+
+        pub fn min_spacing_minutes(&self) -> Option<u64> {
+            self.oracle.min_spacing_minutes()
+        }
+
+        pub fn set_min_spacing_minutes(&mut self, min_spacing_minutes: Option<u64>) {
+            self.oracle.set_min_spacing_minutes(min_spacing_minutes);
+        }
+
+        pub fn min_retained_minutes(&self) -> Option<u64> {
+            self.oracle.min_retained_minutes()
+        }
+
+        pub fn set_min_retained_minutes(&mut self, min_retained_minutes: Option<u64>) {
+            self.oracle.set_min_retained_minutes(min_retained_minutes);
+        }
+
+        pub fn max_observation_gap_minutes(&self) -> Option<u64> {
+            self.oracle.max_observation_gap_minutes()
+        }
+
+        pub fn set_max_observation_gap_minutes(
+            &mut self,
+            max_observation_gap_minutes: Option<u64>,
+        ) {
+            self.oracle
+                .set_max_observation_gap_minutes(max_observation_gap_minutes);
+        }
+
+        pub fn emit_spot_events(&self) -> bool {
+            self.oracle.emit_spot_events()
+        }
+
+        pub fn set_emit_spot_events(&mut self, emit_spot_events: bool) {
+            self.oracle.set_emit_spot_events(emit_spot_events);
+        }
+
+        pub fn token_pair(&self) -> Option<(ResourceAddress, ResourceAddress)> {
+            self.oracle.token_pair()
+        }
+
+        pub fn increase_capacity(&mut self, observations_limit: u16) {
+            self.oracle.increase_capacity(observations_limit);
+        }
+
+        pub fn try_increase_capacity(
+            &mut self,
+            observations_limit: u16,
+        ) -> Result<(), OracleError> {
+            self.oracle.try_increase_capacity(observations_limit)
+        }
+
+        pub fn reserve_capacity(&mut self, observations_limit: u16) {
+            self.oracle.reserve_capacity(observations_limit);
+        }
+
+        pub fn decrease_capacity(&mut self, observations_limit: u16) {
+            self.oracle.decrease_capacity(observations_limit);
+        }
+
+        pub fn freeze_capacity_at_current(&mut self) {
+            self.oracle.freeze_capacity_at_current();
+        }
+
+        pub fn rebase(&mut self) {
+            self.oracle.rebase();
+        }
+
+        pub fn price_sqrt_log_acc_offset(&self) -> Decimal {
+            self.oracle.price_sqrt_log_acc_offset()
+        }
+
+        /// Corrupts the oracle state by clearing a ring buffer slot, for testing `verify_storage`.
+        /// Requires the `test-utils` feature of the oracle, enabled for this test blueprint only.
+        pub fn clear_observation_at_index(&mut self, index: u16) {
+            self.oracle.clear_observation_at_index(index);
+        }
+
+        /// Requires the `test-utils` feature of the oracle, enabled for this test blueprint only.
+        pub fn clear_sub_observations(&mut self) {
+            self.oracle.clear_sub_observations();
+        }
+
+        /// Appends pre-computed observations without observing, for setting up long histories.
+        /// Requires the `test-utils` feature of the oracle, enabled for this test blueprint only.
+        pub fn seed_observations(&mut self, observations: Vec<AccumulatedObservation>) {
+            self.oracle.seed_observations(observations);
+        }
+
+        pub fn sub_observation_state(&self) -> Option<(PreciseDecimal, PreciseDecimal, u64, bool)> {
+            self.oracle.sub_observation_state()
+        }
+
+        pub fn metadata(&self) -> OracleMetadata {
+            self.oracle.metadata()
+        }
+
+        pub fn snapshot(&self) -> OracleSnapshot {
+            self.oracle.snapshot()
+        }
+
+        pub fn compressed_snapshot(&self) -> CompressedOracleSnapshot {
+            self.oracle.compressed_snapshot()
+        }
+
         // For testing
         pub fn last_observation_index(&self) -> Option<u16> {
             self.oracle.last_observation_index()
         }
+
+        /// Requires the `test-utils` feature of the oracle, enabled for this test blueprint only.
+        pub fn assert_invariants(&self) {
+            self.oracle.assert_invariants();
+        }
+
+        pub fn verify_storage(&self) -> bool {
+            self.oracle.verify_storage()
+        }
+
+        pub fn observation_at_index(&self, index: u16) -> Option<AccumulatedObservation> {
+            self.oracle.observation_at_index(index)
+        }
+
+        pub fn spot_log_price_between(&self, index_left: u16, index_right: u16) -> Decimal {
+            self.oracle.spot_log_price_between(index_left, index_right)
+        }
+
+        pub fn twap_between_indices(
+            &self,
+            oldest_offset: u16,
+            newest_offset: u16,
+        ) -> ObservationInterval {
+            self.oracle
+                .twap_between_indices(oldest_offset, newest_offset)
+        }
+
+        pub fn observation_back(&self, n: u16) -> Option<AccumulatedObservation> {
+            self.oracle.observation_back(n)
+        }
+
+        pub fn exact_observation(&self, start_seconds: u64) -> Option<AccumulatedObservation> {
+            self.oracle.exact_observation(start_seconds)
+        }
+
+        pub fn observation_count_in_range(&self, start_seconds: u64, end_seconds: u64) -> u16 {
+            self.oracle
+                .observation_count_in_range(start_seconds, end_seconds)
+        }
     }
 }
@@ -1,6 +1,9 @@
 use common::pools::SwapType;
 use lazy_static::lazy_static;
-use oracle::{AccumulatedObservation, ObservationInterval};
+use oracle::{
+    oracle::SubObservations, AccumulatedObservation, CompressedOracleSnapshot, ObservationInterval,
+    OracleSnapshot, UnixSeconds,
+};
 use pretty_assertions::assert_eq;
 use radix_engine::system::system_modules::execution_trace::ResourceSpecifier::Amount;
 use scrypto::{blueprints::consensus_manager::TimePrecision, prelude::*};
@@ -10,7 +13,7 @@ use serde_json;
 use std::fs::File;
 use std::io::Read;
 use std::mem;
-use test_oracle::test_oracle::{AfterInstantiateState, AfterSwapState, HookCall};
+use test_oracle::test_oracle::{AfterInstantiateState, AfterSwapState, BeforeSwapState, HookCall};
 
 lazy_static! {
     pub static ref TEST_DATAPOINTS: Vec<PreciseDecimal> = {
@@ -31,6 +34,8 @@ lazy_static! {
 
         prices_sqrt
     };
+    /// The active liquidity reported by the empty swap states, i.e. constant during the tests.
+    pub static ref TEST_LIQUIDITY: PreciseDecimal = pdec!(100);
     static ref DUMMY_POOL: ComponentAddress = ComponentAddress::try_from_hex(
         "c0cdeecfc24b8c7132d4636883099c5fe0764dd015e93b196030c3ce2dc2"
     )
@@ -101,6 +106,51 @@ impl Dummy for AfterInstantiateState {
     }
 }
 
+impl Dummy for BeforeSwapState {
+    fn dummy(pool_address: ComponentAddress, _: ResourceAddress, _: ResourceAddress) -> Self {
+        BeforeSwapState {
+            pool_address: pool_address,
+            price_sqrt: pdec!("1.1"),
+            active_liquidity: pdec!(107),
+            swap_type: SwapType::BuyX,
+            input_fee_rate: dec!("0.0145"),
+            fee_protocol_share: dec!("0.0193"),
+        }
+    }
+
+    fn empty(pool_address: ComponentAddress, _: ResourceAddress, _: ResourceAddress) -> Self {
+        BeforeSwapState {
+            pool_address: pool_address,
+            price_sqrt: pdec!("1"),
+            active_liquidity: pdec!(100),
+            swap_type: SwapType::BuyX,
+            input_fee_rate: dec!(0),
+            fee_protocol_share: dec!(0),
+        }
+    }
+
+    fn from_test_datapoints(
+        number_of_states: usize,
+        pool_address: ComponentAddress,
+        x_address: ResourceAddress,
+        y_address: ResourceAddress,
+    ) -> Vec<BeforeSwapState> {
+        assert!(number_of_states <= 20);
+        let ref prices_sqrt = *TEST_DATAPOINTS;
+        let mut states: Vec<BeforeSwapState> = Vec::with_capacity(number_of_states);
+
+        for i in 0..number_of_states {
+            let mut new_state = BeforeSwapState::empty(pool_address, x_address, y_address);
+
+            new_state.price_sqrt = prices_sqrt[i];
+
+            states.push(new_state);
+        }
+
+        states
+    }
+}
+
 impl Dummy for AfterSwapState {
     // fn dummy() -> Self {
     //     BeforeSwapState {
@@ -316,6 +366,28 @@ impl Approximate for AccumulatedObservation {
                 .price_sqrt_log_acc
                 .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
                 .unwrap(),
+            price_sqrt_log_sq_acc: self
+                .price_sqrt_log_sq_acc
+                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+                .unwrap(),
+            price_inverse_acc: self
+                .price_inverse_acc
+                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+                .unwrap(),
+            liquidity_acc: self
+                .liquidity_acc
+                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+                .unwrap(),
+            fee_rate_acc: self
+                .fee_rate_acc
+                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+                .unwrap(),
+            volume_acc: self
+                .volume_acc
+                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+                .unwrap(),
+            swap_count_acc: self.swap_count_acc,
+            observed_minutes_acc: self.observed_minutes_acc,
         }
     }
 }
@@ -323,13 +395,17 @@ impl Approximate for AccumulatedObservation {
 impl Approximate for ObservationInterval {
     fn approximate(&self) -> Self {
         let decimal_places = 15;
+        let price_sqrt = self
+            .price_sqrt
+            .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
+            .unwrap();
         Self {
             start: self.start,
             end: self.end,
-            price_sqrt: self
-                .price_sqrt
-                .checked_round(decimal_places, RoundingMode::ToNegativeInfinity)
-                .unwrap(),
+            duration_minutes: self.duration_minutes,
+            price_sqrt,
+            // Derived from the rounded price_sqrt, so that it does not amplify its rounding
+            price: truncate(price_sqrt * price_sqrt),
         }
     }
 }
@@ -366,6 +442,14 @@ impl OracleTestHelper {
     //     )
     // }
 
+    pub fn before_swap_state_dummy(&self) -> BeforeSwapState {
+        BeforeSwapState::dummy(
+            self.pool_address.unwrap(),
+            self.x_address(),
+            self.y_address(),
+        )
+    }
+
     pub fn after_instantiate_state_dummy(&self) -> AfterInstantiateState {
         AfterInstantiateState::dummy(
             self.pool_address.unwrap(),
@@ -410,6 +494,56 @@ impl OracleTestHelper {
         helper
     }
 
+    /// Instantiates an oracle from the given observations (timestamps in minutes, in
+    /// chronological order) stored in a ring of `observations_limit` slots ending at
+    /// `last_observation_index`, and jumps to `now_seconds`. The current minute starts at the
+    /// last observation with `price_sqrt_last`, a liquidity of 1 and an input fee rate of 1.
+    ///
+    /// This pins the exact layout of the ring, e.g. a wrapped one, so that the query logic can be
+    /// tested directly without observing swaps.
+    pub fn new_with_injected_observations(
+        observations: Vec<AccumulatedObservation>,
+        observations_limit: u16,
+        last_observation_index: u16,
+        price_sqrt_last: PreciseDecimal,
+        now_seconds: u64,
+    ) -> Self {
+        let last_instant = Instant::new((observations.last().unwrap().timestamp * 60) as i64);
+        let mut sub_observations = SubObservations::new_at(last_instant);
+        sub_observations.new_subobservation_at(
+            price_sqrt_last,
+            pdec!(1),
+            dec!(0),
+            dec!(1),
+            last_instant,
+        );
+
+        let snapshot = OracleSnapshot {
+            observations_stored: observations.len() as u16,
+            observations,
+            observations_limit,
+            last_observation_index: Some(last_observation_index),
+            sub_observations: Some(sub_observations),
+            max_extrapolation_seconds: None,
+            max_interpolation_gap_minutes: None,
+            max_interval_minutes: None,
+            price_sqrt_band: None,
+            price_sqrt_floor: None,
+            min_spacing_minutes: None,
+            min_retained_minutes: None,
+            max_observation_gap_minutes: None,
+            emit_spot_events: false,
+            token_pair: None,
+            price_sqrt_log_acc_offset: dec!(0),
+        };
+
+        let mut helper = OracleTestHelper::new();
+        helper.instantiate_from_snapshot_instant(snapshot);
+        helper.jump_to_timestamp_seconds(now_seconds);
+
+        helper
+    }
+
     pub fn new_with_swap_state_seconds(seconds: &Vec<u64>) -> Self {
         let mut helper = OracleTestHelper::new();
         helper.instantiate_instant();
@@ -476,7 +610,15 @@ impl OracleTestHelper {
         self
     }
 
+    /// Instantiates the hook and calls `after_instantiate` like the pool would, so that swaps can
+    /// be observed right away.
     pub fn instantiate_instant(&mut self) -> &mut OracleTestHelper {
+        self.instantiate_uninitialized_instant();
+        self.after_instantiate_instant()
+    }
+
+    /// Instantiates the hook without calling `after_instantiate`.
+    pub fn instantiate_uninitialized_instant(&mut self) -> &mut OracleTestHelper {
         self.instantiate();
         let receipt = self.execute_expect_success(false);
         let (oracle_address, _): (ComponentAddress, Bucket) = receipt.outputs("instantiate")[0];
@@ -489,6 +631,124 @@ impl OracleTestHelper {
         self
     }
 
+    pub fn instantiate_with_default_limit(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+        self.env.manifest_builder = manifest_builder.call_function(
+            self.env.package_address("oracle"),
+            "TestOracle",
+            "instantiate_with_default_limit",
+            manifest_args!(),
+        );
+        self.env
+            .new_instruction("instantiate_with_default_limit", 1, 0);
+        self
+    }
+
+    pub fn instantiate_with_default_limit_instant(&mut self) -> &mut OracleTestHelper {
+        self.instantiate_with_default_limit();
+        let receipt = self.execute_expect_success(false);
+        let (oracle_address, _): (ComponentAddress, Bucket) =
+            receipt.outputs("instantiate_with_default_limit")[0];
+        let hook_badge_address = receipt
+            .execution_receipt
+            .expect_commit_success()
+            .new_resource_addresses()[0];
+        self.oracle_address = Some(oracle_address);
+        self.hook_badge_address = Some(hook_badge_address);
+        self.after_instantiate_instant()
+    }
+
+    pub fn instantiate_with_limit(&mut self, observations_limit: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+        self.env.manifest_builder = manifest_builder.call_function(
+            self.env.package_address("oracle"),
+            "TestOracle",
+            "instantiate_with_limit",
+            manifest_args!(observations_limit),
+        );
+        self.env.new_instruction("instantiate_with_limit", 1, 0);
+        self
+    }
+
+    pub fn instantiate_with_limit_instant(
+        &mut self,
+        observations_limit: u16,
+    ) -> &mut OracleTestHelper {
+        self.instantiate_with_limit(observations_limit);
+        let receipt = self.execute_expect_success(false);
+        let (oracle_address, _): (ComponentAddress, Bucket) =
+            receipt.outputs("instantiate_with_limit")[0];
+        let hook_badge_address = receipt
+            .execution_receipt
+            .expect_commit_success()
+            .new_resource_addresses()[0];
+        self.oracle_address = Some(oracle_address);
+        self.hook_badge_address = Some(hook_badge_address);
+        self.after_instantiate_instant()
+    }
+
+    pub fn instantiate_from_snapshot(&mut self, snapshot: OracleSnapshot) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+        self.env.manifest_builder = manifest_builder.call_function(
+            self.env.package_address("oracle"),
+            "TestOracle",
+            "instantiate_from_snapshot",
+            manifest_args!(snapshot),
+        );
+        self.env.new_instruction("instantiate_from_snapshot", 1, 0);
+        self
+    }
+
+    pub fn instantiate_from_snapshot_instant(
+        &mut self,
+        snapshot: OracleSnapshot,
+    ) -> &mut OracleTestHelper {
+        self.instantiate_from_snapshot(snapshot);
+        let receipt = self.execute_expect_success(false);
+        let (oracle_address, _): (ComponentAddress, Bucket) =
+            receipt.outputs("instantiate_from_snapshot")[0];
+        let hook_badge_address = receipt
+            .execution_receipt
+            .expect_commit_success()
+            .new_resource_addresses()[0];
+        self.oracle_address = Some(oracle_address);
+        self.hook_badge_address = Some(hook_badge_address);
+        self.after_instantiate_instant()
+    }
+
+    pub fn instantiate_from_compressed_snapshot(
+        &mut self,
+        compressed: CompressedOracleSnapshot,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+        self.env.manifest_builder = manifest_builder.call_function(
+            self.env.package_address("oracle"),
+            "TestOracle",
+            "instantiate_from_compressed_snapshot",
+            manifest_args!(compressed),
+        );
+        self.env
+            .new_instruction("instantiate_from_compressed_snapshot", 1, 0);
+        self
+    }
+
+    pub fn instantiate_from_compressed_snapshot_instant(
+        &mut self,
+        compressed: CompressedOracleSnapshot,
+    ) -> &mut OracleTestHelper {
+        self.instantiate_from_compressed_snapshot(compressed);
+        let receipt = self.execute_expect_success(false);
+        let (oracle_address, _): (ComponentAddress, Bucket) =
+            receipt.outputs("instantiate_from_compressed_snapshot")[0];
+        let hook_badge_address = receipt
+            .execution_receipt
+            .expect_commit_success()
+            .new_resource_addresses()[0];
+        self.oracle_address = Some(oracle_address);
+        self.hook_badge_address = Some(hook_badge_address);
+        self.after_instantiate_instant()
+    }
+
     pub fn load_hook_auth(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
@@ -516,191 +776,1439 @@ impl OracleTestHelper {
         self
     }
 
+    pub fn after_instantiate_instant(&mut self) -> &mut OracleTestHelper {
+        self.load_hook_auth()
+            .after_instantiate_default()
+            .execute_expect_success(false);
+        self
+    }
+
     pub fn after_instantiate_default(&mut self) -> &mut OracleTestHelper {
         let after_instantiate_state = AfterInstantiateState::empty(
             self.pool_address.unwrap(),
             self.x_address(),
             self.y_address(),
         );
-        self.after_instantiate(after_instantiate_state);
+        self.after_instantiate(after_instantiate_state);
+        self
+    }
+
+    pub fn get_calls(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "get_calls",
+            manifest_args!(),
+        );
+        self.env.new_instruction("get_calls", 1, 0);
+        self
+    }
+
+    pub fn before_swap(
+        &mut self,
+        before_swap_state: BeforeSwapState,
+        input_address: ResourceAddress,
+        input_amount: Decimal,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, input_address, input_amount)
+            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let input_bucket = lookup.bucket(self.name("input_bucket"));
+                builder.call_method(
+                    self.oracle_address.unwrap(),
+                    "before_swap",
+                    manifest_args!(before_swap_state, input_bucket),
+                )
+            });
+
+        self.env.new_instruction("before_swap", 3, 2);
+        self
+    }
+
+    pub fn swap_price_sqrt(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "swap_price_sqrt",
+            manifest_args!(),
+        );
+        self.env.new_instruction("swap_price_sqrt", 1, 0);
+        self
+    }
+
+    pub fn after_swap(
+        &mut self,
+        after_swap_state: AfterSwapState,
+        input_address: ResourceAddress,
+        input_amount: Decimal,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, input_address, input_amount)
+            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let input_bucket = lookup.bucket(self.name("input_bucket"));
+                builder.call_method(
+                    self.oracle_address.unwrap(),
+                    "after_swap",
+                    manifest_args!(after_swap_state, input_bucket),
+                )
+            });
+
+        self.env.new_instruction("after_swap", 3, 2);
+        self
+    }
+
+    pub fn after_swap_default(&mut self) -> &mut OracleTestHelper {
+        let input_address = self.y_address();
+        let input_amount = dec!("7.463543");
+
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, input_address, input_amount)
+            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let input_bucket = lookup.bucket(self.name("input_bucket"));
+                builder.call_method(
+                    self.oracle_address.unwrap(),
+                    "after_swap",
+                    manifest_args!(self.after_swap_state_dummy(), input_bucket),
+                )
+            });
+
+        self.env.new_instruction("after_swap", 3, 2);
+        self
+    }
+
+    pub fn refresh_from_pool(
+        &mut self,
+        current_price_sqrt: PreciseDecimal,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "refresh_from_pool",
+            manifest_args!(current_price_sqrt),
+        );
+        self.env.new_instruction("refresh_from_pool", 1, 0);
+        self
+    }
+
+    pub fn observe_at(
+        &mut self,
+        price_sqrt: PreciseDecimal,
+        active_liquidity: PreciseDecimal,
+        volume: Decimal,
+        seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observe_at",
+            manifest_args!(
+                price_sqrt,
+                active_liquidity,
+                volume,
+                Instant::new(seconds as i64)
+            ),
+        );
+        self.env.new_instruction("observe_at", 1, 0);
+        self
+    }
+
+    pub fn observe_many(
+        &mut self,
+        entries: Vec<(u64, PreciseDecimal, PreciseDecimal, Decimal)>,
+    ) -> &mut OracleTestHelper {
+        let entries: Vec<(Instant, PreciseDecimal, PreciseDecimal, Decimal)> = entries
+            .into_iter()
+            .map(|(seconds, price_sqrt, active_liquidity, volume)| {
+                (
+                    Instant::new(seconds as i64),
+                    price_sqrt,
+                    active_liquidity,
+                    volume,
+                )
+            })
+            .collect();
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observe_many",
+            manifest_args!(entries),
+        );
+        self.env.new_instruction("observe_many", 1, 0);
+        self
+    }
+
+    /// Back-fills the oracle with the test datapoints at the given swap seconds, without moving
+    /// the ledger clock.
+    pub fn observe_at_swap_seconds(&mut self, seconds: &Vec<u64>) -> &mut OracleTestHelper {
+        self.load_hook_auth();
+        for (&seconds_, &price_sqrt) in seconds.iter().zip(TEST_DATAPOINTS.iter()) {
+            self.observe_at(price_sqrt, *TEST_LIQUIDITY, dec!(0), seconds_);
+        }
+        self
+    }
+
+    /*     pub fn after_swap(
+        &mut self,
+        after_swap_state: AfterSwapState,
+        input_address: ResourceAddress,
+        input_amount: Decimal
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env.manifest_builder = manifest_builder
+            .withdraw_from_account(self.env.account, input_address, input_amount)
+            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
+            .with_name_lookup(|builder, lookup| {
+                let input_bucket = lookup.bucket(self.name("input_bucket"));
+                builder.call_method(
+                    self.oracle_address.unwrap(),
+                    "after_swap",
+                    manifest_args!(after_swap_state, input_bucket)
+                )
+            });
+
+        self.env.new_instruction("after_swap", 3, 2);
+        self
+    } */
+
+    pub fn observation(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("observation", 1, 0);
+        self
+    }
+
+    pub fn observation_typed(&mut self, seconds: UnixSeconds) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_typed",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("observation_typed", 1, 0);
+        self
+    }
+
+    pub fn observation_ago(&mut self, seconds_ago: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_ago",
+            manifest_args!(seconds_ago),
+        );
+        self.env.new_instruction("observation_ago", 1, 0);
+        self
+    }
+
+    pub fn spot_price(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "spot_price",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("spot_price", 1, 0);
+        self
+    }
+
+    pub fn observation_neighbors(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_neighbors",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("observation_neighbors", 1, 0);
+        self
+    }
+
+    pub fn interpolation_error_bound(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "interpolation_error_bound",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("interpolation_error_bound", 1, 0);
+        self
+    }
+
+    pub fn estimate_query_reads(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "estimate_query_reads",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("estimate_query_reads", 1, 0);
+        self
+    }
+
+    pub fn observation_batch(&mut self, seconds: Vec<u64>) -> &mut OracleTestHelper {
+        for timestamp in seconds {
+            self.observation(timestamp);
+        }
+        self
+    }
+
+    pub fn observation_in_minutes(&mut self, minutes: u64) -> &mut OracleTestHelper {
+        self.observation(minutes * 60);
+        self
+    }
+
+    pub fn observation_intervals(&mut self, intervals: Vec<(u64, u64)>) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals",
+            manifest_args!(intervals),
+        );
+        self.env.new_instruction("observation_intervals", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_typed(
+        &mut self,
+        intervals: Vec<(UnixSeconds, UnixSeconds)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_typed",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_typed", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_dedup(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_dedup",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_dedup", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_cached(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_cached",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_cached", 1, 0);
+        self
+    }
+
+    pub fn average_liquidity(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "average_liquidity",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("average_liquidity", 1, 0);
+        self
+    }
+
+    pub fn average_fee_rate(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "average_fee_rate",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("average_fee_rate", 1, 0);
+        self
+    }
+
+    pub fn variance(&mut self, start_seconds: u64, end_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "variance",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("variance", 1, 0);
+        self
+    }
+
+    pub fn log_returns(&mut self, start_seconds: u64, end_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "log_returns",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("log_returns", 1, 0);
+        self
+    }
+
+    pub fn volume(&mut self, start_seconds: u64, end_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "volume",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("volume", 1, 0);
+        self
+    }
+
+    pub fn swap_count_in_range(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "swap_count_in_range",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("swap_count_in_range", 1, 0);
+        self
+    }
+
+    pub fn try_observation(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_observation",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("try_observation", 1, 0);
+        self
+    }
+
+    pub fn try_observation_intervals(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_observation_intervals",
+            manifest_args!(intervals),
+        );
+        self.env.new_instruction("try_observation_intervals", 1, 0);
+        self
+    }
+
+    pub fn try_observation_interval(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_observation_interval",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("try_observation_interval", 1, 0);
+        self
+    }
+
+    pub fn twap_last(&mut self, window_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "twap_last",
+            manifest_args!(window_seconds),
+        );
+        self.env.new_instruction("twap_last", 1, 0);
+        self
+    }
+
+    pub fn try_twap_last(&mut self, window_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_twap_last",
+            manifest_args!(window_seconds),
+        );
+        self.env.new_instruction("try_twap_last", 1, 0);
+        self
+    }
+
+    pub fn twap_full_history(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "twap_full_history",
+            manifest_args!(),
+        );
+        self.env.new_instruction("twap_full_history", 1, 0);
+        self
+    }
+
+    pub fn try_twap_full_history(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_twap_full_history",
+            manifest_args!(),
+        );
+        self.env.new_instruction("try_twap_full_history", 1, 0);
+        self
+    }
+
+    pub fn twap_last_cached(&mut self, window_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "twap_last_cached",
+            manifest_args!(window_seconds),
+        );
+        self.env.new_instruction("twap_last_cached", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_detailed(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_detailed",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_detailed", 1, 0);
+        self
+    }
+
+    pub fn interval_endpoints(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "interval_endpoints",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("interval_endpoints", 1, 0);
+        self
+    }
+
+    pub fn accumulator_delta(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "accumulator_delta",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("accumulator_delta", 1, 0);
+        self
+    }
+
+    pub fn twap_prices(&mut self, intervals: Vec<(u64, u64)>) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "twap_prices",
+            manifest_args!(intervals),
+        );
+        self.env.new_instruction("twap_prices", 1, 0);
+        self
+    }
+
+    pub fn composite_twap(&mut self, intervals: Vec<(u64, u64)>) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "composite_twap",
+            manifest_args!(intervals),
+        );
+        self.env.new_instruction("composite_twap", 1, 0);
+        self
+    }
+
+    pub fn chart_series(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+        step_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "chart_series",
+            manifest_args!(start_seconds, end_seconds, step_seconds),
+        );
+        self.env.new_instruction("chart_series", 1, 0);
+        self
+    }
+
+    pub fn downsample(&mut self, step_minutes: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "downsample",
+            manifest_args!(step_minutes),
+        );
+        self.env.new_instruction("downsample", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_harmonic(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_harmonic",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_harmonic", 1, 0);
+        self
+    }
+
+    pub fn try_observation_interval_harmonic(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_observation_interval_harmonic",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env
+            .new_instruction("try_observation_interval_harmonic", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_inverse(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_inverse",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_inverse", 1, 0);
+        self
+    }
+
+    pub fn observation_inverse(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_inverse",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("observation_inverse", 1, 0);
+        self
+    }
+
+    pub fn observation_precise(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_precise",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("observation_precise", 1, 0);
+        self
+    }
+
+    pub fn midpoint_observation(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "midpoint_observation",
+            manifest_args!(),
+        );
+        self.env.new_instruction("midpoint_observation", 1, 0);
+        self
+    }
+
+    pub fn range_prices(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "range_prices",
+            manifest_args!(),
+        );
+        self.env.new_instruction("range_prices", 1, 0);
+        self
+    }
+
+    pub fn observation_intervals_with_confidence(
+        &mut self,
+        intervals: Vec<(u64, u64)>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_intervals_with_confidence",
+            manifest_args!(intervals),
+        );
+        self.env
+            .new_instruction("observation_intervals_with_confidence", 1, 0);
+        self
+    }
+
+    pub fn observation_interval(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_interval",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("observation_interval", 1, 0);
+        self
+    }
+
+    pub fn increase_capacity(&mut self, new_limit: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "increase_capacity",
+            manifest_args!(new_limit),
+        );
+        self.env.new_instruction("increase_capacity", 1, 0);
+        self
+    }
+
+    pub fn try_increase_capacity(&mut self, observations_limit: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "try_increase_capacity",
+            manifest_args!(observations_limit),
+        );
+        self.env.new_instruction("try_increase_capacity", 1, 0);
+        self
+    }
+
+    pub fn reserve_capacity(&mut self, observations_limit: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "reserve_capacity",
+            manifest_args!(observations_limit),
+        );
+        self.env.new_instruction("reserve_capacity", 1, 0);
+        self
+    }
+
+    pub fn decrease_capacity(&mut self, observations_limit: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "decrease_capacity",
+            manifest_args!(observations_limit),
+        );
+        self.env.new_instruction("decrease_capacity", 1, 0);
+        self
+    }
+
+    pub fn freeze_capacity_at_current(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "freeze_capacity_at_current",
+            manifest_args!(),
+        );
+        self.env.new_instruction("freeze_capacity_at_current", 1, 0);
+        self
+    }
+
+    pub fn rebase(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder =
+            manifest_builder.call_method(self.oracle_address.unwrap(), "rebase", manifest_args!());
+        self.env.new_instruction("rebase", 1, 0);
+        self
+    }
+
+    pub fn price_sqrt_log_acc_offset(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "price_sqrt_log_acc_offset",
+            manifest_args!(),
+        );
+        self.env.new_instruction("price_sqrt_log_acc_offset", 1, 0);
+        self
+    }
+
+    pub fn observations_limit(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observations_limit",
+            manifest_args!(),
+        );
+        self.env.new_instruction("observations_limit", 1, 0);
+        self
+    }
+
+    pub fn oldest_observation_at(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "oldest_observation_at",
+            manifest_args!(),
+        );
+        self.env.new_instruction("oldest_observation_at", 1, 0);
+        self
+    }
+
+    pub fn oldest_observation_timestamp(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "oldest_observation_timestamp",
+            manifest_args!(),
+        );
+        self.env
+            .new_instruction("oldest_observation_timestamp", 1, 0);
+        self
+    }
+
+    pub fn observation_timestamps(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_timestamps",
+            manifest_args!(),
+        );
+        self.env.new_instruction("observation_timestamps", 1, 0);
+        self
+    }
+
+    pub fn last_finalized_timestamp(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "last_finalized_timestamp",
+            manifest_args!(),
+        );
+        self.env.new_instruction("last_finalized_timestamp", 1, 0);
+        self
+    }
+
+    pub fn extrapolation_age_seconds(&mut self, seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "extrapolation_age_seconds",
+            manifest_args!(seconds),
+        );
+        self.env.new_instruction("extrapolation_age_seconds", 1, 0);
+        self
+    }
+
+    pub fn observable_range_seconds(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observable_range_seconds",
+            manifest_args!(),
+        );
+        self.env.new_instruction("observable_range_seconds", 1, 0);
+        self
+    }
+
+    pub fn observation_gaps(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_gaps",
+            manifest_args!(),
+        );
+        self.env.new_instruction("observation_gaps", 1, 0);
+        self
+    }
+
+    pub fn max_extrapolation_seconds(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "max_extrapolation_seconds",
+            manifest_args!(),
+        );
+        self.env.new_instruction("max_extrapolation_seconds", 1, 0);
+        self
+    }
+
+    pub fn set_max_extrapolation_seconds(
+        &mut self,
+        max_extrapolation_seconds: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_max_extrapolation_seconds",
+            manifest_args!(max_extrapolation_seconds),
+        );
+        self.env
+            .new_instruction("set_max_extrapolation_seconds", 1, 0);
+        self
+    }
+
+    pub fn max_interpolation_gap_minutes(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "max_interpolation_gap_minutes",
+            manifest_args!(),
+        );
+        self.env
+            .new_instruction("max_interpolation_gap_minutes", 1, 0);
+        self
+    }
+
+    pub fn set_max_interpolation_gap_minutes(
+        &mut self,
+        max_interpolation_gap_minutes: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_max_interpolation_gap_minutes",
+            manifest_args!(max_interpolation_gap_minutes),
+        );
+        self.env
+            .new_instruction("set_max_interpolation_gap_minutes", 1, 0);
+        self
+    }
+
+    pub fn max_interval_minutes(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "max_interval_minutes",
+            manifest_args!(),
+        );
+        self.env.new_instruction("max_interval_minutes", 1, 0);
+        self
+    }
+
+    pub fn set_max_interval_minutes(
+        &mut self,
+        max_interval_minutes: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_max_interval_minutes",
+            manifest_args!(max_interval_minutes),
+        );
+        self.env.new_instruction("set_max_interval_minutes", 1, 0);
+        self
+    }
+
+    pub fn price_sqrt_band(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "price_sqrt_band",
+            manifest_args!(),
+        );
+        self.env.new_instruction("price_sqrt_band", 1, 0);
+        self
+    }
+
+    pub fn set_price_sqrt_band(
+        &mut self,
+        price_sqrt_band: Option<PreciseDecimal>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_price_sqrt_band",
+            manifest_args!(price_sqrt_band),
+        );
+        self.env.new_instruction("set_price_sqrt_band", 1, 0);
+        self
+    }
+
+    pub fn price_sqrt_floor(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "price_sqrt_floor",
+            manifest_args!(),
+        );
+        self.env.new_instruction("price_sqrt_floor", 1, 0);
+        self
+    }
+
+    pub fn set_price_sqrt_floor(
+        &mut self,
+        price_sqrt_floor: Option<PreciseDecimal>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_price_sqrt_floor",
+            manifest_args!(price_sqrt_floor),
+        );
+        self.env.new_instruction("set_price_sqrt_floor", 1, 0);
+        self
+    }
+
+    pub fn min_spacing_minutes(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "min_spacing_minutes",
+            manifest_args!(),
+        );
+        self.env.new_instruction("min_spacing_minutes", 1, 0);
+        self
+    }
+
+    pub fn set_emit_spot_events(&mut self, emit_spot_events: bool) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_emit_spot_events",
+            manifest_args!(emit_spot_events),
+        );
+        self.env.new_instruction("set_emit_spot_events", 1, 0);
+        self
+    }
+
+    pub fn emit_spot_events(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "emit_spot_events",
+            manifest_args!(),
+        );
+        self.env.new_instruction("emit_spot_events", 1, 0);
+        self
+    }
+
+    pub fn token_pair(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "token_pair",
+            manifest_args!(),
+        );
+        self.env.new_instruction("token_pair", 1, 0);
+        self
+    }
+
+    pub fn set_min_spacing_minutes(
+        &mut self,
+        min_spacing_minutes: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_min_spacing_minutes",
+            manifest_args!(min_spacing_minutes),
+        );
+        self.env.new_instruction("set_min_spacing_minutes", 1, 0);
+        self
+    }
+
+    pub fn min_retained_minutes(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "min_retained_minutes",
+            manifest_args!(),
+        );
+        self.env.new_instruction("min_retained_minutes", 1, 0);
+        self
+    }
+
+    pub fn set_min_retained_minutes(
+        &mut self,
+        min_retained_minutes: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_min_retained_minutes",
+            manifest_args!(min_retained_minutes),
+        );
+        self.env.new_instruction("set_min_retained_minutes", 1, 0);
+        self
+    }
+
+    pub fn max_observation_gap_minutes(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "max_observation_gap_minutes",
+            manifest_args!(),
+        );
+        self.env
+            .new_instruction("max_observation_gap_minutes", 1, 0);
+        self
+    }
+
+    pub fn set_max_observation_gap_minutes(
+        &mut self,
+        max_observation_gap_minutes: Option<u64>,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "set_max_observation_gap_minutes",
+            manifest_args!(max_observation_gap_minutes),
+        );
+        self.env
+            .new_instruction("set_max_observation_gap_minutes", 1, 0);
+        self
+    }
+
+    pub fn metadata(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "metadata",
+            manifest_args!(),
+        );
+        self.env.new_instruction("metadata", 1, 0);
+        self
+    }
+
+    pub fn assert_invariants(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "assert_invariants",
+            manifest_args!(),
+        );
+        self.env.new_instruction("assert_invariants", 1, 0);
+        self
+    }
+
+    pub fn verify_storage(&mut self) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "verify_storage",
+            manifest_args!(),
+        );
+        self.env.new_instruction("verify_storage", 1, 0);
+        self
+    }
+
+    pub fn clear_observation_at_index(&mut self, index: u16) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "clear_observation_at_index",
+            manifest_args!(index),
+        );
+        self.env.new_instruction("clear_observation_at_index", 1, 0);
         self
     }
 
-    pub fn get_calls(&mut self) -> &mut OracleTestHelper {
+    pub fn clear_sub_observations(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "get_calls",
+            "clear_sub_observations",
             manifest_args!(),
         );
-        self.env.new_instruction("get_calls", 1, 0);
+        self.env.new_instruction("clear_sub_observations", 1, 0);
         self
     }
 
-    pub fn after_swap(
+    pub fn seed_observations(
         &mut self,
-        after_swap_state: AfterSwapState,
-        input_address: ResourceAddress,
-        input_amount: Decimal,
+        observations: Vec<AccumulatedObservation>,
     ) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
-        self.env.manifest_builder = manifest_builder
-            .withdraw_from_account(self.env.account, input_address, input_amount)
-            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
-            .with_name_lookup(|builder, lookup| {
-                let input_bucket = lookup.bucket(self.name("input_bucket"));
-                builder.call_method(
-                    self.oracle_address.unwrap(),
-                    "after_swap",
-                    manifest_args!(after_swap_state, input_bucket),
-                )
-            });
-
-        self.env.new_instruction("after_swap", 3, 2);
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "seed_observations",
+            manifest_args!(observations),
+        );
+        self.env.new_instruction("seed_observations", 1, 0);
         self
     }
 
-    pub fn after_swap_default(&mut self) -> &mut OracleTestHelper {
-        let input_address = self.y_address();
-        let input_amount = dec!("7.463543");
-
+    pub fn sub_observation_state(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
-        self.env.manifest_builder = manifest_builder
-            .withdraw_from_account(self.env.account, input_address, input_amount)
-            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
-            .with_name_lookup(|builder, lookup| {
-                let input_bucket = lookup.bucket(self.name("input_bucket"));
-                builder.call_method(
-                    self.oracle_address.unwrap(),
-                    "after_swap",
-                    manifest_args!(self.after_swap_state_dummy(), input_bucket),
-                )
-            });
-
-        self.env.new_instruction("after_swap", 3, 2);
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "sub_observation_state",
+            manifest_args!(),
+        );
+        self.env.new_instruction("sub_observation_state", 1, 0);
         self
     }
 
-    /*     pub fn after_swap(
-        &mut self,
-        after_swap_state: AfterSwapState,
-        input_address: ResourceAddress,
-        input_amount: Decimal
-    ) -> &mut OracleTestHelper {
+    pub fn last_observation_index(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
-        self.env.manifest_builder = manifest_builder
-            .withdraw_from_account(self.env.account, input_address, input_amount)
-            .take_from_worktop(input_address, input_amount, self.name("input_bucket"))
-            .with_name_lookup(|builder, lookup| {
-                let input_bucket = lookup.bucket(self.name("input_bucket"));
-                builder.call_method(
-                    self.oracle_address.unwrap(),
-                    "after_swap",
-                    manifest_args!(after_swap_state, input_bucket)
-                )
-            });
-
-        self.env.new_instruction("after_swap", 3, 2);
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "last_observation_index",
+            manifest_args!(),
+        );
+        self.env.new_instruction("last_observation_index", 1, 0);
         self
-    } */
+    }
 
-    pub fn observation(&mut self, seconds: u64) -> &mut OracleTestHelper {
+    pub fn observation_back(&mut self, n: u16) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "observation",
-            manifest_args!(seconds),
+            "observation_back",
+            manifest_args!(n),
         );
-        self.env.new_instruction("observation", 1, 0);
+        self.env.new_instruction("observation_back", 1, 0);
         self
     }
 
-    pub fn observation_batch(&mut self, seconds: Vec<u64>) -> &mut OracleTestHelper {
-        for timestamp in seconds {
-            self.observation(timestamp);
-        }
+    pub fn exact_observation(&mut self, start_seconds: u64) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "exact_observation",
+            manifest_args!(start_seconds),
+        );
+        self.env.new_instruction("exact_observation", 1, 0);
         self
     }
 
-    pub fn observation_in_minutes(&mut self, minutes: u64) -> &mut OracleTestHelper {
-        self.observation(minutes * 60);
+    pub fn observation_count_in_range(
+        &mut self,
+        start_seconds: u64,
+        end_seconds: u64,
+    ) -> &mut OracleTestHelper {
+        let manifest_builder = mem::take(&mut self.env.manifest_builder);
+
+        self.env().manifest_builder = manifest_builder.call_method(
+            self.oracle_address.unwrap(),
+            "observation_count_in_range",
+            manifest_args!(start_seconds, end_seconds),
+        );
+        self.env.new_instruction("observation_count_in_range", 1, 0);
         self
     }
 
-    pub fn observation_intervals(&mut self, intervals: Vec<(u64, u64)>) -> &mut OracleTestHelper {
+    pub fn observation_at_index(&mut self, index: u16) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "observation_intervals",
-            manifest_args!(intervals),
+            "observation_at_index",
+            manifest_args!(index),
         );
-        self.env.new_instruction("observation_intervals", 1, 0);
+        self.env.new_instruction("observation_at_index", 1, 0);
         self
     }
 
-    pub fn increase_capacity(&mut self, new_limit: u16) -> &mut OracleTestHelper {
+    pub fn spot_log_price_between(
+        &mut self,
+        index_left: u16,
+        index_right: u16,
+    ) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "increase_capacity",
-            manifest_args!(new_limit),
+            "spot_log_price_between",
+            manifest_args!(index_left, index_right),
         );
-        self.env.new_instruction("increase_capacity", 1, 0);
+        self.env.new_instruction("spot_log_price_between", 1, 0);
         self
     }
 
-    pub fn observations_limit(&mut self) -> &mut OracleTestHelper {
+    pub fn twap_between_indices(
+        &mut self,
+        oldest_offset: u16,
+        newest_offset: u16,
+    ) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "observations_limit",
-            manifest_args!(),
+            "twap_between_indices",
+            manifest_args!(oldest_offset, newest_offset),
         );
-        self.env.new_instruction("observations_limit", 1, 0);
+        self.env.new_instruction("twap_between_indices", 1, 0);
         self
     }
 
-    pub fn oldest_observation_at(&mut self) -> &mut OracleTestHelper {
+    pub fn observations_stored(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "oldest_observation_at",
+            "observations_stored",
             manifest_args!(),
         );
-        self.env.new_instruction("oldest_observation_at", 1, 0);
+        self.env.new_instruction("observations_stored", 1, 0);
         self
     }
 
-    pub fn last_observation_index(&mut self) -> &mut OracleTestHelper {
+    pub fn snapshot(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "last_observation_index",
+            "snapshot",
             manifest_args!(),
         );
-        self.env.new_instruction("last_observation_index", 1, 0);
+        self.env.new_instruction("snapshot", 1, 0);
         self
     }
 
-    pub fn observations_stored(&mut self) -> &mut OracleTestHelper {
+    pub fn compressed_snapshot(&mut self) -> &mut OracleTestHelper {
         let manifest_builder = mem::take(&mut self.env.manifest_builder);
 
         self.env().manifest_builder = manifest_builder.call_method(
             self.oracle_address.unwrap(),
-            "observations_stored",
+            "compressed_snapshot",
             manifest_args!(),
         );
-        self.env.new_instruction("observations_stored", 1, 0);
+        self.env.new_instruction("compressed_snapshot", 1, 0);
         self
     }
 
@@ -718,6 +2226,27 @@ impl OracleTestHelper {
 
                 assert_eq!(outputs, vec![(after_instantiate_state,)]);
             }
+            HookCall::BeforeSwap => {
+                self.after_instantiate_default();
+
+                let before_swap_state = self.before_swap_state_dummy();
+                let input_address = self.y_address();
+                let input_amount = dec!("23.36527");
+                self.before_swap(before_swap_state.clone(), input_address, input_amount);
+
+                let receipt = self.execute_expect_success(false);
+                let outputs: Vec<(BeforeSwapState, Bucket)> = receipt.outputs("before_swap");
+
+                let output_buckets = receipt.output_buckets("before_swap");
+
+                assert_eq!(outputs.len(), 1);
+                assert_eq!(outputs[0].0, before_swap_state);
+
+                assert_eq!(
+                    output_buckets,
+                    vec![vec![Amount(input_address, input_amount)]]
+                );
+            }
             HookCall::AfterSwap => {
                 self.after_instantiate_default();
 
@@ -842,6 +2371,13 @@ impl From<DeserializedAccumulatedObservation> for AccumulatedObservation {
         Self {
             timestamp: obs.timestamp,
             price_sqrt_log_acc: obs.acc_price_sqrt_log,
+            price_sqrt_log_sq_acc: dec!(0),
+            price_inverse_acc: pdec!(0),
+            liquidity_acc: dec!(0),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: 0,
+            observed_minutes_acc: 0,
         }
     }
 }
@@ -850,8 +2386,8 @@ impl From<DeserializedAccumulatedObservation> for AccumulatedObservation {
 struct DeserializedObservationInterval {
     start: u64,
     end: u64,
-    #[serde(deserialize_with = "deserialize_decimal")]
-    price_sqrt: Decimal,
+    #[serde(deserialize_with = "deserialize_precise_decimal")]
+    price_sqrt: PreciseDecimal,
 }
 
 impl From<DeserializedObservationInterval> for ObservationInterval {
@@ -859,7 +2395,9 @@ impl From<DeserializedObservationInterval> for ObservationInterval {
         Self {
             start: obs.start,
             end: obs.end,
+            duration_minutes: (obs.end - obs.start) / 60,
             price_sqrt: obs.price_sqrt,
+            price: truncate(obs.price_sqrt * obs.price_sqrt),
         }
     }
 }
@@ -898,6 +2436,37 @@ pub fn import_observation_intervals(path: &str) -> Vec<ObservationInterval> {
     observation_intervals
 }
 
+// This is only for serialization, producing the shape read by `import_accumulated_observations`
+
+#[derive(serde::Serialize)]
+struct SerializedAccumulatedObservation {
+    timestamp: u64,
+    acc_price_sqrt_log: String,
+}
+
+impl From<&AccumulatedObservation> for SerializedAccumulatedObservation {
+    fn from(obs: &AccumulatedObservation) -> Self {
+        Self {
+            timestamp: obs.timestamp,
+            acc_price_sqrt_log: obs.price_sqrt_log_acc.to_string(),
+        }
+    }
+}
+
+/// Writes the timestamps and accumulated logarithms of `observations` to `path` in the JSON shape
+/// read by `import_accumulated_observations`, e.g. to create golden files from an oracle. The
+/// timestamps are written as given, the other accumulators are not exported.
+pub fn export_accumulated_observations(path: &str, observations: &[AccumulatedObservation]) {
+    let serialized_accumulated_observations: Vec<SerializedAccumulatedObservation> = observations
+        .iter()
+        .map(SerializedAccumulatedObservation::from)
+        .collect();
+
+    let contents = serde_json::to_string_pretty(&serialized_accumulated_observations)
+        .expect("Error during serialization");
+    std::fs::write(path, contents).unwrap();
+}
+
 pub fn assert_binary_search_result(timestamps: &Vec<u64>, expected: AccumulatedObservation) {
     let target_timestamp = expected.timestamp;
 
@@ -949,24 +2518,55 @@ pub fn log(value: PreciseDecimal) -> Decimal {
         .unwrap()
 }
 
+/// Mirrors `oracle::accumulated_log` without saturation:
+/// `acc_log + log(finalized) + (minutes_since_last - 1) * log(leaked_value)`.
 fn accumulated_log(
     acc_log: Decimal,
     finalized: PreciseDecimal,
     leaked_value: PreciseDecimal,
     minutes_since_last: u64,
 ) -> Decimal {
-    // TODO confirm math. DO we want to add log(finalized) + log(leaked_value), or log(finalized + leaked_value)
-
-    let leaked_value_log = log(leaked_value);
-    let finalized_log = log(finalized);
-    let minutes_since_last = if minutes_since_last >= 1 {
-        minutes_since_last
-    } else {
-        1
-    };
+    acc_log + log(finalized) + log(leaked_value) * (minutes_since_last - 1)
+}
 
-    // println!("Leaked*minutes_without_transactions{}", leaked_value_log * (minutes_since_last - 1));
-    acc_log + finalized_log + leaked_value_log * (minutes_since_last - 1)
+pub fn log_squared(value: PreciseDecimal) -> Decimal {
+    let value_log = log(value);
+    value_log * value_log
+}
+
+fn accumulated_log_squared(
+    acc_log_squared: Decimal,
+    finalized: PreciseDecimal,
+    leaked_value: PreciseDecimal,
+    minutes_since_last: u64,
+) -> Decimal {
+    acc_log_squared + log_squared(finalized) + log_squared(leaked_value) * (minutes_since_last - 1)
+}
+
+pub fn truncate(value: PreciseDecimal) -> Decimal {
+    value
+        .checked_truncate(RoundingMode::ToNegativeInfinity)
+        .unwrap()
+}
+
+/// Mirrors `oracle::accumulated_inverse` without saturation, at `PreciseDecimal` precision.
+fn accumulated_inverse(
+    acc_inverse: PreciseDecimal,
+    finalized: PreciseDecimal,
+    leaked_value: PreciseDecimal,
+    minutes_since_last: u64,
+) -> PreciseDecimal {
+    acc_inverse + finalized + leaked_value * (minutes_since_last - 1)
+}
+
+/// The inverse price `1 / price_sqrt²`, inverted before squaring like the oracle does.
+pub fn price_inverse(price_sqrt: PreciseDecimal) -> PreciseDecimal {
+    let price_sqrt_inverse = pdec!(1) / price_sqrt;
+    price_sqrt_inverse * price_sqrt_inverse
+}
+
+pub fn inverses(values: &[PreciseDecimal]) -> Vec<PreciseDecimal> {
+    values.iter().map(|value| price_inverse(*value)).collect()
 }
 
 pub fn weighted_average(values: Vec<PreciseDecimal>, weights: Vec<u64>) -> PreciseDecimal {
@@ -1003,24 +2603,13 @@ fn consecutive_differences(numbers: &[u64]) -> Vec<u64> {
         .collect()
 }
 
-pub fn generate_oracle_data(
+/// Calculates the time-weighted average and the last value of each minute with swaps.
+fn minute_averages(
     seconds: &Vec<u64>,
-) -> (
-    Vec<PreciseDecimal>,
-    Vec<AccumulatedObservation>,
-    PreciseDecimal,
-) {
-    assert_ne!(seconds[0], 0);
-    let values = TEST_DATAPOINTS.clone();
-
-    let timestamps_minutes: Vec<u64> = seconds.iter().map(|v| v / 60).collect();
-    let unique_minutes: Vec<u64> = timestamps_minutes
-        .iter()
-        .copied()
-        .collect::<BTreeSet<u64>>()
-        .into_iter()
-        .collect();
-
+    timestamps_minutes: &Vec<u64>,
+    unique_minutes: &Vec<u64>,
+    values: &Vec<PreciseDecimal>,
+) -> (Vec<PreciseDecimal>, Vec<PreciseDecimal>) {
     let mut averages: Vec<PreciseDecimal> = vec![];
 
     let mut last_values: Vec<PreciseDecimal> = vec![];
@@ -1057,6 +2646,48 @@ pub fn generate_oracle_data(
         averages.push(same_minute_avg);
     }
 
+    (averages, last_values)
+}
+
+/// Returns the number of swaps performed before the given minute.
+fn swaps_before(timestamps_minutes: &Vec<u64>, minute: u64) -> u64 {
+    timestamps_minutes
+        .iter()
+        .filter(|&&swap_minute| swap_minute < minute)
+        .count() as u64
+}
+
+pub fn generate_oracle_data(
+    seconds: &Vec<u64>,
+) -> (
+    Vec<PreciseDecimal>,
+    Vec<AccumulatedObservation>,
+    PreciseDecimal,
+) {
+    assert_ne!(seconds[0], 0);
+    let values = TEST_DATAPOINTS.clone();
+    let liquidity = TEST_LIQUIDITY
+        .checked_truncate(RoundingMode::ToNegativeInfinity)
+        .unwrap();
+
+    let timestamps_minutes: Vec<u64> = seconds.iter().map(|v| v / 60).collect();
+    let unique_minutes: Vec<u64> = timestamps_minutes
+        .iter()
+        .copied()
+        .collect::<BTreeSet<u64>>()
+        .into_iter()
+        .collect();
+
+    let (averages, last_values) =
+        minute_averages(seconds, &timestamps_minutes, &unique_minutes, &values);
+    let inverse_values = inverses(&values);
+    let (inverse_averages, inverse_last_values) = minute_averages(
+        seconds,
+        &timestamps_minutes,
+        &unique_minutes,
+        &inverse_values,
+    );
+
     let mut accumulated_observations: Vec<AccumulatedObservation> = vec![];
 
     // Calculate accumulated observations
@@ -1075,6 +2706,23 @@ pub fn generate_oracle_data(
             last_values[0],
             time_since_beginning,
         ),
+        price_sqrt_log_sq_acc: accumulated_log_squared(
+            dec!(0),
+            averages[0],
+            last_values[0],
+            time_since_beginning,
+        ),
+        price_inverse_acc: accumulated_inverse(
+            dec!(0),
+            inverse_averages[0],
+            inverse_last_values[0],
+            time_since_beginning,
+        ),
+        liquidity_acc: liquidity * time_since_beginning,
+        fee_rate_acc: dec!(0),
+        volume_acc: dec!(0),
+        swap_count_acc: swaps_before(&timestamps_minutes, unique_minutes[1]),
+        observed_minutes_acc: 1,
     });
     println!(
         "OBSERVATION {}:\nAcc_log: {}\nfinalized: {}\nleaked_value: {}\nminutes_since_last: {}\nresult: {}",
@@ -1100,6 +2748,23 @@ pub fn generate_oracle_data(
         let new_observation = AccumulatedObservation {
             timestamp: unique_minutes[i] * 60,
             price_sqrt_log_acc,
+            price_sqrt_log_sq_acc: accumulated_log_squared(
+                last_observation.price_sqrt_log_sq_acc,
+                averages[i - 1],
+                last_values[i - 1],
+                time_since_last_obs,
+            ),
+            price_inverse_acc: accumulated_inverse(
+                last_observation.price_inverse_acc,
+                inverse_averages[i - 1],
+                inverse_last_values[i - 1],
+                time_since_last_obs,
+            ),
+            liquidity_acc: last_observation.liquidity_acc + liquidity * time_since_last_obs,
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: swaps_before(&timestamps_minutes, unique_minutes[i]),
+            observed_minutes_acc: i as u64,
         };
 
         println!(
@@ -1186,8 +2851,9 @@ pub fn geometric_mean(
     x_right_seconds: u64,
     y_left: Decimal,
     y_right: Decimal,
-) -> Decimal {
-    let exponent = arithmetic_mean(x_left_seconds, x_right_seconds, y_left, y_right);
+) -> PreciseDecimal {
+    // Like `arithmetic_mean`, but with the precision of the on-chain calculation
+    let exponent = PreciseDecimal::from(y_right - y_left) * 60 / (x_right_seconds - x_left_seconds);
     exponent.exp().unwrap()
 }
 
@@ -1212,9 +2878,29 @@ fn interpolate_with_neighbors_(
 
     let y_target = left.price_sqrt_log_acc + slope * (target - left.timestamp);
 
+    let log_sq_slope = (right.price_sqrt_log_sq_acc - left.price_sqrt_log_sq_acc)
+        / (right.timestamp - left.timestamp);
+
+    let inverse_slope =
+        (right.price_inverse_acc - left.price_inverse_acc) / (right.timestamp - left.timestamp);
+
+    let liquidity_slope =
+        (right.liquidity_acc - left.liquidity_acc) / (right.timestamp - left.timestamp);
+
+    let fee_rate_slope =
+        (right.fee_rate_acc - left.fee_rate_acc) / (right.timestamp - left.timestamp);
+
     AccumulatedObservation {
         timestamp: target,
         price_sqrt_log_acc: y_target,
+        price_sqrt_log_sq_acc: left.price_sqrt_log_sq_acc
+            + log_sq_slope * (target - left.timestamp),
+        price_inverse_acc: left.price_inverse_acc + inverse_slope * (target - left.timestamp),
+        liquidity_acc: left.liquidity_acc + liquidity_slope * (target - left.timestamp),
+        fee_rate_acc: left.fee_rate_acc + fee_rate_slope * (target - left.timestamp),
+        volume_acc: right.volume_acc,
+        swap_count_acc: right.swap_count_acc,
+        observed_minutes_acc: right.observed_minutes_acc,
     }
 }
 
@@ -1286,7 +2972,9 @@ fn get_interval_(seconds: &Vec<u64>, left_seconds: u64, right_seconds: u64) -> O
     ObservationInterval {
         start: left_seconds,
         end: right_seconds,
+        duration_minutes: (right_seconds - left_seconds) / 60,
         price_sqrt: average_price_sqrt,
+        price: truncate(average_price_sqrt * average_price_sqrt),
     }
 }
 
@@ -1362,6 +3050,12 @@ fn test_get_averages_from_swap_seconds_multi_minute_no_leak() {
     observations_expected.push(AccumulatedObservation {
         timestamp: 120,
         price_sqrt_log_acc: log(averages_expected[0]),
+        price_sqrt_log_sq_acc: log_squared(averages_expected[0]),
+        price_inverse_acc: weighted_average(inverses(&TEST_DATAPOINTS[0..3]), vec![5, 10, 35]),
+        liquidity_acc: dec!(100),
+        volume_acc: dec!(0),
+        swap_count_acc: 3,
+        observed_minutes_acc: 1,
     });
 
     assert_eq!(observations_result, observations_expected);
@@ -1390,6 +3084,12 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak() {
     observations_expected.push(AccumulatedObservation {
         timestamp: 120,
         price_sqrt_log_acc: log(averages_expected[0]),
+        price_sqrt_log_sq_acc: log_squared(averages_expected[0]),
+        price_inverse_acc: weighted_average(inverses(&TEST_DATAPOINTS[0..3]), vec![5, 10, 35]),
+        liquidity_acc: dec!(100),
+        volume_acc: dec!(0),
+        swap_count_acc: 3,
+        observed_minutes_acc: 1,
     });
 
     assert_eq!(observations_result, observations_expected);
@@ -1422,6 +3122,12 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak_2() {
     observations_expected.push(AccumulatedObservation {
         timestamp: 120,
         price_sqrt_log_acc: log(averages_expected[0]),
+        price_sqrt_log_sq_acc: log_squared(averages_expected[0]),
+        price_inverse_acc: weighted_average(inverses(&TEST_DATAPOINTS[0..3]), vec![5, 10, 35]),
+        liquidity_acc: dec!(100),
+        volume_acc: dec!(0),
+        swap_count_acc: 3,
+        observed_minutes_acc: 1,
     });
     observations_expected.push(AccumulatedObservation {
         timestamp: 180,
@@ -1431,6 +3137,22 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak_2() {
             TEST_DATAPOINTS[7],
             1,
         ),
+        price_sqrt_log_sq_acc: accumulated_log_squared(
+            observations_expected[0].price_sqrt_log_sq_acc,
+            averages_expected[1],
+            TEST_DATAPOINTS[7],
+            1,
+        ),
+        price_inverse_acc: accumulated_inverse(
+            observations_expected[0].price_inverse_acc,
+            weighted_average(inverses(&TEST_DATAPOINTS[2..8]), vec![5, 10, 15, 0, 10, 20]),
+            pdec!(1) / TEST_DATAPOINTS[7],
+            1,
+        ),
+        liquidity_acc: dec!(200),
+        volume_acc: dec!(0),
+        swap_count_acc: 8,
+        observed_minutes_acc: 2,
     });
 
     assert_eq!(observations_result, observations_expected);
@@ -1456,6 +3178,22 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak_big_gap() {
     observations_expected.push(AccumulatedObservation {
         timestamp: 600,
         price_sqrt_log_acc: accumulated_log(dec!(0), averages_expected[0], TEST_DATAPOINTS[5], 8),
+        price_sqrt_log_sq_acc: accumulated_log_squared(
+            dec!(0),
+            averages_expected[0],
+            TEST_DATAPOINTS[5],
+            8,
+        ),
+        price_inverse_acc: accumulated_inverse(
+            dec!(0),
+            weighted_average(inverses(&TEST_DATAPOINTS[0..6]), vec![7, 0, 0, 5, 0, 45]),
+            pdec!(1) / TEST_DATAPOINTS[5],
+            8,
+        ),
+        liquidity_acc: dec!(800),
+        volume_acc: dec!(0),
+        swap_count_acc: 6,
+        observed_minutes_acc: 1,
     });
 
     assert_eq!(observations_result, observations_expected);
@@ -1482,6 +3220,22 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak_big_gap_2() {
     observations_expected.push(AccumulatedObservation {
         timestamp: 600,
         price_sqrt_log_acc: accumulated_log(dec!(0), averages_expected[0], TEST_DATAPOINTS[0], 8),
+        price_sqrt_log_sq_acc: accumulated_log_squared(
+            dec!(0),
+            averages_expected[0],
+            TEST_DATAPOINTS[0],
+            8,
+        ),
+        price_inverse_acc: accumulated_inverse(
+            dec!(0),
+            weighted_average(inverses(&TEST_DATAPOINTS[0..1]), vec![57]),
+            pdec!(1) / TEST_DATAPOINTS[0],
+            8,
+        ),
+        liquidity_acc: dec!(800),
+        volume_acc: dec!(0),
+        swap_count_acc: 1,
+        observed_minutes_acc: 1,
     });
     observations_expected.push(AccumulatedObservation {
         timestamp: 900,
@@ -1491,6 +3245,22 @@ fn test_get_averages_from_swap_seconds_multi_minute_leak_big_gap_2() {
             TEST_DATAPOINTS[1],
             5,
         ),
+        price_sqrt_log_sq_acc: accumulated_log_squared(
+            observations_expected[0].price_sqrt_log_sq_acc,
+            averages_expected[1],
+            TEST_DATAPOINTS[1],
+            5,
+        ),
+        price_inverse_acc: accumulated_inverse(
+            observations_expected[0].price_inverse_acc,
+            weighted_average(inverses(&TEST_DATAPOINTS[0..2]), vec![7, 53]),
+            pdec!(1) / TEST_DATAPOINTS[1],
+            5,
+        ),
+        liquidity_acc: dec!(1300),
+        volume_acc: dec!(0),
+        swap_count_acc: 2,
+        observed_minutes_acc: 2,
     });
 
     assert_eq!(observations_result, observations_expected);
@@ -1503,6 +3273,13 @@ fn new_observation(seconds: u64) -> AccumulatedObservation {
     AccumulatedObservation {
         timestamp: seconds,
         price_sqrt_log_acc: Decimal::from(seconds),
+        price_sqrt_log_sq_acc: Decimal::from(seconds),
+        price_inverse_acc: PreciseDecimal::from(seconds),
+        liquidity_acc: Decimal::from(seconds),
+        fee_rate_acc: Decimal::from(seconds),
+        volume_acc: dec!(0),
+        swap_count_acc: 0,
+        observed_minutes_acc: 0,
     }
 }
 
@@ -1540,6 +3317,13 @@ fn test_interpolate_(left: u64, right: u64, target: u64) {
     let expected = AccumulatedObservation {
         timestamp: target,
         price_sqrt_log_acc: Decimal::from(target),
+        price_sqrt_log_sq_acc: Decimal::from(target),
+        price_inverse_acc: PreciseDecimal::from(target),
+        liquidity_acc: Decimal::from(target),
+        fee_rate_acc: Decimal::from(target),
+        volume_acc: dec!(0),
+        swap_count_acc: 0,
+        observed_minutes_acc: 0,
     };
 
     assert_eq!(result, expected);
@@ -1565,6 +3349,13 @@ fn test_interpolate(target: u64) {
     let expected = AccumulatedObservation {
         timestamp: target_rounded,
         price_sqrt_log_acc: Decimal::from(target_rounded),
+        price_sqrt_log_sq_acc: Decimal::from(target_rounded),
+        price_inverse_acc: PreciseDecimal::from(target_rounded),
+        liquidity_acc: Decimal::from(target_rounded),
+        fee_rate_acc: Decimal::from(target_rounded),
+        volume_acc: dec!(0),
+        swap_count_acc: 0,
+        observed_minutes_acc: 0,
     };
 
     assert_eq!(result, expected);
@@ -1592,7 +3383,9 @@ fn test_get_interval_average(target_left: u64, target_right: u64) {
     let expected = ObservationInterval {
         start: target_left_rounded,
         end: target_right_rounded,
+        duration_minutes: (target_right_rounded - target_left_rounded) / 60,
         price_sqrt: mean,
+        price: truncate(mean * mean),
     };
 
     let result = get_interval_(&seconds, target_left, target_right);
@@ -1,12 +1,22 @@
 mod helper;
+use common::pools::SwapType;
 use helper::*;
-use oracle::{oracle::accumulated_log, AccumulatedObservation, ObservationInterval};
+use oracle::{
+    oracle::{
+        accumulated_inverse, accumulated_log, accumulated_log_squared, accumulated_value,
+        arithmetic_mean, geometric_mean, DEFAULT_OBSERVATIONS_LIMIT, INTERVAL_END_NOW,
+        LOG_RETURNS_MINUTES_MAX,
+    },
+    AccumulatedObservation, ObservationInterval, ObservationIntervalDetailed, OracleError,
+    OracleMetadata, SpotPriceEvent, UnixMinutes, UnixSeconds,
+};
 use pretty_assertions::assert_eq;
 use scrypto::prelude::*;
+use scrypto_math::*;
 use scrypto_testenv::environment::TestHelperExecution;
 use std::ops::Range;
 use test_case::test_case;
-use test_oracle::test_oracle::HookCall;
+use test_oracle::test_oracle::{AfterSwapState, BeforeSwapState, HookCall};
 
 #[test]
 fn test_instantiate_instant() {
@@ -43,7 +53,11 @@ fn test_before_swap_auth_failure() {
     let mut helper = OracleTestHelper::new();
     helper.instantiate_instant();
 
-    helper.after_swap(helper.after_swap_state_dummy(), helper.y_address(), dec!(1));
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
     helper.execute_expect_failure(false);
 }
 
@@ -53,6 +67,108 @@ fn test_before_swap_auth_success() {
     helper.instantiate_instant();
 
     helper.load_hook_auth();
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
+    helper.execute_expect_success(false);
+}
+
+#[test]
+fn test_after_swap_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.after_swap(helper.after_swap_state_dummy(), helper.y_address(), dec!(1));
+    helper.execute_expect_failure(false);
+}
+
+#[test]
+fn test_after_swap_auth_success() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.load_hook_auth();
+    helper.after_swap(helper.after_swap_state_dummy(), helper.y_address(), dec!(1));
+    helper.execute_expect_success(false);
+}
+
+// Non-positive prices
+
+#[test_case(false; "after_swap")]
+#[test_case(true; "after_previous_swap")]
+fn test_after_swap_zero_price_sqrt_fails(previous_swap: bool) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    if previous_swap {
+        swap_with_liquidity(&mut helper, 120, pdec!(2), pdec!(100));
+    }
+    let mut state = helper.after_swap_state_dummy();
+    state.price_sqrt = pdec!(0);
+
+    helper.load_hook_auth();
+    helper.after_swap(state, helper.y_address(), dec!(1));
+    let receipt = helper.execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("reported a non-positive price_sqrt of 0.")
+    });
+}
+
+#[test]
+fn test_before_swap_zero_price_sqrt_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    let mut state = helper.before_swap_state_dummy();
+    state.price_sqrt = pdec!(0);
+
+    helper.load_hook_auth();
+    helper.before_swap(state, helper.y_address(), dec!(1));
+    let receipt = helper.execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("reported a non-positive price_sqrt of 0.")
+    });
+}
+
+// Hook calls before `after_instantiate`
+
+#[test]
+fn test_before_swap_before_after_instantiate_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_uninitialized_instant();
+
+    helper.load_hook_auth();
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
+    let receipt = helper.execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("before `after_instantiate` has been called")
+    });
+}
+
+#[test]
+fn test_after_swap_before_after_instantiate_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_uninitialized_instant();
+
+    helper.load_hook_auth();
+    helper.after_swap(helper.after_swap_state_dummy(), helper.y_address(), dec!(1));
+    let receipt = helper.execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("before `after_instantiate` has been called")
+    });
+}
+
+#[test]
+fn test_after_swap_after_after_instantiate_succeeds() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_uninitialized_instant();
+
+    helper.load_hook_auth();
+    helper.after_instantiate(helper.after_instantiate_state_empty());
     helper.after_swap(helper.after_swap_state_dummy(), helper.y_address(), dec!(1));
     helper.execute_expect_success(false);
 }
@@ -70,7 +186,11 @@ fn test_get_calls() {
 
     assert_eq!(
         output,
-        vec![vec![HookCall::AfterInstantiate, HookCall::AfterSwap]]
+        vec![vec![
+            HookCall::AfterInstantiate,
+            HookCall::BeforeSwap,
+            HookCall::AfterSwap
+        ]]
     );
 }
 
@@ -82,6 +202,12 @@ fn test_after_instantiate_assert_outputs_equal_inputs() {
     helper.assert_outputs_equal_inputs(HookCall::AfterInstantiate);
 }
 
+#[test]
+fn test_before_swap_assert_outputs_equal_inputs() {
+    let mut helper = OracleTestHelper::new();
+    helper.assert_outputs_equal_inputs(HookCall::BeforeSwap);
+}
+
 #[test]
 fn test_after_swap_assert_outputs_equal_inputs() {
     let mut helper = OracleTestHelper::new();
@@ -246,6 +372,17 @@ fn test_observations_limit() {
     assert_eq!(outputs, vec![10]);
 }
 
+#[test]
+fn test_observations_limit_default() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_default_limit_instant();
+    helper.observations_limit();
+    let receipt = helper.execute_expect_success(false);
+    let outputs: Vec<u16> = receipt.outputs("observations_limit");
+
+    assert_eq!(outputs, vec![DEFAULT_OBSERVATIONS_LIMIT]);
+}
+
 // Observations stored
 #[test]
 fn test_observations_stored_0() {
@@ -344,249 +481,3444 @@ fn test_last_observation_index(timestamps: Range<u64>, expected: Option<u16>) {
     assert_eq!(outputs, vec![expected]);
 }
 
-// Oldest observation timestamp
-#[test_case(4..4, None)]
-#[test_case(4..5, Some(240))]
-#[test_case(4..14, Some(240))]
-#[test_case(4..15, Some(300))]
-#[test_case(4..16, Some(360))]
-fn test_oldest_observation_at(timestamps: Range<u64>, expected: Option<u64>) {
+// Observation at index
+#[test_case(4..7, 0, Some(4))]
+#[test_case(4..7, 2, Some(6))]
+#[test_case(4..7, 3, None; "empty_slot")]
+#[test_case(4..16, 0, Some(14); "wrapped")]
+#[test_case(4..16, 2, Some(6); "wrapped_oldest")]
+#[test_case(4..16, 9, Some(13); "wrapped_last_slot")]
+#[test_case(4..16, 10, None; "beyond_limit")]
+#[test_case(4..16, u16::MAX, None; "max_index")]
+#[test_case(4..4, 0, None; "no_observations")]
+fn test_observation_at_index(timestamps: Range<u64>, index: u16, expected: Option<u64>) {
     let timestamps: Vec<u64> = timestamps.collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    helper.oldest_observation_at();
-    let outputs: Vec<Option<u64>> = helper
+    helper.observation_at_index(index);
+    let outputs: Vec<Option<AccumulatedObservation>> = helper
         .execute_expect_success(false)
-        .outputs("oldest_observation_at");
+        .outputs("observation_at_index");
 
-    assert_eq!(outputs, vec![expected]);
+    let timestamps: Vec<Option<u64>> = outputs
+        .iter()
+        .map(|observation| {
+            observation
+                .as_ref()
+                .map(|observation| observation.timestamp)
+        })
+        .collect();
+    assert_eq!(timestamps, vec![expected]);
 }
 
-// Get observation special cases
-
-#[test]
-fn test_observation_timestamp_older_than_oldest_fails() {
-    let minutes: Vec<u64> = (2..20).collect();
-    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+// Spot log price between adjacent observations
+#[test_case(4..7, 0, 1)]
+#[test_case(4..7, 1, 2)]
+#[test_case(4..16, 9, 0; "wrapped")]
+#[test_case(4..16, 0, 1; "wrapped_last")]
+#[test_case(4..16, 2, 3; "wrapped_oldest")]
+fn test_spot_log_price_between(timestamps: Range<u64>, index_left: u16, index_right: u16) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    helper
-        .observation_in_minutes(9)
-        .execute_expect_failure(false);
-    helper
-        .observation_in_minutes(10)
+    let receipt = helper
+        .observation_at_index(index_left)
+        .observation_at_index(index_right)
+        .spot_log_price_between(index_left, index_right)
         .execute_expect_success(false);
+    let observations: Vec<Option<AccumulatedObservation>> = receipt.outputs("observation_at_index");
+    let outputs: Vec<Decimal> = receipt.outputs("spot_log_price_between");
+
+    let o_l = observations[0].clone().unwrap();
+    let o_r = observations[1].clone().unwrap();
+    let expected = arithmetic_mean(
+        o_l.timestamp,
+        o_r.timestamp,
+        o_l.price_sqrt_log_acc,
+        o_r.price_sqrt_log_acc,
+    );
+    assert_eq!(outputs, vec![expected]);
 }
 
-#[test]
-fn test_observation_timestamp_equals_last_timestamp() {
-    let timestamps: Vec<u64> = (4..8).collect();
+#[test_case(4..7, 1, 0, "does not directly follow"; "reversed")]
+#[test_case(4..7, 0, 2, "does not directly follow"; "not_adjacent")]
+#[test_case(4..7, 0, 0, "does not directly follow"; "same_index")]
+#[test_case(4..16, 1, 2, "does not directly follow"; "last_to_oldest")]
+#[test_case(4..7, 2, 3, "No observation is stored"; "empty_slot")]
+#[test_case(4..4, 0, 1, "No observation is stored"; "no_observations")]
+fn test_spot_log_price_between_fails(
+    timestamps: Range<u64>,
+    index_left: u16,
+    index_right: u16,
+    message: &str,
+) {
+    let timestamps: Vec<u64> = timestamps.collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
     let receipt = helper
-        .observation_in_minutes(7)
-        .execute_expect_success(false);
+        .spot_log_price_between(index_left, index_right)
+        .execute_expect_failure(false);
 
-    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains(message));
+}
 
-    let expected_observations = get_observations_from_observation_minutes(&timestamps);
-    let expected = interpolate_batch_minutes(&expected_observations, &vec![7]);
+// TWAP between stored observations, compared with observation_intervals over the same bounds
+#[test_case(vec![4, 5, 6], 0, 2; "oldest_to_last")]
+#[test_case(vec![4, 5, 6], 0, 1; "adjacent")]
+#[test_case(vec![4, 7, 15, 30], 1, 3; "gaps")]
+#[test_case((4..16).collect(), 0, 9; "wrapped_oldest_to_last")]
+#[test_case((4..16).collect(), 2, 5; "wrapped")]
+fn test_twap_between_indices(timestamps: Vec<u64>, oldest_offset: u16, newest_offset: u16) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    let stored = &timestamps[timestamps.len().saturating_sub(10)..];
+    let start_seconds = stored[oldest_offset as usize] * 60;
+    let end_seconds = stored[newest_offset as usize] * 60;
 
-    assert_eq!(outputs, expected);
+    let receipt = helper
+        .twap_between_indices(oldest_offset, newest_offset)
+        .observation_intervals(vec![(start_seconds, end_seconds)])
+        .execute_expect_success(false);
+    let outputs: Vec<ObservationInterval> = receipt.outputs("twap_between_indices");
+    let expected: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    assert_eq!(outputs[0].start, start_seconds);
+    assert_eq!(outputs[0].end, end_seconds);
+    assert_eq!(outputs, expected[0]);
 }
 
-#[test]
-fn test_observation_timestamp_later_than_last_but_before_current() {
-    let minutes: Vec<u64> = (4..7).collect();
+#[test_case(vec![4, 5, 6], 1, 0, "must be smaller than the newest offset"; "reversed")]
+#[test_case(vec![4, 5, 6], 1, 1, "must be smaller than the newest offset"; "same_offset")]
+#[test_case(vec![4, 5, 6], 0, 3, "is out of range"; "out_of_range")]
+#[test_case(vec![], 0, 1, "is out of range"; "no_observations")]
+fn test_twap_between_indices_fails(
+    timestamps: Vec<u64>,
+    oldest_offset: u16,
+    newest_offset: u16,
+    message: &str,
+) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
-    helper.jump_to_timestamp_minutes(20);
-    helper.observation_in_minutes(12);
+    let receipt = helper
+        .twap_between_indices(oldest_offset, newest_offset)
+        .execute_expect_failure(false);
 
-    let receipt = helper.execute_expect_success(false);
-    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains(message));
+}
 
-    let seconds = convert_observation_minutes_to_swap_state_seconds(&minutes);
-    let (averages, observations, last_value) = generate_oracle_data(&seconds);
+// Observation counted back from the last one
+#[test_case(4..7, 0, Some(6); "latest")]
+#[test_case(4..7, 2, Some(4); "oldest")]
+#[test_case(4..7, 3, None; "out_of_range")]
+#[test_case(4..16, 0, Some(15); "wrapped_latest")]
+#[test_case(4..16, 1, Some(14); "wrapped_across_zero")]
+#[test_case(4..16, 9, Some(6); "wrapped_oldest")]
+#[test_case(4..16, 10, None; "wrapped_out_of_range")]
+#[test_case(4..4, 0, None; "no_observations")]
+fn test_observation_back(timestamps: Range<u64>, n: u16, expected_minute: Option<u64>) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    let last_observation = observations.last().unwrap();
-    let last_average = averages.last().unwrap().clone();
+    helper.observation_back(n);
+    let outputs: Vec<Option<AccumulatedObservation>> = helper
+        .execute_expect_success(false)
+        .outputs("observation_back");
 
-    let price_sqrt_log_acc = accumulated_log(
-        last_observation.price_sqrt_log_acc,
-        last_average,
-        last_value,
-        6,
-    );
+    let timestamps: Vec<Option<u64>> = outputs
+        .iter()
+        .map(|observation| {
+            observation
+                .as_ref()
+                .map(|observation| observation.timestamp)
+        })
+        .collect();
+    assert_eq!(timestamps, vec![expected_minute.map(|minute| minute * 60)]);
+}
 
-    let expected = vec![AccumulatedObservation {
-        timestamp: 12 * 60,
-        price_sqrt_log_acc,
-    }];
+// Observation stored for exactly the given minute
+#[test_case(vec![4, 5, 6], 4; "oldest")]
+#[test_case(vec![4, 5, 6], 5; "middle")]
+#[test_case(vec![4, 5, 6], 6; "last")]
+#[test_case(vec![4, 7, 15], 7; "gaps")]
+#[test_case((4..16).collect(), 6; "wrapped_oldest")]
+#[test_case((4..16).collect(), 15; "wrapped_last")]
+fn test_exact_observation(timestamps: Vec<u64>, minute: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    assert_eq!(outputs, expected);
-}
+    let receipt = helper
+        .exact_observation(minute * 60)
+        .observation(minute * 60)
+        .execute_expect_success(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = receipt.outputs("exact_observation");
+    let expected: Vec<AccumulatedObservation> = receipt.outputs("observation");
 
-// Test binary search
+    assert_eq!(outputs, vec![Some(expected[0].clone())]);
+}
 
-#[test_case(4..5, 4..5, true; "one")] // last observation returned directly (no binary search)
-#[test_case(4..6, 4..6, true; "two")]
-#[test_case(4..6, 5..6, true; "two_only_last")] // last observation returned directly (no binary search)
-#[test_case(4..9, 7..8, true; "second_last")]
-#[test_case(4..14, 4..14, true; "even")]
-#[test_case(4..13, 4..13, true; "odd")]
-#[test_case(4..20, 10..20, true; "even_above_limit")]
-#[test_case(4..15, 5..15, true; "odd_above_limit")]
-#[test_case(4..20, 9..10, false; "even_too_old_above_shift")]
-#[test_case(4..15, 4..5, false; "odd_too_old_above_limit")]
-#[test_case(4..8, 4..8, true; "timestamp_equals_last_timestamp")]
-#[test_case(4..8, 8..9, false; "later_than_current_time_fails")]
-fn test_binary_search_observations(
-    minutes: Range<u64>,
-    target_minutes: Range<usize>,
-    expect_success: bool,
-) {
-    let minutes: Vec<u64> = minutes.collect();
+#[test_case(vec![4, 5, 6], 3; "before_oldest")]
+#[test_case(vec![4, 5, 6], 7; "after_last")]
+#[test_case(vec![4, 7, 15], 5; "between_observations")]
+#[test_case(vec![4, 7, 15], 14; "before_last")]
+#[test_case((4..16).collect(), 5; "overwritten")]
+#[test_case(vec![], 4; "no_observations")]
+fn test_exact_observation_none(timestamps: Vec<u64>, minute: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
-    let expected_stored = get_observations_from_observation_minutes(&minutes);
+    let receipt = helper
+        .exact_observation(minute * 60)
+        .execute_expect_success(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = receipt.outputs("exact_observation");
 
-    let mut expected: Vec<AccumulatedObservation> = vec![];
-    for minute in target_minutes {
-        let seconds = (minute * 60) as u64;
-        if expect_success {
-            helper.observation(seconds);
-            expected.push(get_observation(&expected_stored, seconds));
-        } else {
-            helper.observation(seconds).execute_expect_failure(false);
-        }
-    }
+    assert_eq!(outputs, vec![None]);
+}
 
-    if !expect_success {
-        return;
-    }
+#[test_case(300; "minute_start")]
+#[test_case(330; "within_minute")]
+#[test_case(359; "minute_end")]
+fn test_exact_observation_seconds_rounding(start_seconds: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![4, 5, 6]);
 
-    let receipt = helper.execute_expect_success(false);
-    let output: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let receipt = helper
+        .exact_observation(start_seconds)
+        .execute_expect_success(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = receipt.outputs("exact_observation");
 
-    assert_eq!(output, expected);
+    assert_eq!(
+        outputs[0].as_ref().map(|observation| observation.timestamp),
+        Some(300)
+    );
 }
 
-// Interpolation
+// Number of observations within a window, with the bounds inclusive
+#[test_case(vec![4, 7, 15], 0, 1200, 3; "all")]
+#[test_case(vec![4, 7, 15], 240, 900, 3; "all_at_bounds")]
+#[test_case(vec![4, 7, 15], 241, 899, 1; "some_within_minute_bounds")]
+#[test_case(vec![4, 7, 15], 300, 420, 1; "some")]
+#[test_case(vec![4, 7, 15], 480, 840, 0; "none_between")]
+#[test_case(vec![4, 7, 15], 960, 1200, 0; "none_after")]
+#[test_case(vec![4, 7, 15], 420, 240, 0; "reversed")]
+#[test_case((4..16).collect(), 0, 1200, 10; "wrapped_all")]
+#[test_case((4..16).collect(), 300, 540, 4; "wrapped_some")]
+#[test_case((4..16).collect(), 600, u64::MAX, 6; "wrapped_open_end")]
+#[test_case(vec![], 0, 1200, 0; "no_observations")]
+fn test_observation_count_in_range(
+    timestamps: Vec<u64>,
+    start_seconds: u64,
+    end_seconds: u64,
+    expected: u16,
+) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-#[test]
-fn test_interpolation() {
-    let timestamps: Vec<u64> = vec![3, 6, 10];
+    let outputs: Vec<u16> = helper
+        .observation_count_in_range(start_seconds, end_seconds)
+        .execute_expect_success(false)
+        .outputs("observation_count_in_range");
 
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Observation relative to the current time
+#[test_case(0, 810; "now")]
+#[test_case(30, 780; "same_minute")]
+#[test_case(300, 510; "within_range")]
+#[test_case(570, 240; "oldest")]
+fn test_observation_ago(seconds_ago: u64, seconds: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
-    helper.observation_in_minutes(4);
-    helper.observation_in_minutes(8);
+    helper.jump_to_timestamp_seconds(810);
 
-    let receipt = helper.execute_expect_success(false);
-    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let receipt = helper
+        .observation_ago(seconds_ago)
+        .observation(seconds)
+        .execute_expect_success(false);
+    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation_ago");
+    let expected: Vec<AccumulatedObservation> = receipt.outputs("observation");
 
-    let observations_expected = get_observations_from_observation_minutes(&timestamps);
-    let expected = vec![
-        get_observation(&observations_expected, 4 * 60),
-        get_observation(&observations_expected, 8 * 60),
-    ];
+    assert_eq!(outputs, expected);
+}
 
-    assert_vecs_similar(outputs, expected);
+#[test_case(600; "before_oldest")]
+#[test_case(810; "epoch")]
+#[test_case(u64::MAX; "before_epoch")]
+fn test_observation_ago_out_of_range(seconds_ago: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(810);
+
+    let receipt = helper
+        .observation_ago(seconds_ago)
+        .execute_expect_failure(false);
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains("not in range"));
 }
 
-// Test observation_intervals
+// Spot price
 
-#[test]
-fn test_observation_intervals_left_lesser_than_right() {
-    let minutes: Vec<u64> = (4..9).collect();
-    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 8)];
+// The spot price at a minute is the price of the minute before it
+#[test_case(5 * 60, 5; "second_observation")]
+#[test_case(9 * 60 + 30, 9; "within_minute")]
+#[test_case(13 * 60, 13; "last_observation")]
+fn test_spot_price(seconds: u64, minute: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    let intervals_in_seconds: Vec<(u64, u64)> = intervals
-        .iter()
-        .map(|(start, end)| (start * 60, end * 60))
-        .collect();
+    let outputs: Vec<Decimal> = helper
+        .spot_price(seconds)
+        .execute_expect_success(false)
+        .outputs("spot_price");
 
-    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
-    helper.observation_intervals(intervals_in_seconds);
-    let receipt = helper.execute_expect_success(false);
+    let expected = get_intervals_from_observation_minutes(&timestamps, &vec![(minute - 1, minute)]);
+    assert_eq!(outputs, vec![expected[0].price]);
+}
 
-    let result: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+#[test]
+fn test_spot_price_current_minute() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(16 * 60 + 30);
 
-    let expected = get_intervals_from_observation_minutes(&minutes, &intervals);
+    let receipt = helper
+        .spot_price(16 * 60 + 30)
+        .observation_interval(15 * 60, 16 * 60)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("spot_price");
+    let expected: Vec<ObservationInterval> = receipt.outputs("observation_interval");
 
-    assert_eq!(result, vec![expected]);
+    assert_eq!(outputs, vec![expected[0].price]);
 }
 
 #[test]
-fn test_observation_intervals_left_bigger_than_right() {
-    let timestamps: Vec<u64> = (4..9).collect();
-    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 6)];
+fn test_spot_price_oldest_observation_fails() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
 
-    let intervals_in_seconds: Vec<(u64, u64)> = intervals
-        .iter()
-        .map(|(start, end)| (start * 60, end * 60))
-        .collect();
+    let receipt = helper.spot_price(4 * 60).execute_expect_failure(false);
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains("not in range"));
+}
+
+// Midpoint observation
 
+#[test]
+fn test_midpoint_observation() {
+    let timestamps: Vec<u64> = vec![3, 6, 10];
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
-    helper.observation_intervals(intervals_in_seconds);
+    // The midpoint of [3, 11] is minute 7, interpolated between the observations at 6 and 10
+    helper.jump_to_timestamp_seconds(11 * 60 + 30);
 
-    helper.execute_expect_failure(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = helper
+        .midpoint_observation()
+        .execute_expect_success(false)
+        .outputs("midpoint_observation");
+
+    let observations_expected = get_observations_from_observation_minutes(&timestamps);
+    assert_vecs_similar(
+        outputs.into_iter().map(Option::unwrap).collect(),
+        vec![get_observation(&observations_expected, 7 * 60)],
+    );
 }
 
 #[test]
-fn test_observation_intervals_left_equal_right() {
-    let timestamps: Vec<u64> = (4..9).collect();
-    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 7)];
+fn test_midpoint_observation_empty() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![]);
 
-    let intervals_in_seconds: Vec<(u64, u64)> = intervals
-        .iter()
-        .map(|(start, end)| (start * 60, end * 60))
-        .collect();
+    let outputs: Vec<Option<AccumulatedObservation>> = helper
+        .midpoint_observation()
+        .execute_expect_success(false)
+        .outputs("midpoint_observation");
+
+    assert_eq!(outputs, vec![None]);
+}
 
+// Range prices
+
+#[test]
+fn test_range_prices() {
+    let timestamps: Vec<u64> = (4..14).collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
-    helper.observation_intervals(intervals_in_seconds);
 
-    helper.execute_expect_failure(false);
+    let outputs: Vec<Option<(Decimal, Decimal)>> = helper
+        .range_prices()
+        .execute_expect_success(false)
+        .outputs("range_prices");
+
+    // The prices of the oldest minute 4 and of minute 12 before the current minute 13
+    let expected = get_intervals_from_observation_minutes(&timestamps, &vec![(4, 5), (12, 13)]);
+    assert_eq!(outputs, vec![Some((expected[0].price, expected[1].price))]);
 }
 
 #[test]
-fn test_observation_intervals() {
+fn test_range_prices_extrapolated() {
     let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(16 * 60 + 30);
 
-    let intervals: Vec<(u64, u64)> = vec![(4, 13), (5, 7), (7, 12)];
-    let intervals_in_seconds: Vec<(u64, u64)> = intervals
-        .iter()
-        .map(|(start, end)| (start * 60, end * 60))
+    let receipt = helper
+        .range_prices()
+        .observation_interval(4 * 60, 5 * 60)
+        .observation_interval(15 * 60, 16 * 60)
+        .execute_expect_success(false);
+    let outputs: Vec<Option<(Decimal, Decimal)>> = receipt.outputs("range_prices");
+    let expected: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+
+    assert_eq!(outputs, vec![Some((expected[0].price, expected[1].price))]);
+}
+
+#[test]
+fn test_range_prices_empty() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![]);
+
+    let outputs: Vec<Option<(Decimal, Decimal)>> = helper
+        .range_prices()
+        .execute_expect_success(false)
+        .outputs("range_prices");
+
+    assert_eq!(outputs, vec![None]);
+}
+
+// Oldest observation timestamp
+#[test_case(4..4, None)]
+#[test_case(4..5, Some(240))]
+#[test_case(4..14, Some(240))]
+#[test_case(4..15, Some(300))]
+#[test_case(4..16, Some(360))]
+fn test_oldest_observation_at(timestamps: Range<u64>, expected: Option<u64>) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    helper.oldest_observation_at();
+    let outputs: Vec<Option<u64>> = helper
+        .execute_expect_success(false)
+        .outputs("oldest_observation_at");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Typed timestamps
+
+#[test_case(4..4, None)]
+#[test_case(4..14, Some(UnixSeconds(240)))]
+#[test_case(4..16, Some(UnixSeconds(360)))]
+fn test_oldest_observation_timestamp(timestamps: Range<u64>, expected: Option<UnixSeconds>) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .oldest_observation_timestamp()
+        .oldest_observation_at()
+        .execute_expect_success(false);
+    let typed: Vec<Option<UnixSeconds>> = receipt.outputs("oldest_observation_timestamp");
+    let untyped: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+
+    assert_eq!(typed, vec![expected]);
+    assert_eq!(untyped, vec![expected.map(|timestamp| timestamp.0)]);
+}
+
+// The `u64` interfaces and the typed ones return the same results
+#[test]
+fn test_typed_timestamps_match_u64_interfaces() {
+    let timestamps: Vec<u64> = vec![3, 6, 10];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .observation(8 * 60 + 30)
+        .observation_typed(UnixSeconds(8 * 60 + 30))
+        .observation_intervals(vec![(4 * 60, 9 * 60), (6 * 60, 10 * 60)])
+        .observation_intervals_typed(vec![
+            (UnixSeconds(4 * 60), UnixSeconds(9 * 60)),
+            (UnixMinutes(6).to_seconds(), UnixMinutes(10).to_seconds()),
+        ])
+        .execute_expect_success(false);
+    let observation: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let observation_typed: Vec<AccumulatedObservation> = receipt.outputs("observation_typed");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let intervals_typed: Vec<Vec<ObservationInterval>> =
+        receipt.outputs("observation_intervals_typed");
+
+    assert_eq!(observation_typed, observation);
+    assert_eq!(intervals_typed, intervals);
+}
+
+// Stored observation timestamps
+#[test_case(vec![]; "no_observations")]
+#[test_case(vec![4, 5, 6]; "not_full")]
+#[test_case(vec![4, 7, 15]; "gaps")]
+#[test_case((4..16).collect(); "wrapped")]
+fn test_observation_timestamps(timestamps: Vec<u64>) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let outputs: Vec<Vec<u64>> = helper
+        .observation_timestamps()
+        .execute_expect_success(false)
+        .outputs("observation_timestamps");
+    // The stored observations from the oldest to the last one
+    let stored = timestamps.len().min(10) as u16;
+    let expected: Vec<u64> = (0..stored)
+        .rev()
+        .map(|n| {
+            let observations: Vec<Option<AccumulatedObservation>> = helper
+                .observation_back(n)
+                .execute_expect_success(false)
+                .outputs("observation_back");
+            observations[0].clone().unwrap().timestamp
+        })
         .collect();
 
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Last finalized timestamp
+
+// Swaps at the given seconds, queried at the last one
+#[test_case(vec![], None; "no_swaps")]
+#[test_case(vec![130, 150], None; "first_minute")]
+#[test_case(vec![130, 150, 190], Some(180); "minute_crossed")]
+#[test_case(vec![130, 150, 190, 230], Some(180); "mid_minute")]
+#[test_case(vec![130, 150, 190, 230, 240], Some(240); "minute_boundary")]
+#[test_case(vec![130, 190, 600], Some(600); "after_gap")]
+fn test_last_finalized_timestamp(seconds: Vec<u64>, expected: Option<u64>) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for &seconds_ in &seconds {
+        swap_with_liquidity(&mut helper, seconds_, pdec!(1), pdec!(100));
+    }
+    helper.jump_to_timestamp_seconds(seconds.last().copied().unwrap_or(0) + 10);
+
+    let outputs: Vec<Option<u64>> = helper
+        .last_finalized_timestamp()
+        .execute_expect_success(false)
+        .outputs("last_finalized_timestamp");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Extrapolation age
+
+// Swaps at 130 and 190, i.e. the last observation at 180, queried at 600
+#[test_case(150, 0; "interpolated")]
+#[test_case(180, 0; "last_observation")]
+#[test_case(210, 0; "same_minute")]
+#[test_case(240, 60; "one_minute")]
+#[test_case(420, 240; "four_minutes")]
+#[test_case(600, 420; "now")]
+fn test_extrapolation_age_seconds(seconds: u64, expected: u64) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_with_liquidity(&mut helper, 130, pdec!(1), pdec!(100));
+    swap_with_liquidity(&mut helper, 190, pdec!(2), pdec!(100));
+    helper.jump_to_timestamp_seconds(600);
+
+    let outputs: Vec<u64> = helper
+        .extrapolation_age_seconds(seconds)
+        .execute_expect_success(false)
+        .outputs("extrapolation_age_seconds");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_extrapolation_age_seconds_no_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .extrapolation_age_seconds(60)
+        .execute_expect_failure(false);
+}
+
+// Metadata
+#[test_case(4..4; "no_observations")]
+#[test_case(4..8; "not_full")]
+#[test_case(4..16; "wrapped")]
+fn test_metadata_matches_getters(timestamps: Range<u64>) {
+    let timestamps: Vec<u64> = timestamps.collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
-    helper.observation_intervals(intervals_in_seconds);
-    let receipt = helper.execute_expect_success(false);
-    let outputs: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
 
-    let expected: Vec<Vec<ObservationInterval>> = vec![get_intervals_from_observation_minutes(
-        &timestamps,
-        &intervals,
-    )];
+    let receipt = helper
+        .metadata()
+        .observations_limit()
+        .observations_stored()
+        .last_observation_index()
+        .oldest_observation_at()
+        .execute_expect_success(false);
 
-    assert_eq!(outputs, expected);
+    let metadata: Vec<OracleMetadata> = receipt.outputs("metadata");
+    let observations_limit: Vec<u16> = receipt.outputs("observations_limit");
+    let observations_stored: Vec<u16> = receipt.outputs("observations_stored");
+    let last_observation_index: Vec<Option<u16>> = receipt.outputs("last_observation_index");
+    let oldest_observation_at: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+
+    assert_eq!(metadata[0].observations_limit, observations_limit[0]);
+    assert_eq!(metadata[0].observations_stored, observations_stored[0]);
+    assert_eq!(
+        metadata[0].last_observation_index,
+        last_observation_index[0]
+    );
+    assert_eq!(metadata[0].oldest_observation_at, oldest_observation_at[0]);
 }
 
+#[test_case(vec![], None; "never_updated")]
+#[test_case(vec![130], Some(130); "first_swap")]
+#[test_case(vec![130, 150], Some(150); "same_minute")]
+#[test_case(vec![130, 150, 200], Some(200); "new_minute")]
+fn test_metadata_last_update_timestamp(seconds: Vec<u64>, expected: Option<u64>) {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    let metadata: Vec<OracleMetadata> = helper
+        .metadata()
+        .execute_expect_success(false)
+        .outputs("metadata");
+
+    assert_eq!(metadata[0].last_update_timestamp, expected);
+}
+
+// Token pair
 #[test]
-fn test_observation_intervals_seconds_rounding() {
-    let timestamps: Vec<u64> = (4..14).collect();
-    let swap_seconds = convert_observation_minutes_to_swap_state_seconds(&timestamps);
+fn test_token_pair_not_set() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_uninitialized_instant();
 
-    let intervals_in_seconds: Vec<(u64, u64)> = vec![(635, 660), (660, 735)];
+    let outputs: Vec<Option<(ResourceAddress, ResourceAddress)>> = helper
+        .token_pair()
+        .execute_expect_success(false)
+        .outputs("token_pair");
+
+    assert_eq!(outputs, vec![None]);
+}
+
+#[test_case(false; "x_y")]
+#[test_case(true; "y_x")]
+fn test_token_pair_after_instantiate(reversed: bool) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_uninitialized_instant();
+    let (x_address, y_address) = if reversed {
+        (helper.y_address(), helper.x_address())
+    } else {
+        (helper.x_address(), helper.y_address())
+    };
+    let mut state = helper.after_instantiate_state_empty();
+    state.x_address = x_address;
+    state.y_address = y_address;
+
+    let outputs: Vec<Option<(ResourceAddress, ResourceAddress)>> = helper
+        .load_hook_auth()
+        .after_instantiate(state)
+        .token_pair()
+        .execute_expect_success(false)
+        .outputs("token_pair");
+
+    assert_eq!(outputs, vec![Some((x_address, y_address))]);
+}
 
+// Observable range
+#[test_case(4..4, None; "no_observations")]
+#[test_case(4..5, Some((240, 240)); "one")]
+#[test_case(4..14, Some((240, 780)); "full")]
+#[test_case(4..16, Some((360, 900)); "wrapped")]
+fn test_observable_range_seconds(timestamps: Range<u64>, expected: Option<(u64, u64)>) {
+    let timestamps: Vec<u64> = timestamps.collect();
     let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
-    helper.observation_intervals(intervals_in_seconds.clone());
+
+    helper.observable_range_seconds();
+    let outputs: Vec<Option<(u64, u64)>> = helper
+        .execute_expect_success(false)
+        .outputs("observable_range_seconds");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_observable_range_seconds_rounds_current_time() {
+    let timestamps: Vec<u64> = (4..6).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(1234);
+
+    helper.observable_range_seconds();
+    let outputs: Vec<Option<(u64, u64)>> = helper
+        .execute_expect_success(false)
+        .outputs("observable_range_seconds");
+
+    assert_eq!(outputs, vec![Some((240, 1200))]);
+}
+
+// Observation gaps
+
+#[test_case(vec![], vec![]; "no_observations")]
+#[test_case(vec![4], vec![(240, 0)]; "one")]
+#[test_case(
+    vec![4, 5, 30, 31, 33],
+    vec![(240, 0), (300, 1), (1800, 25), (1860, 1), (1980, 2)];
+    "large_gap"
+)]
+#[test_case(
+    vec![4, 5, 6, 7, 8, 9, 40, 41, 42, 43, 50, 51],
+    vec![
+        (360, 0), (420, 1), (480, 1), (540, 1), (2400, 31),
+        (2460, 1), (2520, 1), (2580, 1), (3000, 7), (3060, 1),
+    ];
+    "wrapped"
+)]
+fn test_observation_gaps(minutes: Vec<u64>, expected: Vec<(u64, u64)>) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let outputs: Vec<Vec<(u64, u64)>> = helper
+        .observation_gaps()
+        .execute_expect_success(false)
+        .outputs("observation_gaps");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Get observation special cases
+
+#[test]
+fn test_observation_timestamp_older_than_oldest_fails() {
+    let minutes: Vec<u64> = (2..20).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .observation_in_minutes(9)
+        .execute_expect_failure(false);
+    helper
+        .observation_in_minutes(10)
+        .execute_expect_success(false);
+}
+
+#[test]
+fn test_observation_timestamp_equals_last_timestamp() {
+    let timestamps: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .observation_in_minutes(7)
+        .execute_expect_success(false);
+
+    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    let expected_observations = get_observations_from_observation_minutes(&timestamps);
+    let expected = interpolate_batch_minutes(&expected_observations, &vec![7]);
+
+    assert_eq!(outputs, expected);
+}
+
+#[test]
+fn test_observation_timestamp_later_than_last_but_before_current() {
+    let minutes: Vec<u64> = (4..7).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_minutes(20);
+    helper.observation_in_minutes(12);
+
     let receipt = helper.execute_expect_success(false);
-    let outputs: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
 
-    let expected: Vec<Vec<ObservationInterval>> = vec![get_intervals_from_swap_seconds(
-        &swap_seconds,
-        &intervals_in_seconds,
-    )];
+    let seconds = convert_observation_minutes_to_swap_state_seconds(&minutes);
+    let (averages, observations, last_value) = generate_oracle_data(&seconds);
+
+    let last_observation = observations.last().unwrap();
+    let last_average = averages.last().unwrap().clone();
+
+    let price_sqrt_log_acc = accumulated_log(
+        last_observation.price_sqrt_log_acc,
+        last_average,
+        last_value,
+        6,
+    );
+    let price_sqrt_log_sq_acc = accumulated_log_squared(
+        last_observation.price_sqrt_log_sq_acc,
+        last_average,
+        last_value,
+        6,
+    );
+    // A single swap at the start of the last minute
+    let price_inverse_acc = accumulated_inverse(
+        last_observation.price_inverse_acc,
+        price_inverse(last_value),
+        price_inverse(last_value),
+        6,
+    );
+    let liquidity_acc = accumulated_value(
+        last_observation.liquidity_acc,
+        *TEST_LIQUIDITY,
+        *TEST_LIQUIDITY,
+        6,
+    );
+
+    let expected = vec![AccumulatedObservation {
+        timestamp: 12 * 60,
+        price_sqrt_log_acc,
+        price_sqrt_log_sq_acc,
+        price_inverse_acc,
+        liquidity_acc,
+        fee_rate_acc: dec!(0),
+        volume_acc: dec!(0),
+        // All swaps took place before the extrapolated minute, each in a minute of its own
+        swap_count_acc: seconds.len() as u64,
+        observed_minutes_acc: seconds.len() as u64,
+    }];
 
     assert_eq!(outputs, expected);
 }
+
+// Bounded extrapolation
+
+fn helper_with_max_extrapolation(max_extrapolation_seconds: Option<u64>) -> OracleTestHelper {
+    let minutes: Vec<u64> = (4..7).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .load_hook_auth()
+        .set_max_extrapolation_seconds(max_extrapolation_seconds)
+        .execute_expect_success(false);
+    helper.jump_to_timestamp_minutes(20);
+
+    helper
+}
+
+#[test_case(None; "unlimited")]
+#[test_case(Some(600); "limited")]
+fn test_max_extrapolation_seconds(max_extrapolation_seconds: Option<u64>) {
+    let mut helper = helper_with_max_extrapolation(max_extrapolation_seconds);
+
+    let outputs: Vec<Option<u64>> = helper
+        .max_extrapolation_seconds()
+        .execute_expect_success(false)
+        .outputs("max_extrapolation_seconds");
+
+    assert_eq!(outputs, vec![max_extrapolation_seconds]);
+}
+
+// The last observation is at minute 6
+#[test_case(None, 20 * 60, true; "unlimited")]
+#[test_case(Some(600), 16 * 60, true; "at_bound")]
+#[test_case(Some(600), 16 * 60 + 59, true; "at_bound_rounded")]
+#[test_case(Some(600), 17 * 60, false; "over_bound")]
+#[test_case(Some(659), 17 * 60, false; "over_bound_seconds")]
+#[test_case(Some(0), 6 * 60, true; "zero_bound_last_observation")]
+#[test_case(Some(0), 7 * 60, false; "zero_bound")]
+fn test_observation_max_extrapolation(
+    max_extrapolation_seconds: Option<u64>,
+    seconds: u64,
+    expect_success: bool,
+) {
+    let mut helper = helper_with_max_extrapolation(max_extrapolation_seconds);
+
+    helper.observation(seconds);
+    if expect_success {
+        helper.execute_expect_success(false);
+    } else {
+        helper.execute_expect_failure(false);
+    }
+}
+
+#[test]
+fn test_try_observation_extrapolation_too_stale() {
+    let mut helper = helper_with_max_extrapolation(Some(600));
+
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> = helper
+        .try_observation(17 * 60)
+        .execute_expect_success(false)
+        .outputs("try_observation");
+
+    assert_eq!(outputs, vec![Err(OracleError::ExtrapolationTooStale)]);
+}
+
+#[test]
+fn test_set_max_extrapolation_seconds_auth_failure() {
+    let minutes: Vec<u64> = (4..7).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .set_max_extrapolation_seconds(Some(600))
+        .execute_expect_failure(false);
+}
+
+// Bounded interpolation
+
+// The observations at the minutes 5 and 15 are 10 minutes apart, the others are adjacent
+fn helper_with_max_interpolation_gap(
+    max_interpolation_gap_minutes: Option<u64>,
+) -> OracleTestHelper {
+    let minutes: Vec<u64> = vec![4, 5, 15, 16];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .load_hook_auth()
+        .set_max_interpolation_gap_minutes(max_interpolation_gap_minutes)
+        .execute_expect_success(false);
+
+    helper
+}
+
+#[test_case(None; "unlimited")]
+#[test_case(Some(5); "limited")]
+fn test_max_interpolation_gap_minutes(max_interpolation_gap_minutes: Option<u64>) {
+    let mut helper = helper_with_max_interpolation_gap(max_interpolation_gap_minutes);
+
+    let outputs: Vec<Option<u64>> = helper
+        .max_interpolation_gap_minutes()
+        .execute_expect_success(false)
+        .outputs("max_interpolation_gap_minutes");
+
+    assert_eq!(outputs, vec![max_interpolation_gap_minutes]);
+}
+
+#[test_case(None, 10 * 60, true; "unlimited")]
+#[test_case(Some(5), 4 * 60 + 30, true; "close_neighbors")]
+#[test_case(Some(5), 10 * 60, false; "far_neighbors")]
+#[test_case(Some(10), 10 * 60, true; "at_bound")]
+#[test_case(Some(9), 10 * 60, false; "over_bound")]
+#[test_case(Some(5), 5 * 60, true; "exact_left")]
+#[test_case(Some(5), 15 * 60, true; "exact_right")]
+fn test_observation_max_interpolation_gap(
+    max_interpolation_gap_minutes: Option<u64>,
+    seconds: u64,
+    expect_success: bool,
+) {
+    let mut helper = helper_with_max_interpolation_gap(max_interpolation_gap_minutes);
+
+    helper.observation(seconds);
+    if expect_success {
+        helper.execute_expect_success(false);
+    } else {
+        let receipt = helper.execute_expect_failure(false);
+        receipt.execution_receipt.expect_specific_failure(|error| {
+            format!("{:?}", error).contains("which are more than")
+        });
+    }
+}
+
+#[test]
+fn test_max_interpolation_gap_ignores_extrapolation() {
+    let mut helper = helper_with_max_interpolation_gap(Some(0));
+    helper.jump_to_timestamp_minutes(30);
+
+    helper.observation(30 * 60).execute_expect_success(false);
+}
+
+#[test]
+fn test_observation_intervals_max_interpolation_gap() {
+    let mut helper = helper_with_max_interpolation_gap(Some(5));
+
+    helper
+        .observation_intervals(vec![(4 * 60, 5 * 60), (5 * 60, 15 * 60)])
+        .execute_expect_success(false);
+    helper
+        .observation_intervals(vec![(4 * 60, 10 * 60)])
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_try_observation_interpolation_gap_too_wide() {
+    let mut helper = helper_with_max_interpolation_gap(Some(5));
+
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> = helper
+        .try_observation(10 * 60)
+        .execute_expect_success(false)
+        .outputs("try_observation");
+
+    assert_eq!(outputs, vec![Err(OracleError::InterpolationGapTooWide)]);
+}
+
+#[test]
+fn test_set_max_interpolation_gap_minutes_auth_failure() {
+    let minutes: Vec<u64> = (4..7).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .set_max_interpolation_gap_minutes(Some(5))
+        .execute_expect_failure(false);
+}
+
+// Maximum interval length
+
+// Observations for the minutes 4 to 13, queried at minute 13
+fn helper_with_max_interval(max_interval_minutes: Option<u64>) -> OracleTestHelper {
+    let minutes: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .load_hook_auth()
+        .set_max_interval_minutes(max_interval_minutes)
+        .execute_expect_success(false);
+
+    helper
+}
+
+#[test_case(None; "unlimited")]
+#[test_case(Some(5); "limited")]
+fn test_max_interval_minutes(max_interval_minutes: Option<u64>) {
+    let mut helper = helper_with_max_interval(max_interval_minutes);
+
+    let outputs: Vec<Option<u64>> = helper
+        .max_interval_minutes()
+        .execute_expect_success(false)
+        .outputs("max_interval_minutes");
+
+    assert_eq!(outputs, vec![max_interval_minutes]);
+}
+
+#[test_case(None, 240, 780, true; "unlimited")]
+#[test_case(Some(5), 300, 600, true; "at_bound")]
+#[test_case(Some(5), 359, 600, true; "at_bound_rounded")]
+#[test_case(Some(5), 300, 660, false; "over_bound")]
+#[test_case(Some(5), 480, INTERVAL_END_NOW, true; "now_at_bound")]
+#[test_case(Some(5), 420, INTERVAL_END_NOW, false; "now_over_bound")]
+fn test_observation_intervals_max_interval(
+    max_interval_minutes: Option<u64>,
+    start_seconds: u64,
+    end_seconds: u64,
+    expect_success: bool,
+) {
+    let mut helper = helper_with_max_interval(max_interval_minutes);
+
+    helper.observation_intervals(vec![(start_seconds, end_seconds)]);
+    if expect_success {
+        helper.execute_expect_success(false);
+    } else {
+        let receipt = helper.execute_expect_failure(false);
+        receipt.execution_receipt.expect_specific_failure(|error| {
+            format!("{:?}", error).contains("minutes, more than the maximum of 5 minutes.")
+        });
+    }
+}
+
+#[test]
+fn test_try_observation_interval_max_interval() {
+    let mut helper = helper_with_max_interval(Some(5));
+
+    let receipt = helper
+        .try_observation_interval(300, 660)
+        .try_observation_interval(300, 600)
+        .execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval");
+
+    assert_eq!(outputs[0], Err(OracleError::IntervalTooLong));
+    assert!(outputs[1].is_ok());
+}
+
+#[test]
+fn test_set_max_interval_minutes_zero_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .set_max_interval_minutes(Some(0))
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_set_max_interval_minutes_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_max_interval_minutes(Some(5))
+        .execute_expect_failure(false);
+}
+
+// Price band
+
+// A spike at second 180 between two minutes at price 1
+fn helper_with_price_spike(
+    price_sqrt_band: Option<PreciseDecimal>,
+    spike: PreciseDecimal,
+) -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(300);
+    helper
+        .load_hook_auth()
+        .set_price_sqrt_band(price_sqrt_band)
+        .execute_expect_success(false);
+    helper
+        .load_hook_auth()
+        .observe_many(vec![
+            (120, pdec!(1), *TEST_LIQUIDITY, dec!(0)),
+            (180, spike, *TEST_LIQUIDITY, dec!(0)),
+            (240, pdec!(1), *TEST_LIQUIDITY, dec!(0)),
+        ])
+        .execute_expect_success(false);
+
+    helper
+}
+
+#[test_case(None, pdec!(10), pdec!(10); "unbounded")]
+#[test_case(Some(pdec!(2)), pdec!("1.5"), pdec!("1.5"); "within_band")]
+#[test_case(Some(pdec!(2)), pdec!(2), pdec!(2); "at_band")]
+#[test_case(Some(pdec!(2)), pdec!(10), pdec!(2); "above_band")]
+#[test_case(Some(pdec!(2)), pdec!("0.1"), pdec!("0.5"); "below_band")]
+#[test_case(Some(pdec!(1)), pdec!(10), pdec!(1); "zero_width_band")]
+fn test_price_sqrt_band_clamps_spike(
+    price_sqrt_band: Option<PreciseDecimal>,
+    spike: PreciseDecimal,
+    expected: PreciseDecimal,
+) {
+    let mut helper = helper_with_price_spike(price_sqrt_band, spike);
+
+    let outputs: Vec<ObservationInterval> = helper
+        .observation_interval(180, 240)
+        .execute_expect_success(false)
+        .outputs("observation_interval");
+
+    assert!((outputs[0].price_sqrt - expected).checked_abs().unwrap() < pdec!("0.000000000001"));
+}
+
+#[test]
+fn test_price_sqrt_band() {
+    let mut helper = helper_with_price_spike(Some(pdec!(2)), pdec!(1));
+
+    let outputs: Vec<Option<PreciseDecimal>> = helper
+        .price_sqrt_band()
+        .execute_expect_success(false)
+        .outputs("price_sqrt_band");
+
+    assert_eq!(outputs, vec![Some(pdec!(2))]);
+}
+
+#[test]
+fn test_set_price_sqrt_band_below_one_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .set_price_sqrt_band(Some(pdec!("0.5")))
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_set_price_sqrt_band_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_price_sqrt_band(Some(pdec!(2)))
+        .execute_expect_failure(false);
+}
+
+// Price floor
+
+// 10^-30
+fn tiny_price_sqrt() -> PreciseDecimal {
+    pdec!("0.000000000000000000000000000001")
+}
+
+// A near-zero price during minute 2, e.g. at deep out-of-range ticks
+fn helper_with_tiny_price(price_sqrt_floor: Option<PreciseDecimal>) -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(300);
+    helper
+        .load_hook_auth()
+        .set_price_sqrt_floor(price_sqrt_floor)
+        .execute_expect_success(false);
+    helper
+        .load_hook_auth()
+        .observe_many(vec![
+            (120, tiny_price_sqrt(), *TEST_LIQUIDITY, dec!(0)),
+            (180, pdec!(1), *TEST_LIQUIDITY, dec!(0)),
+        ])
+        .execute_expect_success(false);
+
+    helper
+}
+
+#[test_case(None, tiny_price_sqrt(); "unfloored")]
+#[test_case(Some(pdec!("0.000001")), pdec!("0.000001"); "below_floor")]
+#[test_case(Some(tiny_price_sqrt()), tiny_price_sqrt(); "at_floor")]
+#[test_case(Some(tiny_price_sqrt() / 10), tiny_price_sqrt(); "above_floor")]
+fn test_price_sqrt_floor_clamps_tiny_price(
+    price_sqrt_floor: Option<PreciseDecimal>,
+    expected: PreciseDecimal,
+) {
+    let mut helper = helper_with_tiny_price(price_sqrt_floor);
+
+    let outputs: Vec<AccumulatedObservation> = helper
+        .observation(180)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    // The first observation accumulates the logarithm of the recorded price of minute 2
+    assert_eq!(outputs[0].price_sqrt_log_acc, log(expected));
+    assert_eq!(outputs[0].price_sqrt_log_sq_acc, log_squared(expected));
+}
+
+#[test]
+fn test_price_sqrt_floor() {
+    let mut helper = helper_with_tiny_price(Some(pdec!("0.000001")));
+
+    let outputs: Vec<Option<PreciseDecimal>> = helper
+        .price_sqrt_floor()
+        .execute_expect_success(false)
+        .outputs("price_sqrt_floor");
+
+    assert_eq!(outputs, vec![Some(pdec!("0.000001"))]);
+}
+
+#[test]
+fn test_set_price_sqrt_floor_zero_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .set_price_sqrt_floor(Some(pdec!(0)))
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_set_price_sqrt_floor_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_price_sqrt_floor(Some(pdec!("0.000001")))
+        .execute_expect_failure(false);
+}
+
+// Test binary search
+
+#[test_case(4..5, 4..5, true; "one")] // last observation returned directly (no binary search)
+#[test_case(4..6, 4..6, true; "two")]
+#[test_case(4..6, 5..6, true; "two_only_last")] // last observation returned directly (no binary search)
+#[test_case(4..9, 7..8, true; "second_last")]
+#[test_case(4..14, 4..14, true; "even")]
+#[test_case(4..13, 4..13, true; "odd")]
+#[test_case(4..20, 10..20, true; "even_above_limit")]
+#[test_case(4..15, 5..15, true; "odd_above_limit")]
+#[test_case(4..20, 9..10, false; "even_too_old_above_shift")]
+#[test_case(4..15, 4..5, false; "odd_too_old_above_limit")]
+#[test_case(4..8, 4..8, true; "timestamp_equals_last_timestamp")]
+#[test_case(4..8, 8..9, false; "later_than_current_time_fails")]
+fn test_binary_search_observations(
+    minutes: Range<u64>,
+    target_minutes: Range<usize>,
+    expect_success: bool,
+) {
+    let minutes: Vec<u64> = minutes.collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    let expected_stored = get_observations_from_observation_minutes(&minutes);
+
+    let mut expected: Vec<AccumulatedObservation> = vec![];
+    for minute in target_minutes {
+        let seconds = (minute * 60) as u64;
+        if expect_success {
+            helper.observation(seconds);
+            expected.push(get_observation(&expected_stored, seconds));
+        } else {
+            helper.observation(seconds).execute_expect_failure(false);
+        }
+    }
+
+    if !expect_success {
+        return;
+    }
+
+    let receipt = helper.execute_expect_success(false);
+    let output: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    assert_eq!(output, expected);
+}
+
+// Neighbors
+
+#[test_case(vec![4, 6, 10, 11, 15], vec![240, 300, 390, 480, 720, 900]; "gaps")]
+#[test_case((4..20).collect(), vec![600, 630, 900, 1140]; "wrapped")]
+fn test_observation_neighbors(minutes: Vec<u64>, targets: Vec<u64>) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    let expected_stored = get_observations_from_observation_minutes(&minutes);
+
+    let mut expected: Vec<(AccumulatedObservation, AccumulatedObservation)> = vec![];
+    for &seconds in targets.iter() {
+        helper.observation_neighbors(seconds);
+        expected.push(find_neighbors(&expected_stored, (seconds / 60) * 60));
+    }
+
+    let receipt = helper.execute_expect_success(false);
+    let output: Vec<(AccumulatedObservation, AccumulatedObservation)> =
+        receipt.outputs("observation_neighbors");
+
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn test_observation_neighbors_after_last() {
+    let minutes: Vec<u64> = (4..7).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    let last = get_observations_from_observation_minutes(&minutes)
+        .last()
+        .unwrap()
+        .clone();
+
+    helper.jump_to_timestamp_minutes(20);
+    let receipt = helper
+        .observation_neighbors(12 * 60)
+        .execute_expect_success(false);
+    let output: Vec<(AccumulatedObservation, AccumulatedObservation)> =
+        receipt.outputs("observation_neighbors");
+
+    assert_eq!(output, vec![(last.clone(), last)]);
+}
+
+#[test]
+fn test_observation_neighbors_too_old() {
+    let minutes: Vec<u64> = (4..20).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper
+        .observation_neighbors(9 * 60)
+        .execute_expect_failure(false);
+}
+
+// Interpolation
+
+#[test]
+fn test_interpolation() {
+    let timestamps: Vec<u64> = vec![3, 6, 10];
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_in_minutes(4);
+    helper.observation_in_minutes(8);
+
+    let receipt = helper.execute_expect_success(false);
+    let outputs: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    let observations_expected = get_observations_from_observation_minutes(&timestamps);
+    let expected = vec![
+        get_observation(&observations_expected, 4 * 60),
+        get_observation(&observations_expected, 8 * 60),
+    ];
+
+    assert_vecs_similar(outputs, expected);
+}
+
+// Test observation_intervals
+
+#[test]
+fn test_observation_intervals_left_lesser_than_right() {
+    let minutes: Vec<u64> = (4..9).collect();
+    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 8)];
+
+    let intervals_in_seconds: Vec<(u64, u64)> = intervals
+        .iter()
+        .map(|(start, end)| (start * 60, end * 60))
+        .collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.observation_intervals(intervals_in_seconds);
+    let receipt = helper.execute_expect_success(false);
+
+    let result: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    let expected = get_intervals_from_observation_minutes(&minutes, &intervals);
+
+    assert_eq!(result, vec![expected]);
+}
+
+#[test]
+fn test_observation_intervals_left_bigger_than_right() {
+    let timestamps: Vec<u64> = (4..9).collect();
+    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 6)];
+
+    let intervals_in_seconds: Vec<(u64, u64)> = intervals
+        .iter()
+        .map(|(start, end)| (start * 60, end * 60))
+        .collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(intervals_in_seconds);
+
+    helper.execute_expect_failure(false);
+}
+
+#[test]
+fn test_observation_intervals_left_equal_right() {
+    let timestamps: Vec<u64> = (4..9).collect();
+    let intervals: Vec<(u64, u64)> = vec![(6, 7), (7, 7)];
+
+    let intervals_in_seconds: Vec<(u64, u64)> = intervals
+        .iter()
+        .map(|(start, end)| (start * 60, end * 60))
+        .collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(intervals_in_seconds);
+
+    helper.execute_expect_failure(false);
+}
+
+#[test]
+fn test_observation_intervals() {
+    let timestamps: Vec<u64> = (4..14).collect();
+
+    let intervals: Vec<(u64, u64)> = vec![(4, 13), (5, 7), (7, 12)];
+    let intervals_in_seconds: Vec<(u64, u64)> = intervals
+        .iter()
+        .map(|(start, end)| (start * 60, end * 60))
+        .collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(intervals_in_seconds);
+    let receipt = helper.execute_expect_success(false);
+    let outputs: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    let expected: Vec<Vec<ObservationInterval>> = vec![get_intervals_from_observation_minutes(
+        &timestamps,
+        &intervals,
+    )];
+
+    assert_eq!(outputs, expected);
+}
+
+// Deduplicated intervals
+
+/// Returns the intervals and the execution cost units consumed by `observation_intervals` or
+/// `observation_intervals_dedup`.
+fn intervals_and_cost(
+    helper: &mut OracleTestHelper,
+    intervals: Vec<(u64, u64)>,
+    dedup: bool,
+) -> (Vec<ObservationInterval>, u32) {
+    let method_name = if dedup {
+        helper.observation_intervals_dedup(intervals);
+        "observation_intervals_dedup"
+    } else {
+        helper.observation_intervals(intervals);
+        "observation_intervals"
+    };
+    let receipt = helper.execute_expect_success(false);
+    let outputs: Vec<Vec<ObservationInterval>> = receipt.outputs(method_name);
+    let cost = receipt
+        .execution_receipt
+        .fee_summary
+        .total_execution_cost_units_consumed;
+
+    (outputs[0].clone(), cost)
+}
+
+#[test]
+fn test_observation_intervals_dedup_identical_intervals() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let intervals: Vec<(u64, u64)> = vec![(5 * 60, 12 * 60); 3];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let (all, all_cost) = intervals_and_cost(&mut helper, intervals.clone(), false);
+    let (dedup, dedup_cost) = intervals_and_cost(&mut helper, intervals, true);
+
+    assert_eq!(dedup.len(), 3);
+    assert_eq!(dedup, all);
+    // The interval is only looked up once instead of three times
+    assert!(
+        dedup_cost < all_cost,
+        "{} cost units with deduplication, {} without",
+        dedup_cost,
+        all_cost
+    );
+}
+
+#[test]
+fn test_observation_intervals_dedup_preserves_order() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let intervals: Vec<(u64, u64)> = vec![(4, 13), (5, 7), (4, 13), (7, 12), (5, 7)];
+    let intervals_in_seconds: Vec<(u64, u64)> = intervals
+        .iter()
+        .map(|(start, end)| (start * 60, end * 60))
+        .collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    let (outputs, _) = intervals_and_cost(&mut helper, intervals_in_seconds, true);
+
+    assert_eq!(
+        outputs,
+        get_intervals_from_observation_minutes(&timestamps, &intervals)
+    );
+}
+
+#[test]
+fn test_observation_intervals_dedup_empty() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let (outputs, _) = intervals_and_cost(&mut helper, vec![], true);
+
+    assert_eq!(outputs, vec![]);
+}
+
+// Cached intervals
+
+/// Returns the results of two consecutive `observation_intervals_cached` calls and of
+/// `observation_intervals` for the same intervals.
+fn cached_and_fresh_intervals(
+    helper: &mut OracleTestHelper,
+    intervals: Vec<(u64, u64)>,
+) -> (Vec<Vec<ObservationInterval>>, Vec<ObservationInterval>) {
+    let receipt = helper
+        .observation_intervals_cached(intervals.clone())
+        .observation_intervals_cached(intervals.clone())
+        .observation_intervals(intervals)
+        .execute_expect_success(false);
+    let cached: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals_cached");
+    let fresh: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    (cached, fresh[0].clone())
+}
+
+// More intervals than the cache holds, some of them ending after the last observation at minute
+// 13 or at the current time
+#[test]
+fn test_observation_intervals_cached_matches_fresh() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_minutes(16);
+    let mut intervals: Vec<(u64, u64)> = (4..13).map(|start| (start * 60, 13 * 60)).collect();
+    intervals.extend(vec![
+        (5 * 60 + 30, 12 * 60 + 59),
+        (10 * 60, 16 * 60),
+        (10 * 60, INTERVAL_END_NOW),
+        (4 * 60, 13 * 60),
+    ]);
+
+    let (cached, fresh) = cached_and_fresh_intervals(&mut helper, intervals.clone());
+    assert_eq!(cached, vec![fresh.clone(), fresh]);
+
+    // A later transaction is served from the cache filled by the previous one
+    let (cached, fresh) = cached_and_fresh_intervals(&mut helper, intervals);
+    assert_eq!(cached, vec![fresh.clone(), fresh]);
+}
+
+// With a minimum spacing, the last observation is replaced in place by the next one, keeping
+// `last_observation_index` unchanged while the interval ending at it changes
+#[test]
+fn test_observation_intervals_cached_invalidated_by_new_observation() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper
+        .load_hook_auth()
+        .set_min_spacing_minutes(Some(5))
+        .execute_expect_success(false);
+    for (seconds, price_sqrt) in [
+        (120, pdec!(2)),
+        (180, pdec!(3)),
+        (240, pdec!(5)),
+        (300, pdec!(7)),
+    ] {
+        swap_with_liquidity(&mut helper, seconds, price_sqrt, pdec!(100));
+    }
+    let intervals = vec![(180, 300)];
+
+    let (cached, before) = cached_and_fresh_intervals(&mut helper, intervals.clone());
+    assert_eq!(cached, vec![before.clone(), before.clone()]);
+
+    swap_with_liquidity(&mut helper, 360, pdec!(11), pdec!(100));
+
+    let (cached, after) = cached_and_fresh_intervals(&mut helper, intervals);
+    assert_ne!(after, before);
+    assert_eq!(cached, vec![after.clone(), after]);
+}
+
+// A price_sqrt with only 10 significant digits within the 18 decimal places of a Decimal, e.g. for
+// a pair where one token is worth about 10^18 times less than the other
+#[test]
+fn test_observation_intervals_small_price_sqrt_precision() {
+    let price_sqrt = pdec!("0.000000001234567890987654321");
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for seconds in [120, 180, 240] {
+        swap_with_liquidity(&mut helper, seconds, price_sqrt, pdec!(100));
+    }
+
+    let outputs: Vec<Vec<ObservationInterval>> = helper
+        .observation_intervals(vec![(180, 240)])
+        .execute_expect_success(false)
+        .outputs("observation_intervals");
+    let interval = &outputs[0][0];
+
+    // Relative error below 10^-15, where truncating to a Decimal loses everything after 10^-10
+    let precise_error = (interval.price_sqrt - price_sqrt).checked_abs().unwrap() / price_sqrt;
+    let truncated = PreciseDecimal::from(truncate(interval.price_sqrt));
+    let truncated_error = (truncated - price_sqrt).checked_abs().unwrap() / price_sqrt;
+    assert!(precise_error < pdec!("0.000000000000001"));
+    assert!(truncated_error > pdec!("0.0000000001"));
+}
+
+#[test]
+fn test_observation_intervals_seconds_rounding() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let swap_seconds = convert_observation_minutes_to_swap_state_seconds(&timestamps);
+
+    let intervals_in_seconds: Vec<(u64, u64)> = vec![(635, 660), (660, 735)];
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(intervals_in_seconds.clone());
+    let receipt = helper.execute_expect_success(false);
+    let outputs: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    let expected: Vec<Vec<ObservationInterval>> = vec![get_intervals_from_swap_seconds(
+        &swap_seconds,
+        &intervals_in_seconds,
+    )];
+
+    assert_eq!(outputs, expected);
+}
+
+#[test_case(240, 780; "full_range")]
+#[test_case(635, 660; "seconds_rounding")]
+#[test_case(420, 720; "inner_range")]
+fn test_observation_interval_matches_intervals(start_seconds: u64, end_seconds: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(vec![(start_seconds, end_seconds)]);
+    helper.observation_interval(start_seconds, end_seconds);
+    let receipt = helper.execute_expect_success(false);
+
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let interval: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+
+    assert_eq!(interval, vec![intervals[0][0].clone()]);
+}
+
+#[test_case(240, 780, 9; "full_range")]
+#[test_case(635, 660, 1; "seconds_rounding")]
+#[test_case(420, 720, 5; "inner_range")]
+fn test_observation_interval_duration_minutes(start_seconds: u64, end_seconds: u64, expected: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(vec![(start_seconds, end_seconds)]);
+    helper.observation_intervals_harmonic(vec![(start_seconds, end_seconds)]);
+    let receipt = helper.execute_expect_success(false);
+
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let harmonic: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals_harmonic");
+
+    assert_eq!(intervals[0][0].duration_minutes, expected);
+    assert_eq!(harmonic[0][0].duration_minutes, expected);
+}
+
+#[test]
+fn test_observation_intervals_price() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let intervals = vec![(240, 780), (635, 660), (420, 720), (300, 361)];
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(intervals.clone());
+    helper.observation_intervals_harmonic(intervals);
+    let receipt = helper.execute_expect_success(false);
+
+    let geometric: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let harmonic: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals_harmonic");
+
+    for interval in geometric[0].iter() {
+        assert_eq!(
+            interval.price,
+            truncate(interval.price_sqrt * interval.price_sqrt)
+        );
+    }
+    // The harmonic price and price_sqrt are both taken from the accumulated inverse, so they
+    // only agree up to rounding
+    for interval in harmonic[0].iter() {
+        let squared = truncate(interval.price_sqrt * interval.price_sqrt);
+        assert!((squared - interval.price).checked_abs().unwrap() < dec!("0.000000000001"));
+    }
+}
+
+#[test_case(240, 780; "full_range")]
+#[test_case(635, 660; "seconds_rounding")]
+#[test_case(420, 720; "inner_range")]
+fn test_observation_intervals_detailed_reproducible(start_seconds: u64, end_seconds: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals_detailed(vec![(start_seconds, end_seconds)]);
+    helper.observation_intervals(vec![(start_seconds, end_seconds)]);
+    let receipt = helper.execute_expect_success(false);
+
+    let detailed: Vec<Vec<ObservationIntervalDetailed>> =
+        receipt.outputs("observation_intervals_detailed");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let detailed = &detailed[0][0];
+    let interval = &intervals[0][0];
+
+    assert_eq!(
+        (detailed.start, detailed.end, detailed.price_sqrt),
+        (interval.start, interval.end, interval.price_sqrt)
+    );
+    assert_eq!(
+        geometric_mean(
+            detailed.start / 60,
+            detailed.end / 60,
+            detailed.acc_left,
+            detailed.acc_right
+        ),
+        detailed.price_sqrt
+    );
+}
+
+// The endpoints reproduce the geometric mean, whether they are stored, interpolated or
+// extrapolated
+#[test_case(240, 600; "stored")]
+#[test_case(330, 500; "interpolated")]
+#[test_case(480, 840; "extrapolated")]
+#[test_case(300, INTERVAL_END_NOW; "end_now")]
+fn test_interval_endpoints_reproduce_price_sqrt(start_seconds: u64, end_seconds: u64) {
+    let timestamps: Vec<u64> = vec![4, 7, 10];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(14 * 60 + 30);
+
+    let receipt = helper
+        .interval_endpoints(start_seconds, end_seconds)
+        .observation_intervals(vec![(start_seconds, end_seconds)])
+        .execute_expect_success(false);
+    let endpoints: Vec<(AccumulatedObservation, AccumulatedObservation)> =
+        receipt.outputs("interval_endpoints");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let (left, right) = &endpoints[0];
+    let interval = &intervals[0][0];
+
+    assert_eq!(
+        (left.timestamp, right.timestamp),
+        (interval.start, interval.end)
+    );
+    assert_eq!(
+        geometric_mean(
+            left.timestamp / 60,
+            right.timestamp / 60,
+            left.price_sqrt_log_acc,
+            right.price_sqrt_log_acc
+        ),
+        interval.price_sqrt
+    );
+}
+
+#[test_case(240, 600; "stored")]
+#[test_case(330, 500; "interpolated")]
+#[test_case(480, 840; "extrapolated")]
+#[test_case(300, INTERVAL_END_NOW; "end_now")]
+fn test_accumulator_delta_reproduces_price_sqrt(start_seconds: u64, end_seconds: u64) {
+    let timestamps: Vec<u64> = vec![4, 7, 10];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(14 * 60 + 30);
+
+    let receipt = helper
+        .accumulator_delta(start_seconds, end_seconds)
+        .observation_intervals(vec![(start_seconds, end_seconds)])
+        .execute_expect_success(false);
+    let deltas: Vec<(Decimal, u64)> = receipt.outputs("accumulator_delta");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let (delta, minutes) = deltas[0];
+    let interval = &intervals[0][0];
+
+    assert_eq!(minutes, interval.duration_minutes);
+    assert_eq!(
+        (PreciseDecimal::from(delta) / minutes).exp().unwrap(),
+        interval.price_sqrt
+    );
+}
+
+// An open-ended interval equals the interval up to the current time rounded down to the minute
+#[test_case(1200; "minute_boundary")]
+#[test_case(1234; "mid_minute")]
+fn test_observation_intervals_end_now(now_seconds: u64) {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(now_seconds);
+
+    let receipt = helper
+        .observation_intervals(vec![(300, INTERVAL_END_NOW), (300, 1200)])
+        .try_observation_interval(300, INTERVAL_END_NOW)
+        .average_liquidity(300, INTERVAL_END_NOW)
+        .average_liquidity(300, 1200)
+        .execute_expect_success(false);
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let try_intervals: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval");
+    let liquidities: Vec<Decimal> = receipt.outputs("average_liquidity");
+
+    assert_eq!(intervals[0][0], intervals[0][1]);
+    assert_eq!(intervals[0][0].end, 1200);
+    assert_eq!(try_intervals, vec![Ok(intervals[0][1].clone())]);
+    assert_eq!(liquidities[0], liquidities[1]);
+}
+
+#[test]
+fn test_try_observation_interval_end_now_same_minute() {
+    let timestamps: Vec<u64> = (4..14).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.jump_to_timestamp_seconds(1234);
+
+    let outputs: Vec<Result<ObservationInterval, OracleError>> = helper
+        .try_observation_interval(1210, INTERVAL_END_NOW)
+        .execute_expect_success(false)
+        .outputs("try_observation_interval");
+
+    assert_eq!(outputs, vec![Err(OracleError::IntervalNotIncreasing)]);
+}
+
+#[test]
+fn test_observation_interval_left_equal_right() {
+    let timestamps: Vec<u64> = (4..9).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_interval(7 * 60, 7 * 60 + 59);
+
+    helper.execute_expect_failure(false);
+}
+
+#[test_case(300, 1200, "right bound of interval [300, 1200] is in the future"; "right_in_future")]
+#[test_case(60, 420, "left bound of interval [60, 420] is older than the oldest observation"; "left_too_old")]
+#[test_case(60, 180, "Interval [60, 180] lies entirely before the oldest observation at 240"; "both_too_old")]
+#[test_case(60, 240, "left bound of interval [60, 240] is older than the oldest observation"; "right_at_oldest")]
+#[test_case(300, u64::MAX, "right bound of interval [300, 18446744073709551615] is in the future"; "right_max")]
+#[test_case(u64::MAX - 60, u64::MAX, "left bound of interval [18446744073709551555, 18446744073709551615] is in the future"; "left_max")]
+fn test_observation_interval_bound_out_of_range(
+    start_seconds: u64,
+    end_seconds: u64,
+    expected_message: &str,
+) {
+    let timestamps: Vec<u64> = (4..9).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+    helper.observation_intervals(vec![(start_seconds, end_seconds)]);
+
+    let receipt = helper.execute_expect_failure(false);
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains(expected_message));
+}
+
+// Boundary of the last observation
+
+// The last observation is stored at minute 3 by the swap at 180, which starts minute 3 at the
+// price_sqrt 2. The swap at 200 changes it to 4 mid-minute, i.e. minute 3 averages
+// (2 * 20 + 4 * 40) / 60 = 10 / 3.
+fn helper_with_boundary_swaps() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_with_liquidity(&mut helper, 120, pdec!(1), pdec!(100));
+    swap_with_liquidity(&mut helper, 180, pdec!(2), pdec!(100));
+    swap_with_liquidity(&mut helper, 200, pdec!(4), pdec!(100));
+
+    helper
+}
+
+#[test]
+fn test_observation_at_last_observation_mid_minute() {
+    let mut helper = helper_with_boundary_swaps();
+
+    let observations: Vec<AccumulatedObservation> = helper
+        .observation(180)
+        .observation(200)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    // The current minute is still accumulating, so both return the stored observation
+    assert_eq!(observations[0], observations[1]);
+    assert_eq!(observations[0].timestamp, 180);
+    assert_eq!(observations[0].swap_count_acc, 1);
+    assert_eq!(observations[0].volume_acc, dec!(1));
+}
+
+#[test]
+fn test_observation_one_minute_after_last_observation() {
+    let mut helper = helper_with_boundary_swaps();
+    helper.jump_to_timestamp_seconds(240);
+
+    let observations: Vec<AccumulatedObservation> = helper
+        .observation(180)
+        .observation(240)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    // Only minute 3 is added on top of the last observation, with both of its swaps
+    assert_eq!(observations[1].timestamp, 240);
+    assert_eq!(observations[1].swap_count_acc, 3);
+    assert_eq!(observations[1].volume_acc, dec!(3));
+    assert_eq!(
+        observations[1].liquidity_acc - observations[0].liquidity_acc,
+        dec!(100)
+    );
+}
+
+#[test]
+fn test_observation_intervals_across_last_observation() {
+    let mut helper = helper_with_boundary_swaps();
+    helper.jump_to_timestamp_seconds(240);
+
+    let outputs: Vec<Vec<ObservationInterval>> = helper
+        .observation_intervals(vec![(120, 180), (180, 240), (120, 240)])
+        .execute_expect_success(false)
+        .outputs("observation_intervals");
+    let price_sqrts: Vec<PreciseDecimal> = outputs[0]
+        .iter()
+        .map(|interval| interval.price_sqrt)
+        .collect();
+
+    let expected = vec![
+        pdec!(1),
+        pdec!(10) / 3,
+        (pdec!(10) / 3).checked_sqrt().unwrap(),
+    ];
+    for (price_sqrt, expected) in price_sqrts.into_iter().zip(expected) {
+        assert!((price_sqrt - expected).checked_abs().unwrap() < pdec!("0.000000000001"));
+    }
+}
+
+#[test]
+fn test_extrapolated_observation_continuous_with_stored() {
+    let mut helper = helper_with_boundary_swaps();
+    helper.jump_to_timestamp_seconds(240);
+
+    let extrapolated: Vec<AccumulatedObservation> = helper
+        .observation(240)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    // The next swap stores the observation of minute 4 from the same state of minute 3
+    swap_with_liquidity(&mut helper, 240, pdec!(8), pdec!(100));
+    let stored: Vec<AccumulatedObservation> = helper
+        .observation(240)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    assert_eq!(extrapolated, stored);
+}
+
+// Accumulation overflow
+
+#[test]
+fn test_accumulated_log_matches_formula() {
+    let acc_value = dec!("12.5");
+    let finalized = pdec!("1.5");
+    let last_value = pdec!("2.5");
+
+    let result = accumulated_log(acc_value, finalized, last_value, 4);
+
+    assert_eq!(result, acc_value + log(finalized) + log(last_value) * 3);
+}
+
+#[test]
+fn test_accumulated_log_first_minute_ignores_last_value() {
+    let result = accumulated_log(dec!(0), pdec!("1.5"), pdec!("2.5"), 1);
+
+    assert_eq!(result, log(pdec!("1.5")));
+}
+
+// The first observation is accumulated from an empty one: 2 during [135, 150) and 5 during
+// [150, 180) finalize minute 2 to an average of 4, and 5 is kept for any idle minute after it
+#[test_case(190, log(pdec!(4)); "next_minute")]
+#[test_case(310, log(pdec!(4)) + log(pdec!(5)) * 2; "after_idle_minutes")]
+fn test_first_observation_accumulator(seconds: u64, expected: Decimal) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_with_liquidity(&mut helper, 135, pdec!(2), pdec!(100));
+    swap_with_liquidity(&mut helper, 150, pdec!(5), pdec!(100));
+    swap_with_liquidity(&mut helper, seconds, pdec!(7), pdec!(100));
+
+    let receipt = helper.observation_at_index(0).execute_expect_success(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = receipt.outputs("observation_at_index");
+
+    let observation = outputs[0].clone().unwrap();
+    assert_eq!(observation.timestamp, seconds / 60);
+    assert_eq!(observation.price_sqrt_log_acc, expected);
+}
+
+// The model of the test helper agrees with the on-chain accumulation for the first observation
+#[test_case(vec![60, 120]; "next_minute")]
+#[test_case(vec![90, 150]; "next_minute_not_aligned")]
+#[test_case(vec![60, 240]; "after_idle_minutes")]
+fn test_first_observation_matches_model(seconds: Vec<u64>) {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    let receipt = helper.observation_at_index(0).execute_expect_success(false);
+    let outputs: Vec<Option<AccumulatedObservation>> = receipt.outputs("observation_at_index");
+
+    let (_, expected, _) = generate_oracle_data(&seconds);
+    let observation = outputs[0].clone().unwrap();
+    assert_eq!(observation.timestamp * 60, expected[0].timestamp);
+    assert_eq!(
+        observation.price_sqrt_log_acc,
+        expected[0].price_sqrt_log_acc
+    );
+}
+
+#[test]
+fn test_accumulated_log_saturates_at_max() {
+    let result = accumulated_log(
+        Decimal::MAX - dec!(10),
+        pdec!("1000000"),
+        pdec!("1000000"),
+        u64::MAX,
+    );
+
+    assert_eq!(result, Decimal::MAX);
+}
+
+#[test]
+fn test_accumulated_log_saturates_at_min() {
+    let result = accumulated_log(
+        Decimal::MIN + dec!(10),
+        pdec!("0.000001"),
+        pdec!("0.000001"),
+        u64::MAX,
+    );
+
+    assert_eq!(result, Decimal::MIN);
+}
+
+#[test]
+fn test_accumulated_log_large_gap_does_not_panic() {
+    let result = accumulated_log(dec!(0), pdec!("1000000"), pdec!("1000000"), u64::MAX);
+
+    assert_eq!(result, log(pdec!("1000000")) * Decimal::from(u64::MAX));
+}
+
+// Rebase
+
+const REBASE_INTERVALS: [(u64, u64); 4] = [(120, 180), (120, 420), (180, 300), (250, 410)];
+
+fn observation_intervals_outputs(helper: &mut OracleTestHelper) -> Vec<Vec<ObservationInterval>> {
+    helper
+        .observation_intervals(REBASE_INTERVALS.to_vec())
+        .execute_expect_success(false)
+        .outputs("observation_intervals")
+}
+
+fn oldest_observation(helper: &mut OracleTestHelper) -> AccumulatedObservation {
+    let outputs: Vec<Option<AccumulatedObservation>> = helper
+        .observation_at_index(0)
+        .execute_expect_success(false)
+        .outputs("observation_at_index");
+    outputs[0].clone().unwrap()
+}
+
+#[test]
+fn test_rebase_keeps_observation_intervals() {
+    let mut helper = helper_with_minute_prices();
+    let before = observation_intervals_outputs(&mut helper);
+
+    helper
+        .load_hook_auth()
+        .rebase()
+        .execute_expect_success(false);
+
+    assert_eq!(observation_intervals_outputs(&mut helper), before);
+}
+
+#[test]
+fn test_rebase_records_offset() {
+    let mut helper = helper_with_minute_prices();
+    let oldest = oldest_observation(&mut helper);
+
+    helper
+        .load_hook_auth()
+        .rebase()
+        .execute_expect_success(false);
+
+    let outputs: Vec<Decimal> = helper
+        .price_sqrt_log_acc_offset()
+        .execute_expect_success(false)
+        .outputs("price_sqrt_log_acc_offset");
+    assert_eq!(outputs, vec![oldest.price_sqrt_log_acc]);
+    assert_eq!(oldest_observation(&mut helper).price_sqrt_log_acc, dec!(0));
+}
+
+#[test]
+fn test_rebase_then_observe_matches_without_rebase() {
+    let mut rebased = helper_with_minute_prices();
+    let mut not_rebased = helper_with_minute_prices();
+    rebased
+        .load_hook_auth()
+        .rebase()
+        .execute_expect_success(false);
+
+    for helper in [&mut rebased, &mut not_rebased] {
+        swap_with_liquidity(helper, 480, TEST_DATAPOINTS[0], pdec!(100));
+        swap_with_liquidity(helper, 600, TEST_DATAPOINTS[1], pdec!(100));
+    }
+
+    let intervals = vec![(120, 600), (300, 540), (420, 600)];
+    let rebased_outputs: Vec<Vec<ObservationInterval>> = rebased
+        .observation_intervals(intervals.clone())
+        .execute_expect_success(false)
+        .outputs("observation_intervals");
+    let not_rebased_outputs: Vec<Vec<ObservationInterval>> = not_rebased
+        .observation_intervals(intervals)
+        .execute_expect_success(false)
+        .outputs("observation_intervals");
+    assert_eq!(rebased_outputs, not_rebased_outputs);
+}
+
+#[test]
+fn test_rebase_without_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .rebase()
+        .execute_expect_success(false);
+
+    let outputs: Vec<Decimal> = helper
+        .price_sqrt_log_acc_offset()
+        .execute_expect_success(false)
+        .outputs("price_sqrt_log_acc_offset");
+    assert_eq!(outputs, vec![dec!(0)]);
+}
+
+#[test]
+fn test_rebase_auth_failure() {
+    let mut helper = helper_with_minute_prices();
+
+    helper.rebase().execute_expect_failure(false);
+}
+
+// Before swap
+
+#[test]
+fn test_before_and_after_swap_prices_captured() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let before_states = BeforeSwapState::from_test_datapoints(
+        2,
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    let after_states = AfterSwapState::from_test_datapoints(
+        2,
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+
+    helper.jump_to_timestamp_seconds(130);
+    helper.load_hook_auth();
+    helper.before_swap(before_states[0].clone(), helper.y_address(), dec!(1));
+    helper.after_swap(after_states[1].clone(), helper.y_address(), dec!(1));
+    helper.swap_price_sqrt();
+    let receipt = helper.execute_expect_success(false);
+
+    let outputs: Vec<(Option<PreciseDecimal>, PreciseDecimal)> = receipt.outputs("swap_price_sqrt");
+
+    assert_eq!(
+        outputs,
+        vec![(Some(TEST_DATAPOINTS[0]), TEST_DATAPOINTS[1])]
+    );
+}
+
+#[test]
+fn test_before_swap_same_minute_no_observation() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.jump_to_timestamp_seconds(130);
+    helper
+        .load_hook_auth()
+        .after_swap_default()
+        .execute_expect_success(false);
+
+    helper.jump_to_timestamp_seconds(150);
+    helper.load_hook_auth();
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
+    helper.observations_stored();
+    let outputs: Vec<u16> = helper
+        .execute_expect_success(false)
+        .outputs("observations_stored");
+
+    assert_eq!(outputs, vec![0]);
+}
+
+#[test]
+fn test_before_swap_new_minute_stores_observation() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.jump_to_timestamp_seconds(130);
+    helper
+        .load_hook_auth()
+        .after_swap_default()
+        .execute_expect_success(false);
+
+    helper.jump_to_timestamp_seconds(190);
+    helper.load_hook_auth();
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
+    helper.observations_stored();
+    helper.observation(180);
+    let receipt = helper.execute_expect_success(false);
+
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    assert_eq!(stored, vec![1]);
+    assert_eq!(observations[0].timestamp, 180);
+    assert_eq!(
+        observations[0].price_sqrt_log_acc,
+        log(helper.after_swap_state_dummy().price_sqrt)
+    );
+}
+
+// Liquidity accumulator
+
+fn swap_with_liquidity(
+    helper: &mut OracleTestHelper,
+    seconds: u64,
+    price_sqrt: PreciseDecimal,
+    active_liquidity: PreciseDecimal,
+) {
+    let mut state = AfterSwapState::empty(
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    state.price_sqrt = price_sqrt;
+    state.active_liquidity = active_liquidity;
+
+    helper.jump_to_timestamp_seconds(seconds);
+    helper.load_hook_auth();
+    helper.after_swap(state, helper.y_address(), dec!(1));
+    helper.execute_expect_success(false);
+}
+
+#[test_case(120, 180, dec!(100))]
+#[test_case(120, 300, dec!(100))]
+#[test_case(150, 280, dec!(100))]
+fn test_average_liquidity_constant(start: u64, end: u64, expected: Decimal) {
+    let seconds: Vec<u64> = vec![60, 120, 180, 240, 300];
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    let receipt = helper
+        .average_liquidity(start, end)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("average_liquidity");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_average_liquidity_changing() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    swap_with_liquidity(&mut helper, 120, TEST_DATAPOINTS[0], pdec!(100));
+    swap_with_liquidity(&mut helper, 210, TEST_DATAPOINTS[1], pdec!(200));
+    swap_with_liquidity(&mut helper, 300, TEST_DATAPOINTS[2], pdec!(300));
+
+    // Minute 2: 100, minute 3: (100 * 30 + 200 * 30) / 60 = 150, minute 4: 200
+    let receipt = helper
+        .observation(180)
+        .observation(300)
+        .average_liquidity(180, 300)
+        .execute_expect_success(false);
+
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let liquidity_accs: Vec<Decimal> = observations.iter().map(|o| o.liquidity_acc).collect();
+    assert_eq!(liquidity_accs, vec![dec!(100), dec!(450)]);
+
+    let outputs: Vec<Decimal> = receipt.outputs("average_liquidity");
+    assert_eq!(outputs, vec![dec!(175)]);
+}
+
+#[test]
+fn test_average_liquidity_extrapolated() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    swap_with_liquidity(&mut helper, 120, TEST_DATAPOINTS[0], pdec!(100));
+    swap_with_liquidity(&mut helper, 210, TEST_DATAPOINTS[1], pdec!(200));
+    swap_with_liquidity(&mut helper, 300, TEST_DATAPOINTS[2], pdec!(300));
+
+    helper.jump_to_timestamp_seconds(420);
+    let receipt = helper
+        .average_liquidity(300, 420)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("average_liquidity");
+
+    assert_eq!(outputs, vec![dec!(300)]);
+}
+
+#[test]
+fn test_average_liquidity_left_equal_right() {
+    let seconds: Vec<u64> = vec![60, 120, 180];
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    helper
+        .average_liquidity(120, 150)
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_accumulated_value_formula() {
+    let result = accumulated_value(dec!(10), pdec!(150), pdec!(200), 3);
+
+    assert_eq!(result, dec!(10) + dec!(150) + dec!(200) * 2);
+}
+
+// Fee rate accumulator
+
+fn swap_with_fee_rate(
+    helper: &mut OracleTestHelper,
+    seconds: u64,
+    price_sqrt: PreciseDecimal,
+    input_fee_rate: Decimal,
+) {
+    let mut state = AfterSwapState::empty(
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    state.price_sqrt = price_sqrt;
+    state.input_fee_rate = input_fee_rate;
+
+    helper.jump_to_timestamp_seconds(seconds);
+    helper.load_hook_auth();
+    helper.after_swap(state, helper.y_address(), dec!(1));
+    helper.execute_expect_success(false);
+}
+
+// Minute 2: 0.003, minute 3: (0.003 * 30 + 0.01 * 30) / 60 = 0.0065, minute 4: 0.01 and
+// 0.005 from minute 5 on
+fn helper_with_fee_rates() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    swap_with_fee_rate(&mut helper, 120, TEST_DATAPOINTS[0], dec!("0.003"));
+    swap_with_fee_rate(&mut helper, 210, TEST_DATAPOINTS[1], dec!("0.01"));
+    swap_with_fee_rate(&mut helper, 240, TEST_DATAPOINTS[2], dec!("0.01"));
+    swap_with_fee_rate(&mut helper, 300, TEST_DATAPOINTS[3], dec!("0.005"));
+
+    helper
+}
+
+#[test]
+fn test_fee_rate_accumulated() {
+    let mut helper = helper_with_fee_rates();
+
+    let receipt = helper
+        .observation(180)
+        .observation(240)
+        .observation(300)
+        .execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let fee_rate_accs: Vec<Decimal> = observations.iter().map(|o| o.fee_rate_acc).collect();
+
+    assert_eq!(
+        fee_rate_accs,
+        vec![dec!("0.003"), dec!("0.0095"), dec!("0.0195")]
+    );
+}
+
+#[test_case(180, 240, dec!("0.0065"); "changed_within_minute")]
+#[test_case(240, 300, dec!("0.01"); "constant")]
+#[test_case(180, 300, dec!("0.00825"); "across_minutes")]
+#[test_case(210, 290, dec!("0.0065"); "rounded_to_minutes")]
+fn test_average_fee_rate(start: u64, end: u64, expected: Decimal) {
+    let mut helper = helper_with_fee_rates();
+
+    let receipt = helper
+        .average_fee_rate(start, end)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("average_fee_rate");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_average_fee_rate_extrapolated() {
+    let mut helper = helper_with_fee_rates();
+
+    helper.jump_to_timestamp_seconds(420);
+    let receipt = helper
+        .average_fee_rate(240, 420)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("average_fee_rate");
+
+    // Minute 4: 0.01, minutes 5 and 6: 0.005
+    assert_eq!(outputs, vec![dec!("0.02") / 3]);
+}
+
+#[test]
+fn test_average_fee_rate_kept_by_observe_at() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    swap_with_fee_rate(&mut helper, 120, TEST_DATAPOINTS[0], dec!("0.003"));
+    helper.jump_to_timestamp_seconds(190);
+    helper
+        .load_hook_auth()
+        .observe_at(TEST_DATAPOINTS[1], *TEST_LIQUIDITY, dec!(0), 190)
+        .execute_expect_success(false);
+
+    helper.jump_to_timestamp_seconds(300);
+    let receipt = helper
+        .average_fee_rate(180, 300)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("average_fee_rate");
+
+    assert_eq!(outputs, vec![dec!("0.003")]);
+}
+
+#[test]
+fn test_average_fee_rate_left_equal_right() {
+    let mut helper = helper_with_fee_rates();
+
+    helper
+        .average_fee_rate(180, 210)
+        .execute_expect_failure(false);
+}
+
+// Variance
+
+// The population variance of the log price, from the price_sqrt of every minute
+fn brute_force_variance(price_sqrts: &[PreciseDecimal]) -> Decimal {
+    let logs: Vec<Decimal> = price_sqrts
+        .iter()
+        .map(|&price_sqrt| log(price_sqrt))
+        .collect();
+    let count = logs.len() as u64;
+
+    let mean = logs.iter().fold(dec!(0), |sum, &x| sum + x) / count;
+    let mean_sq = logs.iter().fold(dec!(0), |sum, &x| sum + x * x) / count;
+
+    dec!(4) * (mean_sq - mean * mean)
+}
+
+fn helper_with_minute_prices() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    // Every swap at the start of a minute, minute 6 is idle and keeps the price of minute 5
+    swap_with_liquidity(&mut helper, 120, TEST_DATAPOINTS[0], pdec!(100));
+    swap_with_liquidity(&mut helper, 180, TEST_DATAPOINTS[1], pdec!(100));
+    swap_with_liquidity(&mut helper, 240, TEST_DATAPOINTS[2], pdec!(100));
+    swap_with_liquidity(&mut helper, 300, TEST_DATAPOINTS[3], pdec!(100));
+    swap_with_liquidity(&mut helper, 420, TEST_DATAPOINTS[4], pdec!(100));
+
+    helper
+}
+
+#[test_case(120, 420, vec![0, 1, 2, 3, 3])]
+#[test_case(180, 300, vec![1, 2])]
+#[test_case(240, 420, vec![2, 3, 3])]
+fn test_variance_brute_force(start: u64, end: u64, datapoints: Vec<usize>) {
+    let mut helper = helper_with_minute_prices();
+
+    let receipt = helper.variance(start, end).execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("variance");
+
+    let price_sqrts: Vec<PreciseDecimal> = datapoints.iter().map(|&i| TEST_DATAPOINTS[i]).collect();
+    assert_eq!(outputs, vec![brute_force_variance(&price_sqrts)]);
+}
+
+#[test]
+fn test_variance_single_minute() {
+    let seconds: Vec<u64> = vec![60, 120, 180, 240, 300];
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    let receipt = helper.variance(180, 240).execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("variance");
+
+    // A single minute has no variance
+    assert_eq!(outputs, vec![dec!(0)]);
+}
+
+#[test]
+fn test_variance_left_equal_right() {
+    let mut helper = helper_with_minute_prices();
+
+    helper.variance(120, 150).execute_expect_failure(false);
+}
+
+#[test]
+fn test_accumulated_log_squared_formula() {
+    let result = accumulated_log_squared(dec!(10), pdec!(2), pdec!(3), 3);
+
+    assert_eq!(
+        result,
+        dec!(10) + log(pdec!(2)) * log(pdec!(2)) + log(pdec!(3)) * log(pdec!(3)) * 2
+    );
+}
+
+// Log returns
+
+#[test_case(120, 420, vec![0, 1, 2, 3, 3])]
+#[test_case(180, 300, vec![1, 2])]
+#[test_case(240, 420, vec![2, 3, 3])]
+fn test_log_returns(start: u64, end: u64, datapoints: Vec<usize>) {
+    let mut helper = helper_with_minute_prices();
+
+    let receipt = helper.log_returns(start, end).execute_expect_success(false);
+    let outputs: Vec<Vec<(u64, Decimal)>> = receipt.outputs("log_returns");
+
+    let log_prices: Vec<Decimal> = datapoints
+        .iter()
+        .map(|&i| dec!(2) * log(TEST_DATAPOINTS[i]))
+        .collect();
+    let expected: Vec<(u64, Decimal)> = log_prices
+        .windows(2)
+        .enumerate()
+        .map(|(i, log_prices)| (start + (i as u64 + 1) * 60, log_prices[1] - log_prices[0]))
+        .collect();
+    assert_eq!(outputs, vec![expected]);
+}
+
+fn price_sqrt_log_acc_at(helper: &mut OracleTestHelper, seconds: u64) -> Decimal {
+    let outputs: Vec<AccumulatedObservation> = helper
+        .observation(seconds)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    outputs[0].price_sqrt_log_acc
+}
+
+#[test]
+fn test_log_returns_sum_to_log_price_change() {
+    let mut helper = helper_with_minute_prices();
+
+    let outputs: Vec<Vec<(u64, Decimal)>> = helper
+        .log_returns(120, 420)
+        .execute_expect_success(false)
+        .outputs("log_returns");
+    let sum = outputs[0]
+        .iter()
+        .fold(dec!(0), |sum, (_, log_return)| sum + *log_return);
+
+    // The log price of the last minute [360, 420) minus the one of the first minute [120, 180)
+    let first_log_price = dec!(2)
+        * (price_sqrt_log_acc_at(&mut helper, 180) - price_sqrt_log_acc_at(&mut helper, 120));
+    let last_log_price = dec!(2)
+        * (price_sqrt_log_acc_at(&mut helper, 420) - price_sqrt_log_acc_at(&mut helper, 360));
+    assert_eq!(sum, last_log_price - first_log_price);
+}
+
+#[test]
+fn test_log_returns_single_minute() {
+    let mut helper = helper_with_minute_prices();
+
+    let outputs: Vec<Vec<(u64, Decimal)>> = helper
+        .log_returns(180, 240)
+        .execute_expect_success(false)
+        .outputs("log_returns");
+
+    // A single minute has no minute before it to return from
+    assert_eq!(outputs, vec![vec![]]);
+}
+
+#[test]
+fn test_log_returns_left_equal_right() {
+    let mut helper = helper_with_minute_prices();
+
+    helper.log_returns(120, 150).execute_expect_failure(false);
+}
+
+#[test_case(LOG_RETURNS_MINUTES_MAX, true; "at_max")]
+#[test_case(LOG_RETURNS_MINUTES_MAX + 1, false; "above_max")]
+fn test_log_returns_minutes_max(minutes: u64, success: bool) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![4, 5]);
+    helper.jump_to_timestamp_minutes(4 + minutes);
+
+    helper.log_returns(240, (4 + minutes) * 60);
+    if success {
+        let outputs: Vec<Vec<(u64, Decimal)>> =
+            helper.execute_expect_success(false).outputs("log_returns");
+        assert_eq!(outputs[0].len() as u64, minutes - 1);
+    } else {
+        let receipt = helper.execute_expect_failure(false);
+        receipt.execution_receipt.expect_specific_failure(|error| {
+            format!("{:?}", error).contains("spans more than 240 minutes")
+        });
+    }
+}
+
+// Volume accumulator
+
+fn swap_with_volume(
+    helper: &mut OracleTestHelper,
+    seconds: u64,
+    swap_type: SwapType,
+    input_amount: Decimal,
+    output_amount: Decimal,
+) {
+    let mut state = AfterSwapState::empty(
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    state.swap_type = swap_type;
+    state.input_amount = input_amount;
+    state.output_amount = output_amount;
+
+    helper.jump_to_timestamp_seconds(seconds);
+    helper.load_hook_auth();
+    helper.after_swap(state, helper.y_address(), dec!(1));
+    helper.execute_expect_success(false);
+}
+
+fn helper_with_volume() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    // Volume in token x per minute: minute 2: 10 + 5, minute 3: 20, minute 5: 1
+    swap_with_volume(&mut helper, 120, SwapType::SellX, dec!(10), dec!(9));
+    swap_with_volume(&mut helper, 130, SwapType::BuyX, dec!(6), dec!(5));
+    swap_with_volume(&mut helper, 210, SwapType::SellX, dec!(20), dec!(18));
+    swap_with_volume(&mut helper, 300, SwapType::BuyX, dec!(2), dec!(1));
+
+    helper
+}
+
+#[test]
+fn test_volume_accumulated() {
+    let mut helper = helper_with_volume();
+
+    let receipt = helper
+        .observation(180)
+        .observation(300)
+        .execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let volume_accs: Vec<Decimal> = observations.iter().map(|o| o.volume_acc).collect();
+
+    assert_eq!(volume_accs, vec![dec!(15), dec!(35)]);
+}
+
+#[test_case(180, 300, dec!(20))]
+#[test_case(180, 240, dec!(20))]
+#[test_case(200, 299, dec!(20))]
+#[test_case(240, 300, dec!(0))]
+fn test_volume_interval(start: u64, end: u64, expected: Decimal) {
+    let mut helper = helper_with_volume();
+
+    let receipt = helper.volume(start, end).execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("volume");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_volume_extrapolated() {
+    let mut helper = helper_with_volume();
+    helper.jump_to_timestamp_seconds(420);
+
+    let receipt = helper
+        .volume(180, 420)
+        .volume(300, 360)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("volume");
+
+    assert_eq!(outputs, vec![dec!(21), dec!(1)]);
+}
+
+#[test]
+fn test_volume_left_equal_right() {
+    let mut helper = helper_with_volume();
+
+    helper.volume(180, 200).execute_expect_failure(false);
+}
+
+// Swap count
+
+fn helper_with_swap_counts() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    // Swaps per minute: minute 2: 3, minute 3: 1, minute 4: none (only a price before a swap),
+    // minute 5: 2
+    for seconds in [120, 130, 140, 200] {
+        swap_with_liquidity(&mut helper, seconds, pdec!(1), pdec!(100));
+    }
+    helper.jump_to_timestamp_seconds(250);
+    helper.load_hook_auth();
+    helper.before_swap(
+        helper.before_swap_state_dummy(),
+        helper.y_address(),
+        dec!(1),
+    );
+    helper.execute_expect_success(false);
+    for seconds in [300, 310] {
+        swap_with_liquidity(&mut helper, seconds, pdec!(1), pdec!(100));
+    }
+
+    helper
+}
+
+#[test]
+fn test_swap_count_accumulated() {
+    let mut helper = helper_with_swap_counts();
+
+    let receipt = helper
+        .observation(180)
+        .observation(240)
+        .observation(300)
+        .execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let swap_count_accs: Vec<u64> = observations.iter().map(|o| o.swap_count_acc).collect();
+
+    assert_eq!(swap_count_accs, vec![3, 4, 4]);
+}
+
+#[test_case(180, 300, 1)]
+#[test_case(180, 240, 1)]
+#[test_case(240, 300, 0)]
+#[test_case(180, 360, 3)]
+#[test_case(300, 360, 2)]
+fn test_swap_count_in_range(start: u64, end: u64, expected: u64) {
+    let mut helper = helper_with_swap_counts();
+    helper.jump_to_timestamp_seconds(360);
+
+    let receipt = helper
+        .swap_count_in_range(start, end)
+        .execute_expect_success(false);
+    let outputs: Vec<u64> = receipt.outputs("swap_count_in_range");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+// Spot price events
+
+// Swaps at the price_sqrt 2 and 3, returning the spot price events of both transactions
+fn swaps_with_spot_events(emit_spot_events: bool) -> Vec<SpotPriceEvent> {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper
+        .load_hook_auth()
+        .set_emit_spot_events(emit_spot_events)
+        .execute_expect_success(false);
+
+    let mut events: Vec<SpotPriceEvent> = vec![];
+    for (seconds, price_sqrt) in [(130, pdec!(2)), (200, pdec!(3))] {
+        let mut state = AfterSwapState::empty(
+            helper.pool_address.unwrap(),
+            helper.x_address(),
+            helper.y_address(),
+        );
+        state.price_sqrt = price_sqrt;
+        state.active_liquidity = pdec!(100);
+
+        helper.jump_to_timestamp_seconds(seconds);
+        helper.load_hook_auth();
+        helper.after_swap(state, helper.y_address(), dec!(1));
+        let receipt = helper.execute_expect_success(false);
+        let commit = receipt.execution_receipt.expect_commit_success();
+        events.extend(
+            commit
+                .application_events
+                .iter()
+                .filter(|(identifier, _)| identifier.1 == "SpotPriceEvent")
+                .map(|(_, data)| scrypto_decode::<SpotPriceEvent>(data).unwrap()),
+        );
+    }
+
+    events
+}
+
+#[test]
+fn test_spot_events_enabled() {
+    let events = swaps_with_spot_events(true);
+
+    assert_eq!(
+        events,
+        vec![
+            SpotPriceEvent {
+                timestamp_seconds: 130,
+                price_sqrt: pdec!(2),
+            },
+            SpotPriceEvent {
+                timestamp_seconds: 200,
+                price_sqrt: pdec!(3),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_spot_events_disabled() {
+    let events = swaps_with_spot_events(false);
+
+    assert_eq!(events, vec![]);
+}
+
+#[test]
+fn test_emit_spot_events_default_off() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let outputs: Vec<bool> = helper
+        .emit_spot_events()
+        .execute_expect_success(false)
+        .outputs("emit_spot_events");
+
+    assert_eq!(outputs, vec![false]);
+}
+
+#[test]
+fn test_set_emit_spot_events_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_emit_spot_events(true)
+        .execute_expect_failure(false);
+}
+
+// Keeper refresh
+
+// Swaps at price 1 followed by a quiet period, during which the pool price moved to 2
+#[test_case(false, pdec!(1); "stale")]
+#[test_case(true, pdec!(2); "refreshed")]
+fn test_refresh_from_pool_after_quiet_period(refresh: bool, expected: PreciseDecimal) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for seconds in [120, 180] {
+        swap_with_liquidity(&mut helper, seconds, pdec!(1), pdec!(100));
+    }
+
+    helper.jump_to_timestamp_seconds(600);
+    if refresh {
+        helper
+            .load_hook_auth()
+            .refresh_from_pool(pdec!(2))
+            .execute_expect_success(false);
+    }
+    helper.jump_to_timestamp_seconds(900);
+
+    let receipt = helper
+        .observation_interval(600, 900)
+        .average_liquidity(600, 900)
+        .swap_count_in_range(180, 900)
+        .execute_expect_success(false);
+    let intervals: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+    let liquidities: Vec<Decimal> = receipt.outputs("average_liquidity");
+    let swap_counts: Vec<u64> = receipt.outputs("swap_count_in_range");
+
+    assert!((intervals[0].price_sqrt - expected).checked_abs().unwrap() < pdec!("0.000000000001"));
+    // The refresh keeps the liquidity and is not counted as a swap, only the one at second 180
+    assert_eq!(liquidities, vec![dec!(100)]);
+    assert_eq!(swap_counts, vec![1]);
+}
+
+#[test]
+fn test_refresh_from_pool_without_swap_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(600);
+
+    helper
+        .load_hook_auth()
+        .refresh_from_pool(pdec!(2))
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_refresh_from_pool_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_with_liquidity(&mut helper, 120, pdec!(1), pdec!(100));
+    helper.jump_to_timestamp_seconds(600);
+
+    helper
+        .refresh_from_pool(pdec!(2))
+        .execute_expect_failure(false);
+}
+
+// Harmonic mean
+
+fn harmonic_and_geometric(
+    prices_sqrt: Vec<PreciseDecimal>,
+) -> (ObservationInterval, ObservationInterval) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    // One price per minute, starting at minute 2
+    for (i, &price_sqrt) in prices_sqrt.iter().enumerate() {
+        swap_with_liquidity(&mut helper, 120 + 60 * i as u64, price_sqrt, pdec!(100));
+    }
+
+    let end = 60 * (prices_sqrt.len() as u64 + 1);
+    let receipt = helper
+        .observation_intervals_harmonic(vec![(180, end)])
+        .observation_intervals(vec![(180, end)])
+        .execute_expect_success(false);
+    let harmonic: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals_harmonic");
+    let geometric: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    (harmonic[0][0].clone(), geometric[0][0].clone())
+}
+
+#[test]
+fn test_observation_intervals_harmonic_constant() {
+    let (harmonic, geometric) = harmonic_and_geometric(vec![pdec!(2); 4]);
+
+    assert_eq!(harmonic.price_sqrt, pdec!(2));
+    assert_eq!(harmonic.price, dec!(4));
+    assert!((geometric.price_sqrt - pdec!(2)).checked_abs().unwrap() < pdec!("0.000000000001"));
+}
+
+#[test]
+fn test_observation_intervals_harmonic_volatile() {
+    // The interval covers the minutes with the prices 1 and 16
+    let (harmonic, geometric) =
+        harmonic_and_geometric(vec![pdec!(2), pdec!(1), pdec!(4), pdec!(1)]);
+    let arithmetic = (dec!(1) + dec!(16)) / 2;
+
+    // 2 / (1 / 1 + 1 / 16), instead of 1.6² = 2.56 squaring the harmonic mean of price_sqrt
+    assert_eq!(harmonic.price, dec!(32) / 17);
+    assert!(harmonic.price < geometric.price);
+    assert!(geometric.price < arithmetic);
+}
+
+// One price_sqrt from minute 2 to the last swap
+fn helper_with_constant_price_sqrt(
+    price_sqrt: PreciseDecimal,
+    last_seconds: u64,
+) -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(last_seconds);
+
+    // Observing does not abort, whatever the harmonic accumulator does
+    helper
+        .load_hook_auth()
+        .observe_many(vec![
+            (120, price_sqrt, pdec!(100), dec!(0)),
+            (180, price_sqrt, pdec!(100), dec!(0)),
+            (last_seconds, price_sqrt, pdec!(100), dec!(0)),
+        ])
+        .execute_expect_success(false);
+    helper
+}
+
+#[test_case(pdec!("1000000000000"), dec!("1000000000000000000000000"); "high_price")]
+#[test_case(pdec!("0.0000000000000000001"), dec!(0); "low_price")]
+fn test_try_observation_interval_harmonic_tick_range(price_sqrt: PreciseDecimal, price: Decimal) {
+    let mut helper = helper_with_constant_price_sqrt(price_sqrt, 240);
+
+    let receipt = helper
+        .try_observation_interval_harmonic(180, 240)
+        .execute_expect_success(false);
+    let intervals: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval_harmonic");
+    let interval = intervals[0].clone().unwrap();
+
+    // A price of 1e-38 is below the precision of a Decimal, like for the geometric mean
+    assert_eq!(interval.price_sqrt, price_sqrt);
+    assert_eq!(interval.price, price);
+}
+
+// The inverse of price 3.24e38 has no digit left, the one of price 1e-38 saturates the
+// accumulator after about 579 minutes and the one of price 1e-60 at once
+#[test_case(pdec!("18000000000000000000"), 240; "zero_inverse")]
+#[test_case(pdec!("0.0000000000000000001"), 42000; "saturated_inverse")]
+#[test_case(pdec!("0.000000000000000000000000000001"), 240; "below_tick_range")]
+fn test_try_observation_interval_harmonic_overflow(price_sqrt: PreciseDecimal, last_seconds: u64) {
+    let mut helper = helper_with_constant_price_sqrt(price_sqrt, last_seconds);
+
+    let receipt = helper
+        .try_observation_interval_harmonic(180, last_seconds)
+        .execute_expect_success(false);
+    let intervals: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval_harmonic");
+    assert_eq!(intervals, vec![Err(OracleError::PriceOverflow)]);
+
+    let receipt = helper
+        .observation_intervals_harmonic(vec![(180, last_seconds)])
+        .execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("does not fit into a Decimal")
+    });
+}
+
+// Inverse direction
+
+fn inverse_and_geometric(prices_sqrt: Vec<PreciseDecimal>) -> (PreciseDecimal, PreciseDecimal) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    // One price per minute, starting at minute 2
+    for (i, &price_sqrt) in prices_sqrt.iter().enumerate() {
+        swap_with_liquidity(&mut helper, 120 + 60 * i as u64, price_sqrt, pdec!(100));
+    }
+
+    let end = 60 * (prices_sqrt.len() as u64 + 1);
+    let receipt = helper
+        .observation_intervals_inverse(vec![(180, end)])
+        .observation_intervals(vec![(180, end)])
+        .execute_expect_success(false);
+    let inverse: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals_inverse");
+    let geometric: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    (inverse[0][0].price_sqrt, geometric[0][0].price_sqrt)
+}
+
+#[test]
+fn test_observation_intervals_inverse_constant() {
+    let (inverse, _) = inverse_and_geometric(vec![pdec!(2); 4]);
+
+    assert!((inverse - pdec!("0.5")).checked_abs().unwrap() < pdec!("0.000000000001"));
+}
+
+#[test]
+fn test_observation_intervals_inverse_volatile() {
+    // The interval covers the minutes with the prices 1 and 4
+    let (inverse, geometric) = inverse_and_geometric(vec![pdec!(2), pdec!(1), pdec!(4), pdec!(1)]);
+    // Arithmetic mean of the inverse: (1 / 1 + 1 / 4) / 2
+    let arithmetic_inverse = pdec!("0.625");
+
+    // The geometric mean of the inverse is the inverse of the geometric mean: 1 / 2
+    assert!((inverse * geometric - pdec!(1)).checked_abs().unwrap() < pdec!("0.000000000001"));
+    assert!((inverse - pdec!("0.5")).checked_abs().unwrap() < pdec!("0.000000000001"));
+    assert!(inverse < arithmetic_inverse);
+}
+
+#[test]
+fn test_observation_inverse_negates_log_accumulator() {
+    let mut helper = helper_with_minute_prices();
+
+    let receipt = helper
+        .observation(270)
+        .observation_inverse(270)
+        .execute_expect_success(false);
+    let observation: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let inverse: Vec<AccumulatedObservation> = receipt.outputs("observation_inverse");
+
+    assert_eq!(
+        inverse[0].price_sqrt_log_acc,
+        -observation[0].price_sqrt_log_acc
+    );
+    assert_eq!(inverse[0].timestamp, observation[0].timestamp);
+    assert_eq!(
+        inverse[0].price_sqrt_log_sq_acc,
+        observation[0].price_sqrt_log_sq_acc
+    );
+}
+
+// Confidence
+
+// Swaps at the start of the minutes 2 to 6, 20 and 21, i.e. observations for the minutes 3 to 6,
+// 20 and 21, and synthetic ones within the gap if a maximum observation gap is configured
+fn helper_with_gap(max_observation_gap_minutes: Option<u64>) -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(30);
+    helper
+        .load_hook_auth()
+        .set_max_observation_gap_minutes(max_observation_gap_minutes)
+        .execute_expect_success(false);
+
+    for minute in (2..7).chain(20..22) {
+        swap_with_liquidity(&mut helper, minute * 60, pdec!(2), pdec!(100));
+    }
+    helper.jump_to_timestamp_seconds(1380);
+
+    helper
+}
+
+#[test_case(180, 420, 4, 4; "dense")]
+#[test_case(240, 360, 2, 2; "dense_inner")]
+#[test_case(360, 1200, 1, 14; "gap")]
+#[test_case(360, 1260, 2, 15; "gap_and_observation_after")]
+#[test_case(1200, 1320, 2, 2; "after_gap")]
+#[test_case(1260, INTERVAL_END_NOW, 1, 2; "extrapolated")]
+fn test_observation_intervals_with_confidence(
+    start: u64,
+    end: u64,
+    observed_minutes: u64,
+    duration_minutes: u64,
+) {
+    let mut helper = helper_with_gap(None);
+
+    let receipt = helper
+        .observation_intervals_with_confidence(vec![(start, end)])
+        .observation_intervals(vec![(start, end)])
+        .execute_expect_success(false);
+    let outputs: Vec<Vec<(ObservationInterval, Decimal)>> =
+        receipt.outputs("observation_intervals_with_confidence");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    let expected_confidence = Decimal::from(observed_minutes) / Decimal::from(duration_minutes);
+    assert_eq!(
+        outputs,
+        vec![vec![(intervals[0][0].clone(), expected_confidence)]]
+    );
+    assert_eq!(intervals[0][0].duration_minutes, duration_minutes);
+}
+
+#[test]
+fn test_observation_intervals_with_confidence_dense_vs_gap() {
+    let mut helper = helper_with_gap(None);
+
+    let receipt = helper
+        .observation_intervals_with_confidence(vec![(180, 360), (360, 1200)])
+        .execute_expect_success(false);
+    let outputs: Vec<Vec<(ObservationInterval, Decimal)>> =
+        receipt.outputs("observation_intervals_with_confidence");
+
+    assert_eq!(outputs[0][0].1, dec!(1));
+    assert!(outputs[0][1].1 < dec!("0.1"));
+}
+
+// The synthetic observations filling the gap are stored, but do not count as observed minutes
+#[test_case(None, 1; "gap_not_filled")]
+#[test_case(Some(1), 14; "gap_filled")]
+fn test_observation_intervals_with_confidence_synthetic_observations(
+    max_observation_gap_minutes: Option<u64>,
+    stored: u16,
+) {
+    let mut helper = helper_with_gap(max_observation_gap_minutes);
+
+    let receipt = helper
+        .observation_intervals_with_confidence(vec![(360, 1200)])
+        .observation_count_in_range(360, 1199)
+        .execute_expect_success(false);
+    let outputs: Vec<Vec<(ObservationInterval, Decimal)>> =
+        receipt.outputs("observation_intervals_with_confidence");
+    let counts: Vec<u16> = receipt.outputs("observation_count_in_range");
+
+    assert_eq!(counts, vec![stored]);
+    assert_eq!(outputs[0][0].1, dec!(1) / 14);
+}
+
+// TWAP prices
+
+#[test]
+fn test_twap_prices_square_of_price_sqrt() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for (i, price_sqrt) in [pdec!(2), pdec!(3), pdec!(2), pdec!(4)]
+        .into_iter()
+        .enumerate()
+    {
+        swap_with_liquidity(&mut helper, 120 + 60 * i as u64, price_sqrt, pdec!(100));
+    }
+
+    let intervals = vec![(180, 300), (240, 300)];
+    let receipt = helper
+        .twap_prices(intervals.clone())
+        .observation_intervals(intervals)
+        .execute_expect_success(false);
+    let prices: Vec<Vec<Decimal>> = receipt.outputs("twap_prices");
+    let observation_intervals: Vec<Vec<ObservationInterval>> =
+        receipt.outputs("observation_intervals");
+
+    let expected: Vec<Decimal> = observation_intervals[0]
+        .iter()
+        .map(|interval| truncate(interval.price_sqrt * interval.price_sqrt))
+        .collect();
+    assert_eq!(prices[0], expected);
+}
+
+// Composite TWAP
+
+#[test_case(vec![(120, 300), (300, 480)]; "two_intervals")]
+#[test_case(vec![(120, 180), (180, 420), (420, 480)]; "three_intervals")]
+#[test_case(vec![(300, 480), (120, 300)]; "unordered")]
+fn test_composite_twap_matches_spanning_interval(intervals: Vec<(u64, u64)>) {
+    let mut helper = helper_with_chart_prices();
+
+    let receipt = helper
+        .composite_twap(intervals)
+        .observation_intervals(vec![(120, 480)])
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("composite_twap");
+    let expected: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    assert_eq!(outputs, vec![expected[0][0].price]);
+}
+
+#[test]
+fn test_composite_twap_weighted_by_duration() {
+    let mut helper = helper_with_chart_prices();
+    let intervals = vec![(120, 180), (300, 480)];
+
+    let receipt = helper
+        .composite_twap(intervals.clone())
+        .observation_intervals_detailed(intervals)
+        .execute_expect_success(false);
+    let outputs: Vec<Decimal> = receipt.outputs("composite_twap");
+    let detailed: Vec<Vec<ObservationIntervalDetailed>> =
+        receipt.outputs("observation_intervals_detailed");
+
+    // One minute of the first interval and three minutes of the second one
+    let log_sum = detailed[0].iter().fold(dec!(0), |log_sum, interval| {
+        log_sum + interval.acc_right - interval.acc_left
+    });
+    let price_sqrt = geometric_mean(0, 4, dec!(0), log_sum);
+    assert_eq!(outputs, vec![truncate(price_sqrt * price_sqrt)]);
+}
+
+#[test]
+fn test_composite_twap_no_intervals() {
+    let mut helper = helper_with_chart_prices();
+
+    let receipt = helper.composite_twap(vec![]).execute_expect_failure(false);
+
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("At least one interval is required.")
+    });
+}
+
+// Chart series
+
+// The price_sqrt of minute m is m - 1, from minute 2 to minute 7
+fn helper_with_chart_prices() -> OracleTestHelper {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for minute in 2..8 {
+        swap_with_liquidity(
+            &mut helper,
+            minute * 60,
+            PreciseDecimal::from(minute - 1),
+            pdec!(100),
+        );
+    }
+    helper.jump_to_timestamp_seconds(480);
+
+    helper
+}
+
+// The price of a sample is the product of the price_sqrt of two minutes, or the square of one
+#[test_case(120, 480, 120, vec![(120, dec!(2)), (240, dec!(12)), (360, dec!(30))]; "aligned")]
+#[test_case(150, 480, 90, vec![(150, dec!(2)), (240, dec!(9)), (330, dec!(20))]; "not_aligned")]
+#[test_case(120, 479, 120, vec![(120, dec!(2)), (240, dec!(12))]; "remainder")]
+fn test_chart_series(
+    start_seconds: u64,
+    end_seconds: u64,
+    step_seconds: u64,
+    expected: Vec<(u64, Decimal)>,
+) {
+    let mut helper = helper_with_chart_prices();
+
+    let outputs: Vec<Vec<(u64, Decimal)>> = helper
+        .chart_series(start_seconds, end_seconds, step_seconds)
+        .execute_expect_success(false)
+        .outputs("chart_series");
+
+    assert_eq!(outputs[0].len(), expected.len());
+    for ((timestamp, price), (expected_timestamp, expected_price)) in
+        outputs[0].iter().zip(expected)
+    {
+        assert_eq!(*timestamp, expected_timestamp);
+        assert!((*price - expected_price).checked_abs().unwrap() < dec!("0.000000000001"));
+    }
+}
+
+#[test_case(120, u64::MAX, u64::MAX, true; "step_max")]
+#[test_case(u64::MAX - 60, u64::MAX, 60, false; "start_max")]
+#[test_case(120, u64::MAX, 60, false; "end_max")]
+fn test_chart_series_max_bounds(
+    start_seconds: u64,
+    end_seconds: u64,
+    step_seconds: u64,
+    expect_empty: bool,
+) {
+    let mut helper = helper_with_chart_prices();
+
+    helper.chart_series(start_seconds, end_seconds, step_seconds);
+    if expect_empty {
+        let outputs: Vec<Vec<(u64, Decimal)>> =
+            helper.execute_expect_success(false).outputs("chart_series");
+        assert_eq!(outputs, vec![vec![]]);
+    } else {
+        let receipt = helper.execute_expect_failure(false);
+        receipt
+            .execution_receipt
+            .expect_specific_failure(|error| format!("{:?}", error).contains("is in the future"));
+    }
+}
+
+#[test]
+fn test_chart_series_step_below_minute_fails() {
+    let mut helper = helper_with_chart_prices();
+
+    helper
+        .chart_series(120, 480, 30)
+        .execute_expect_failure(false);
+}
+
+// Downsample
+
+#[test_case(3, vec![4, 7, 10, 13, 16, 17]; "step_three")]
+#[test_case(1, (4..18).collect(); "step_one")]
+#[test_case(13, vec![4, 17]; "step_span")]
+#[test_case(100, vec![4, 17]; "step_beyond_span")]
+fn test_downsample(step_minutes: u64, expected_minutes: Vec<u64>) {
+    let minutes: Vec<u64> = vec![4, 5, 9, 10, 17];
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let expected_seconds: Vec<u64> = expected_minutes.iter().map(|minute| minute * 60).collect();
+    let receipt = helper
+        .downsample(step_minutes)
+        .observation_batch(expected_seconds.clone())
+        .execute_expect_success(false);
+    let outputs: Vec<Vec<AccumulatedObservation>> = receipt.outputs("downsample");
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    let timestamps: Vec<u64> = outputs[0]
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+    assert_eq!(timestamps, expected_seconds);
+    assert_eq!(outputs[0], observations);
+}
+
+#[test]
+fn test_downsample_no_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let outputs: Vec<Vec<AccumulatedObservation>> = helper
+        .downsample(5)
+        .execute_expect_success(false)
+        .outputs("downsample");
+
+    assert_eq!(outputs, vec![vec![]]);
+}
+
+#[test]
+fn test_downsample_step_zero_fails() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    helper.downsample(0).execute_expect_failure(false);
+}
+
+// TWAP over the last window
+
+fn twap_last_pair(
+    helper: &mut OracleTestHelper,
+    window_seconds: u64,
+) -> (Vec<ObservationInterval>, ObservationInterval) {
+    let receipt = helper
+        .twap_last_cached(window_seconds)
+        .twap_last_cached(window_seconds)
+        .twap_last(window_seconds)
+        .execute_expect_success(false);
+    let cached: Vec<ObservationInterval> = receipt.outputs("twap_last_cached");
+    let fresh: Vec<ObservationInterval> = receipt.outputs("twap_last");
+
+    (cached, fresh[0].clone())
+}
+
+#[test]
+fn test_twap_last_matches_observation_interval() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for (i, price_sqrt) in [pdec!(2), pdec!(3), pdec!(2), pdec!(4)]
+        .into_iter()
+        .enumerate()
+    {
+        swap_with_liquidity(&mut helper, 120 + 60 * i as u64, price_sqrt, pdec!(100));
+    }
+
+    let receipt = helper
+        .twap_last(120)
+        .observation_interval(180, 300)
+        .execute_expect_success(false);
+    let twap: Vec<ObservationInterval> = receipt.outputs("twap_last");
+    let interval: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+
+    assert_eq!(twap, interval);
+}
+
+#[test_case(4..8; "not_full")]
+#[test_case(4..20; "wrapped")]
+fn test_twap_full_history_matches_observation_intervals(minutes: Range<u64>) {
+    let minutes: Vec<u64> = minutes.collect();
+    let last_minute = *minutes.last().unwrap();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_minutes(last_minute + 2);
+
+    let receipt = helper.oldest_observation_at().execute_expect_success(false);
+    let oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+
+    let receipt = helper
+        .twap_full_history()
+        .observation_intervals(vec![(oldest[0].unwrap(), (last_minute + 2) * 60)])
+        .execute_expect_success(false);
+    let full_history: Vec<ObservationInterval> = receipt.outputs("twap_full_history");
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+
+    assert_eq!(full_history, intervals[0]);
+}
+
+// Windows exceeding the stored history are clamped to the oldest observation
+#[test_case(vec![4], 5, 3600, 240; "one_minute_of_history")]
+#[test_case((4..8).collect(), 10, 1_000_000, 240; "long_window")]
+#[test_case((4..8).collect(), 10, u64::MAX, 240; "maximum_window")]
+#[test_case((4..20).collect(), 22, 3600, 600; "wrapped")]
+#[test_case((4..8).collect(), 10, 120, 480; "not_clamped")]
+fn test_twap_last_clamped(minutes: Vec<u64>, now_minutes: u64, window_seconds: u64, start: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_minutes(now_minutes);
+
+    let receipt = helper
+        .twap_last(window_seconds)
+        .observation_interval(start, now_minutes * 60)
+        .execute_expect_success(false);
+    let twap: Vec<ObservationInterval> = receipt.outputs("twap_last");
+    let interval: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+
+    assert_eq!(twap, interval);
+}
+
+#[test_case(vec![], 5; "no_observations")]
+#[test_case(vec![4], 4; "single_minute")]
+fn test_twap_last_insufficient_history(minutes: Vec<u64>, now_minutes: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_minutes(now_minutes);
+
+    let receipt = helper.twap_last(3600).execute_expect_failure(false);
+
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("Less than one minute of history is available")
+    });
+}
+
+#[test]
+fn test_twap_full_history_single_minute() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![4]);
+    helper.jump_to_timestamp_minutes(4);
+
+    helper.twap_full_history().execute_expect_failure(false);
+}
+
+#[test]
+fn test_twap_last_cached_recomputed_after_observe() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    for (i, price_sqrt) in [pdec!(2), pdec!(3), pdec!(2), pdec!(4)]
+        .into_iter()
+        .enumerate()
+    {
+        swap_with_liquidity(&mut helper, 120 + 60 * i as u64, price_sqrt, pdec!(100));
+    }
+
+    let (cached, fresh) = twap_last_pair(&mut helper, 120);
+    assert_eq!(cached, vec![fresh.clone(), fresh]);
+
+    // A new minute without observations
+    helper.jump_to_timestamp_seconds(360);
+    let (cached, fresh) = twap_last_pair(&mut helper, 120);
+    assert_eq!(cached, vec![fresh.clone(), fresh]);
+
+    // A new observation within the same minute
+    swap_with_liquidity(&mut helper, 370, pdec!(8), pdec!(100));
+    let (cached, fresh) = twap_last_pair(&mut helper, 120);
+    assert_eq!(cached, vec![fresh.clone(), fresh]);
+}
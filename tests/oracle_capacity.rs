@@ -0,0 +1,774 @@
+mod helper;
+use helper::*;
+use oracle::{AccumulatedObservation, OracleError};
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use test_case::test_case;
+use test_oracle::test_oracle::AfterSwapState;
+
+/// Performs one swap per given second, with the test datapoints as prices starting at `first`.
+fn swap_at_seconds(helper: &mut OracleTestHelper, seconds: &[u64], first: usize) {
+    let states = AfterSwapState::from_test_datapoints(
+        first + seconds.len(),
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    for (&seconds_, state) in seconds.iter().zip(states[first..].iter()) {
+        helper.jump_to_timestamp_seconds(seconds_);
+        helper.load_hook_auth();
+        helper.after_swap(state.clone(), helper.y_address(), dec!(1));
+        helper.execute_expect_success(false);
+    }
+}
+
+// One swap per minute from minute 3 to 21, i.e. observations for the minutes 4 to 21
+#[test_case(8, 12; "not_full")]
+#[test_case(14, 15; "wrapped")]
+fn test_increase_capacity_continues_accumulation(swaps_before: usize, observations_limit: u16) {
+    let seconds: Vec<u64> = (3..22).map(|minute| minute * 60).collect();
+    let expected = get_observations_from_swap_seconds(&seconds);
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds[..swaps_before], 0);
+    helper
+        .load_hook_auth()
+        .increase_capacity(observations_limit)
+        .execute_expect_success(false);
+    swap_at_seconds(&mut helper, &seconds[swaps_before..], swaps_before);
+
+    let stored = expected.len().min(observations_limit as usize);
+    let expected: Vec<AccumulatedObservation> = expected[expected.len() - stored..].to_vec();
+    let targets: Vec<u64> = expected
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+
+    helper.observation_batch(targets);
+    helper.observations_limit();
+    helper.observations_stored();
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let limit: Vec<u16> = receipt.outputs("observations_limit");
+    let stored_outputs: Vec<u16> = receipt.outputs("observations_stored");
+
+    assert_eq!(observations, expected);
+    assert_eq!(limit, vec![observations_limit]);
+    assert_eq!(stored_outputs, vec![stored as u16]);
+}
+
+// Single observation capacity
+
+// One swap per minute from minute 2 to 5, i.e. observations for the minutes 3 to 5
+fn helpers_with_limit(observations_limit: u16) -> (OracleTestHelper, OracleTestHelper) {
+    let seconds: Vec<u64> = (2..6).map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(observations_limit);
+    swap_at_seconds(&mut helper, &seconds, 0);
+    helper.jump_to_timestamp_seconds(600);
+
+    let mut reference = OracleTestHelper::new();
+    reference.instantiate_instant();
+    swap_at_seconds(&mut reference, &seconds, 0);
+    reference.jump_to_timestamp_seconds(600);
+
+    (helper, reference)
+}
+
+#[test_case(300; "exact")]
+#[test_case(330; "exact_rounded")]
+#[test_case(480; "extrapolated")]
+#[test_case(600; "extrapolated_now")]
+fn test_single_observation_capacity(seconds: u64) {
+    let (mut helper, mut reference) = helpers_with_limit(1);
+
+    let receipt = helper
+        .observation(seconds)
+        .observations_stored()
+        .execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let expected: Vec<AccumulatedObservation> = reference
+        .observation(seconds)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    assert_eq!(observations, expected);
+    assert_eq!(stored, vec![1]);
+}
+
+// The only stored observation is both neighbors of any minute in range, whether it matches or not
+#[test_case(300; "exact")]
+#[test_case(330; "exact_rounded")]
+#[test_case(480; "extrapolated")]
+fn test_single_observation_capacity_neighbors(seconds: u64) {
+    let (mut helper, _) = helpers_with_limit(1);
+
+    let receipt = helper
+        .observation(300)
+        .observation_neighbors(seconds)
+        .execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let neighbors: Vec<(AccumulatedObservation, AccumulatedObservation)> =
+        receipt.outputs("observation_neighbors");
+
+    assert_eq!(
+        neighbors,
+        vec![(observations[0].clone(), observations[0].clone())]
+    );
+}
+
+// Targets older than the only stored observation, which would be served by the overwritten
+// observations or an interpolation between them, are out of range
+#[test]
+fn test_single_observation_capacity_older_fails() {
+    let (mut helper, mut reference) = helpers_with_limit(1);
+
+    reference.observation(270).execute_expect_success(false);
+    helper.observation(270).execute_expect_failure(false);
+}
+
+// Query read estimate
+
+// Observations for the minutes 3 to 2 + stored
+fn helper_with_ring(stored: u16) -> OracleTestHelper {
+    let entries: Vec<(u64, PreciseDecimal, PreciseDecimal, Decimal)> = (0..=stored as u64)
+        .map(|i| {
+            let price_sqrt = TEST_DATAPOINTS[i as usize % TEST_DATAPOINTS.len()];
+            ((2 + i) * 60, price_sqrt, *TEST_LIQUIDITY, dec!(0))
+        })
+        .collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(stored);
+    helper.jump_to_timestamp_seconds((4 + stored as u64) * 60);
+    helper
+        .load_hook_auth()
+        .observe_many(entries)
+        .execute_expect_success(false);
+
+    helper
+}
+
+#[test_case(3, 300, 2; "last")]
+#[test_case(3, 420, 2; "extrapolated")]
+#[test_case(3, 240, 5; "older")]
+#[test_case(20, 180, 9; "oldest_20")]
+#[test_case(150, 180, 12; "oldest_150")]
+#[test_case(150, 9120, 2; "last_150")]
+fn test_estimate_query_reads(stored: u16, seconds: u64, expected: u16) {
+    let mut helper = helper_with_ring(stored);
+
+    let outputs: Vec<u16> = helper
+        .estimate_query_reads(seconds)
+        .execute_expect_success(false)
+        .outputs("estimate_query_reads");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_estimate_query_reads_no_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let outputs: Vec<u16> = helper
+        .estimate_query_reads(60)
+        .execute_expect_success(false)
+        .outputs("estimate_query_reads");
+
+    assert_eq!(outputs, vec![0]);
+}
+
+/// Returns the estimated reads and the execution cost units consumed by a lookup.
+fn estimate_and_cost(helper: &mut OracleTestHelper, seconds: u64) -> (u16, u32) {
+    let estimates: Vec<u16> = helper
+        .estimate_query_reads(seconds)
+        .execute_expect_success(false)
+        .outputs("estimate_query_reads");
+    let receipt = helper.observation(seconds).execute_expect_success(false);
+    let cost = receipt
+        .execution_receipt
+        .fee_summary
+        .total_execution_cost_units_consumed;
+
+    (estimates[0], cost)
+}
+
+#[test]
+fn test_estimate_query_reads_ordering_matches_cost() {
+    let lookups: Vec<(u16, u32)> = [3, 20, 150]
+        .into_iter()
+        .flat_map(|stored| {
+            let mut helper = helper_with_ring(stored);
+            let last_seconds = (2 + stored as u64) * 60;
+            // The last observation, followed by the oldest one
+            vec![
+                estimate_and_cost(&mut helper, last_seconds),
+                estimate_and_cost(&mut helper, 180),
+            ]
+        })
+        .collect();
+
+    let oldest: Vec<(u16, u32)> = lookups.iter().skip(1).step_by(2).cloned().collect();
+    // Larger rings take more halvings to reach the oldest observation
+    for pair in oldest.windows(2) {
+        assert!(pair[0].0 < pair[1].0);
+        assert!(pair[0].1 < pair[1].1);
+    }
+    // Older observations are more expensive than the last one
+    for pair in lookups.chunks(2) {
+        assert!(pair[0].0 < pair[1].0);
+        assert!(pair[0].1 < pair[1].1);
+    }
+}
+
+// Minimum spacing
+
+// One swap per minute from minute 2 to 21, i.e. observations for the minutes 3 to 21
+#[test_case(None, (12..22).collect(); "none")]
+#[test_case(Some(1), (12..22).collect(); "one")]
+#[test_case(Some(5), vec![3, 8, 13, 18, 21]; "five")]
+#[test_case(Some(20), vec![3, 21]; "longer_than_history")]
+fn test_min_spacing_minutes(min_spacing_minutes: Option<u64>, expected_minutes: Vec<u64>) {
+    let seconds: Vec<u64> = (2..22).map(|minute| minute * 60).collect();
+    let expected_gaps: Vec<(u64, u64)> = expected_minutes
+        .iter()
+        .enumerate()
+        .map(|(i, &minute)| {
+            let gap = if i == 0 {
+                0
+            } else {
+                minute - expected_minutes[i - 1]
+            };
+            (minute * 60, gap)
+        })
+        .collect();
+    // The accumulators of the stored observations are exact
+    let expected: Vec<AccumulatedObservation> = get_observations_from_swap_seconds(&seconds)
+        .into_iter()
+        .filter(|observation| expected_minutes.contains(&(observation.timestamp / 60)))
+        .collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper
+        .load_hook_auth()
+        .set_min_spacing_minutes(min_spacing_minutes)
+        .execute_expect_success(false);
+    swap_at_seconds(&mut helper, &seconds, 0);
+
+    helper.observation_batch(expected_minutes.iter().map(|minute| minute * 60).collect());
+    helper.observation_gaps();
+    helper.min_spacing_minutes();
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let gaps: Vec<Vec<(u64, u64)>> = receipt.outputs("observation_gaps");
+    let spacing: Vec<Option<u64>> = receipt.outputs("min_spacing_minutes");
+
+    assert_eq!(gaps, vec![expected_gaps]);
+    assert_eq!(observations, expected);
+    assert_eq!(spacing, vec![min_spacing_minutes]);
+}
+
+#[test]
+fn test_set_min_spacing_minutes_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_min_spacing_minutes(Some(5))
+        .execute_expect_failure(false);
+}
+
+#[test_case(9; "decrease")]
+#[test_case(10; "same_limit")]
+fn test_increase_capacity_not_larger_fails(observations_limit: u16) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .increase_capacity(observations_limit)
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_increase_capacity_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.increase_capacity(20).execute_expect_failure(false);
+}
+
+// The stored observations are kept in any case, a limit that is not larger leaves the oracle
+// unchanged
+#[test_case(10, Err(OracleError::CapacityTooSmall), 10; "same_limit")]
+#[test_case(9, Err(OracleError::CapacityTooSmall), 10; "decrease")]
+#[test_case(20, Ok(()), 20; "increase")]
+#[test_case(u16::MAX, Ok(()), u16::MAX; "max")]
+fn test_try_increase_capacity(
+    observations_limit: u16,
+    expected: Result<(), OracleError>,
+    expected_limit: u16,
+) {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .observation(300)
+        .load_hook_auth()
+        .try_increase_capacity(observations_limit)
+        .observation(300)
+        .observations_limit()
+        .observations_stored()
+        .execute_expect_success(false);
+    let outputs: Vec<Result<(), OracleError>> = receipt.outputs("try_increase_capacity");
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let limit: Vec<u16> = receipt.outputs("observations_limit");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+
+    assert_eq!(outputs, vec![expected]);
+    assert_eq!(observations[1], observations[0]);
+    assert_eq!(limit, vec![expected_limit]);
+    assert_eq!(stored, vec![minutes.len() as u16]);
+}
+
+#[test]
+fn test_try_increase_capacity_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .try_increase_capacity(20)
+        .execute_expect_failure(false);
+}
+
+// Observation rate limit
+
+// One swap per minute from minute 2 to 21, i.e. observations for the minutes 3 to 21 flooding a
+// ring of 5 observations
+#[test_case(None, vec![17, 18, 19, 20, 21]; "none")]
+#[test_case(Some(1), vec![17, 18, 19, 20, 21]; "one")]
+#[test_case(Some(10), vec![6, 13, 14, 15, 21]; "ten")]
+#[test_case(Some(30), vec![3, 4, 5, 6, 21]; "longer_than_history")]
+fn test_min_retained_minutes(min_retained_minutes: Option<u64>, expected_minutes: Vec<u64>) {
+    let seconds: Vec<u64> = (2..22).map(|minute| minute * 60).collect();
+    // The accumulators of the stored observations are exact
+    let expected: Vec<AccumulatedObservation> = get_observations_from_swap_seconds(&seconds)
+        .into_iter()
+        .filter(|observation| expected_minutes.contains(&(observation.timestamp / 60)))
+        .collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(5);
+    helper
+        .load_hook_auth()
+        .set_min_retained_minutes(min_retained_minutes)
+        .execute_expect_success(false);
+    swap_at_seconds(&mut helper, &seconds, 0);
+
+    helper.observation_batch(expected_minutes.iter().map(|minute| minute * 60).collect());
+    helper.observation_gaps();
+    helper.min_retained_minutes();
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let gaps: Vec<Vec<(u64, u64)>> = receipt.outputs("observation_gaps");
+    let retained: Vec<Option<u64>> = receipt.outputs("min_retained_minutes");
+
+    let stored_minutes: Vec<u64> = gaps[0].iter().map(|(seconds, _)| seconds / 60).collect();
+    assert_eq!(stored_minutes, expected_minutes);
+    assert_eq!(observations, expected);
+    assert_eq!(retained, vec![min_retained_minutes]);
+    // The ring keeps covering the configured time depth, or all history if it is shorter
+    let covered = stored_minutes.last().unwrap() - stored_minutes[0];
+    assert!(covered >= min_retained_minutes.unwrap_or(0).min(21 - 3));
+}
+
+#[test]
+fn test_set_min_retained_minutes_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_min_retained_minutes(Some(30))
+        .execute_expect_failure(false);
+}
+
+// Maximum observation gap
+
+// Swaps in the minutes 2 and 3, where minute 3 is not flat, followed by a gap of three hours
+const SWAPS_BEFORE_GAP: [u64; 3] = [120, 180, 210];
+const SWAP_AFTER_GAP: u64 = 183 * 60;
+
+#[test_case(None, vec![3, 183]; "none")]
+#[test_case(Some(60), vec![3, 63, 123, 183]; "hourly")]
+#[test_case(Some(50), vec![3, 53, 103, 153, 183]; "not_aligned")]
+#[test_case(Some(180), vec![3, 183]; "gap_not_exceeded")]
+fn test_max_observation_gap_minutes(
+    max_observation_gap_minutes: Option<u64>,
+    expected_minutes: Vec<u64>,
+) {
+    let targets: Vec<u64> = expected_minutes[1..expected_minutes.len() - 1]
+        .iter()
+        .map(|minute| minute * 60)
+        .collect();
+
+    // Without a swap after the gap, the observations within the gap are extrapolated flat from
+    // the last active minute, which is exactly what the synthetic observations store
+    let mut extrapolated = OracleTestHelper::new();
+    extrapolated.instantiate_instant();
+    swap_at_seconds(&mut extrapolated, &SWAPS_BEFORE_GAP, 0);
+    extrapolated.jump_to_timestamp_seconds(SWAP_AFTER_GAP);
+    let expected: Vec<AccumulatedObservation> = if targets.is_empty() {
+        vec![]
+    } else {
+        extrapolated
+            .observation_batch(targets.clone())
+            .execute_expect_success(false)
+            .outputs("observation")
+    };
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper
+        .load_hook_auth()
+        .set_max_observation_gap_minutes(max_observation_gap_minutes)
+        .execute_expect_success(false);
+    swap_at_seconds(&mut helper, &SWAPS_BEFORE_GAP, 0);
+    swap_at_seconds(&mut helper, &[SWAP_AFTER_GAP], SWAPS_BEFORE_GAP.len());
+
+    helper.observation_gaps();
+    helper.max_observation_gap_minutes();
+    for index in 1..expected_minutes.len() as u16 - 1 {
+        helper.observation_at_index(index);
+    }
+    let receipt = helper.execute_expect_success(false);
+    let gaps: Vec<Vec<(u64, u64)>> = receipt.outputs("observation_gaps");
+    let max_gap: Vec<Option<u64>> = receipt.outputs("max_observation_gap_minutes");
+    let synthetic: Vec<Option<AccumulatedObservation>> = receipt.outputs("observation_at_index");
+
+    let stored_minutes: Vec<u64> = gaps[0].iter().map(|(seconds, _)| seconds / 60).collect();
+    assert_eq!(stored_minutes, expected_minutes);
+    assert_eq!(max_gap, vec![max_observation_gap_minutes]);
+    let synthetic: Vec<AccumulatedObservation> = synthetic
+        .into_iter()
+        .map(|observation| {
+            let mut observation = observation.unwrap();
+            observation.timestamp *= 60;
+            observation
+        })
+        .collect();
+    assert_eq!(synthetic, expected);
+}
+
+// A gap of 100 minutes needs 9 synthetic observations every 10 minutes, of which a ring of 5
+// only holds the last 4 next to the observation after the gap
+#[test]
+fn test_max_observation_gap_minutes_bounded_by_limit() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(5);
+    helper
+        .load_hook_auth()
+        .set_max_observation_gap_minutes(Some(10))
+        .execute_expect_success(false);
+    swap_at_seconds(&mut helper, &[120, 180, 103 * 60], 0);
+
+    let receipt = helper
+        .observation_gaps()
+        .observations_stored()
+        .execute_expect_success(false);
+    let gaps: Vec<Vec<(u64, u64)>> = receipt.outputs("observation_gaps");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+
+    let stored_minutes: Vec<u64> = gaps[0].iter().map(|(seconds, _)| seconds / 60).collect();
+    assert_eq!(stored_minutes, vec![63, 73, 83, 93, 103]);
+    assert_eq!(stored, vec![5]);
+}
+
+#[test]
+fn test_set_max_observation_gap_minutes_zero_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .set_max_observation_gap_minutes(Some(0))
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_set_max_observation_gap_minutes_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .set_max_observation_gap_minutes(Some(60))
+        .execute_expect_failure(false);
+}
+
+// Reserve capacity
+
+// One swap per minute from minute 3 to 21, i.e. observations for the minutes 4 to 21
+#[test_case(0, 20; "empty")]
+#[test_case(8, 12; "not_full")]
+#[test_case(14, 15; "wrapped")]
+fn test_reserve_capacity_queries_ignore_placeholders(swaps_before: usize, observations_limit: u16) {
+    let seconds: Vec<u64> = (3..22).map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds[..swaps_before], 0);
+    let receipt = helper
+        .oldest_observation_at()
+        .observations_stored()
+        .execute_expect_success(false);
+    let oldest_before: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+    let stored_before: Vec<u16> = receipt.outputs("observations_stored");
+
+    // Neither the stored observations nor the observable range change by reserving the slots
+    let receipt = helper
+        .load_hook_auth()
+        .reserve_capacity(observations_limit)
+        .oldest_observation_at()
+        .observations_stored()
+        .observation_at_index(stored_before[0])
+        .assert_invariants()
+        .execute_expect_success(false);
+    let oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let placeholder: Vec<Option<AccumulatedObservation>> = receipt.outputs("observation_at_index");
+
+    assert_eq!(oldest, oldest_before);
+    assert_eq!(stored, stored_before);
+    assert_eq!(placeholder, vec![None]);
+
+    // The placeholders are overwritten like empty slots
+    swap_at_seconds(&mut helper, &seconds[swaps_before..], swaps_before);
+
+    let expected = get_observations_from_swap_seconds(&seconds);
+    let stored = expected.len().min(observations_limit as usize);
+    let expected: Vec<AccumulatedObservation> = expected[expected.len() - stored..].to_vec();
+    let targets: Vec<u64> = expected
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+
+    helper.observation_batch(targets);
+    helper.observations_stored();
+    helper.assert_invariants();
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let stored_outputs: Vec<u16> = receipt.outputs("observations_stored");
+
+    assert_eq!(observations, expected);
+    assert_eq!(stored_outputs, vec![stored as u16]);
+}
+
+// Targets before the oldest observation are still out of range, instead of being served by a
+// placeholder with timestamp zero
+#[test]
+fn test_reserve_capacity_older_fails() {
+    let seconds: Vec<u64> = (3..8).map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds, 0);
+    helper
+        .load_hook_auth()
+        .reserve_capacity(20)
+        .execute_expect_success(false);
+
+    helper.observation(240).execute_expect_success(false);
+    helper.observation(180).execute_expect_failure(false);
+}
+
+#[test_case(9; "decrease")]
+#[test_case(10; "same_limit")]
+fn test_reserve_capacity_not_larger_fails(observations_limit: u16) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .reserve_capacity(observations_limit)
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_reserve_capacity_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.reserve_capacity(20).execute_expect_failure(false);
+}
+
+// Decrease capacity
+
+/// Asserts that exactly the given observations are stored and that the oracle state is consistent.
+fn assert_stored_observations(helper: &mut OracleTestHelper, expected: &[AccumulatedObservation]) {
+    let targets: Vec<u64> = expected
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+
+    helper.observation_batch(targets);
+    helper.oldest_observation_at();
+    helper.observations_stored();
+    helper.assert_invariants();
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+
+    assert_eq!(observations, expected);
+    assert_eq!(oldest, vec![Some(expected[0].timestamp)]);
+    assert_eq!(stored, vec![expected.len() as u16]);
+}
+
+// One swap per minute from minute 3 to 21, i.e. observations for the minutes 4 to 21 in a ring of
+// 10 observations
+#[test_case(11, 4; "last_index_at_top")]
+#[test_case(10, 4; "last_index_below_top")]
+#[test_case(15, 4; "wrapped")]
+#[test_case(15, 10; "wrapped_same_limit")]
+#[test_case(5, 8; "not_full")]
+#[test_case(11, 1; "single")]
+fn test_decrease_capacity_keeps_recent_observations(swaps_before: usize, observations_limit: u16) {
+    let seconds: Vec<u64> = (3..22).map(|minute| minute * 60).collect();
+    let expected = get_observations_from_swap_seconds(&seconds);
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds[..swaps_before], 0);
+    helper
+        .load_hook_auth()
+        .decrease_capacity(observations_limit)
+        .execute_expect_success(false);
+
+    // The most recent observations are retained and immediately readable
+    let observed = swaps_before - 1;
+    let retained = observed.min(observations_limit as usize);
+    assert_stored_observations(&mut helper, &expected[observed - retained..observed]);
+
+    // New observations continue the accumulation and wrap around within the new limit
+    swap_at_seconds(&mut helper, &seconds[swaps_before..], swaps_before);
+
+    let stored = expected.len().min(observations_limit as usize);
+    assert_stored_observations(&mut helper, &expected[expected.len() - stored..]);
+}
+
+// The slots beyond the new limit are cleared, so that growing the ring again does not expose
+// stale observations
+#[test]
+fn test_decrease_capacity_clears_slots_beyond_limit() {
+    let seconds: Vec<u64> = (3..14).map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds, 0);
+    helper
+        .load_hook_auth()
+        .decrease_capacity(4)
+        .execute_expect_success(false);
+
+    let receipt = helper
+        .load_hook_auth()
+        .increase_capacity(10)
+        .observations_stored()
+        .assert_invariants()
+        .verify_storage()
+        .execute_expect_success(false);
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let verified: Vec<bool> = receipt.outputs("verify_storage");
+
+    assert_eq!(stored, vec![4]);
+    assert_eq!(verified, vec![true]);
+}
+
+#[test_case(0; "zero")]
+#[test_case(11; "increase")]
+fn test_decrease_capacity_invalid_limit_fails(observations_limit: u16) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .load_hook_auth()
+        .decrease_capacity(observations_limit)
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_decrease_capacity_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper.decrease_capacity(5).execute_expect_failure(false);
+}
+
+// Freeze capacity
+
+// One swap per minute from minute 3 to 21, i.e. observations for the minutes 4 to 21 in a ring of
+// 10 observations, frozen after `swaps_before` swaps
+#[test_case(3; "two")]
+#[test_case(7; "not_full")]
+#[test_case(11; "full")]
+#[test_case(15; "wrapped")]
+fn test_freeze_capacity_at_current(swaps_before: usize) {
+    let seconds: Vec<u64> = (3..22).map(|minute| minute * 60).collect();
+    let expected = get_observations_from_swap_seconds(&seconds);
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds[..swaps_before], 0);
+    helper
+        .load_hook_auth()
+        .freeze_capacity_at_current()
+        .execute_expect_success(false);
+
+    let observed = swaps_before - 1;
+    let frozen = observed.min(10);
+    assert_stored_observations(&mut helper, &expected[observed - frozen..observed]);
+
+    // The ring holds exactly the frozen number of observations, evicting the oldest ones
+    swap_at_seconds(&mut helper, &seconds[swaps_before..], swaps_before);
+
+    assert_stored_observations(&mut helper, &expected[expected.len() - frozen..]);
+    let limit: Vec<u16> = helper
+        .observations_limit()
+        .execute_expect_success(false)
+        .outputs("observations_limit");
+    assert_eq!(limit, vec![frozen as u16]);
+}
+
+#[test_case(0; "empty")]
+#[test_case(2; "single")]
+fn test_freeze_capacity_at_current_too_few_observations_fails(swaps: usize) {
+    let seconds: Vec<u64> = (3..3 + swaps as u64).map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &seconds, 0);
+
+    let receipt = helper
+        .load_hook_auth()
+        .freeze_capacity_at_current()
+        .execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("at least 2 observations")
+    });
+}
+
+#[test]
+fn test_freeze_capacity_at_current_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    swap_at_seconds(&mut helper, &[180, 240, 300], 0);
+
+    helper
+        .freeze_capacity_at_current()
+        .execute_expect_failure(false);
+}
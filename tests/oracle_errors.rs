@@ -0,0 +1,333 @@
+mod helper;
+use helper::*;
+use oracle::{
+    oracle::{checked_geometric_mean, checked_inverse_mean},
+    AccumulatedObservation, ObservationInterval, Oracle, OracleError, OracleSnapshot,
+};
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use test_case::test_case;
+
+#[test]
+fn test_try_new_capacity_too_small() {
+    assert_eq!(
+        Oracle::try_new(0).err(),
+        Some(OracleError::CapacityTooSmall)
+    );
+}
+
+#[test]
+fn test_try_observation_no_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let receipt = helper.try_observation(60).execute_expect_success(false);
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> =
+        receipt.outputs("try_observation");
+
+    assert_eq!(outputs, vec![Err(OracleError::NoObservations)]);
+}
+
+#[test_case(180, OracleError::TimestampTooOld; "too_old")]
+#[test_case(480, OracleError::TimestampInFuture; "in_future")]
+fn test_try_observation_error(seconds: u64, expected: OracleError) {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .try_observation(seconds)
+        .execute_expect_success(false);
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> =
+        receipt.outputs("try_observation");
+
+    assert_eq!(outputs, vec![Err(expected)]);
+}
+
+#[test]
+fn test_try_observation_matches_observation() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .observation(300)
+        .try_observation(300)
+        .execute_expect_success(false);
+    let expected: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> =
+        receipt.outputs("try_observation");
+
+    assert_eq!(outputs, vec![Ok(expected[0].clone())]);
+}
+
+#[test_case(300, 330, OracleError::IntervalNotIncreasing; "same_minute")]
+#[test_case(360, 300, OracleError::IntervalNotIncreasing; "decreasing")]
+#[test_case(180, 300, OracleError::TimestampTooOld; "start_too_old")]
+#[test_case(300, 480, OracleError::TimestampInFuture; "end_in_future")]
+#[test_case(300, u64::MAX, OracleError::TimestampInFuture; "end_max")]
+#[test_case(u64::MAX - 60, u64::MAX, OracleError::TimestampInFuture; "start_max")]
+#[test_case(u64::MAX - 1, u64::MAX, OracleError::IntervalNotIncreasing; "same_minute_max")]
+#[test_case(u64::MAX, 300, OracleError::IntervalNotIncreasing; "decreasing_max")]
+fn test_try_observation_interval_error(start: u64, end: u64, expected: OracleError) {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .try_observation_interval(start, end)
+        .execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval");
+
+    assert_eq!(outputs, vec![Err(expected)]);
+}
+
+#[test]
+fn test_try_observation_interval_matches_observation_interval() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .observation_interval(240, 420)
+        .try_observation_interval(240, 420)
+        .execute_expect_success(false);
+    let expected: Vec<ObservationInterval> = receipt.outputs("observation_interval");
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval");
+
+    assert_eq!(outputs, vec![Ok(expected[0].clone())]);
+}
+
+#[test]
+fn test_try_observation_intervals_empty() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .observation_intervals(vec![])
+        .try_observation_intervals(vec![])
+        .execute_expect_success(false);
+    let intervals: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let outputs: Vec<Result<Vec<ObservationInterval>, OracleError>> =
+        receipt.outputs("try_observation_intervals");
+
+    // Only the fallible variant rejects an empty list, the other one keeps returning it
+    assert_eq!(intervals, vec![vec![]]);
+    assert_eq!(outputs, vec![Err(OracleError::EmptyIntervals)]);
+}
+
+#[test]
+fn test_try_observation_intervals_matches_observation_intervals() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .observation_intervals(vec![(240, 420), (300, 360)])
+        .try_observation_intervals(vec![(240, 420), (300, 360)])
+        .execute_expect_success(false);
+    let expected: Vec<Vec<ObservationInterval>> = receipt.outputs("observation_intervals");
+    let outputs: Vec<Result<Vec<ObservationInterval>, OracleError>> =
+        receipt.outputs("try_observation_intervals");
+
+    assert_eq!(outputs, vec![Ok(expected[0].clone())]);
+}
+
+#[test]
+fn test_try_twap_full_history_no_observations() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let receipt = helper.try_twap_full_history().execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_twap_full_history");
+
+    assert_eq!(outputs, vec![Err(OracleError::NoObservations)]);
+}
+
+#[test]
+fn test_try_twap_full_history_single_minute() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![4]);
+    helper.jump_to_timestamp_minutes(4);
+
+    let receipt = helper.try_twap_full_history().execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_twap_full_history");
+
+    assert_eq!(outputs, vec![Err(OracleError::IntervalNotIncreasing)]);
+}
+
+#[test]
+fn test_try_twap_full_history_matches_twap_full_history() {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .twap_full_history()
+        .try_twap_full_history()
+        .execute_expect_success(false);
+    let expected: Vec<ObservationInterval> = receipt.outputs("twap_full_history");
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_twap_full_history");
+
+    assert_eq!(outputs, vec![Ok(expected[0].clone())]);
+}
+
+#[test]
+fn test_try_twap_last_brand_new_oracle() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let receipt = helper.try_twap_last(3600).execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> = receipt.outputs("try_twap_last");
+
+    assert_eq!(outputs, vec![Err(OracleError::InsufficientHistory)]);
+}
+
+#[test]
+fn test_try_twap_last_single_minute() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&vec![4]);
+    helper.jump_to_timestamp_minutes(4);
+
+    let receipt = helper.try_twap_last(3600).execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> = receipt.outputs("try_twap_last");
+
+    assert_eq!(outputs, vec![Err(OracleError::InsufficientHistory)]);
+}
+
+#[test_case(vec![4], 5, 3600; "one_minute_of_history")]
+#[test_case((4..8).collect(), 10, 1_000_000; "long_window")]
+fn test_try_twap_last_matches_twap_last(minutes: Vec<u64>, now_minutes: u64, window_seconds: u64) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_minutes(now_minutes);
+
+    let receipt = helper
+        .twap_last(window_seconds)
+        .try_twap_last(window_seconds)
+        .execute_expect_success(false);
+    let expected: Vec<ObservationInterval> = receipt.outputs("twap_last");
+    let outputs: Vec<Result<ObservationInterval, OracleError>> = receipt.outputs("try_twap_last");
+
+    assert_eq!(outputs, vec![Ok(expected[0].clone())]);
+}
+
+// Price overflow
+
+#[test_case(dec!(0), dec!(100), None; "exp_overflow")]
+#[test_case(Decimal::MIN, Decimal::MAX, None; "saturated_accumulators")]
+#[test_case(dec!(0), dec!(0), Some(pdec!(1)); "constant")]
+fn test_checked_geometric_mean(
+    y_left: Decimal,
+    y_right: Decimal,
+    expected: Option<PreciseDecimal>,
+) {
+    assert_eq!(checked_geometric_mean(4, 5, y_left, y_right), expected);
+}
+
+#[test_case(pdec!(1), PreciseDecimal::MAX, None; "saturated_accumulator")]
+#[test_case(pdec!(1), pdec!(1), None; "zero_inverse")]
+#[test_case(pdec!(1), pdec!(3), Some(pdec!(2)); "constant")]
+fn test_checked_inverse_mean(
+    y_left: PreciseDecimal,
+    y_right: PreciseDecimal,
+    expected: Option<PreciseDecimal>,
+) {
+    assert_eq!(checked_inverse_mean(4, 5, y_left, y_right), expected);
+}
+
+// Observations for the minutes 4 to 7, where the logarithm of the price_sqrt accumulated during
+// minute 6 is replaced by `log_price_sqrt`
+fn helper_with_extreme_last_minute(log_price_sqrt: Decimal) -> OracleTestHelper {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let snapshots: Vec<OracleSnapshot> = helper
+        .snapshot()
+        .execute_expect_success(false)
+        .outputs("snapshot");
+    let mut snapshot = snapshots[0].clone();
+    let observations = &mut snapshot.observations;
+    let previous_log_acc = observations[observations.len() - 2].price_sqrt_log_acc;
+    observations.last_mut().unwrap().price_sqrt_log_acc = previous_log_acc + log_price_sqrt;
+    helper.instantiate_from_snapshot_instant(snapshot);
+
+    helper
+}
+
+// e^100 overflows the price_sqrt, e^50 only the price
+#[test_case(dec!(100); "price_sqrt_overflow")]
+#[test_case(dec!(50); "price_overflow")]
+fn test_observation_interval_price_overflow(log_price_sqrt: Decimal) {
+    let mut helper = helper_with_extreme_last_minute(log_price_sqrt);
+
+    helper.observation_interval(360, 420);
+    let receipt = helper.execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error)
+            .contains("The average price over interval [360, 420] does not fit into a Decimal.")
+    });
+}
+
+#[test_case(dec!(100); "price_sqrt_overflow")]
+#[test_case(dec!(50); "price_overflow")]
+fn test_try_observation_interval_price_overflow(log_price_sqrt: Decimal) {
+    let mut helper = helper_with_extreme_last_minute(log_price_sqrt);
+
+    let receipt = helper
+        .try_observation_interval(360, 420)
+        .try_observation_interval(240, 360)
+        .execute_expect_success(false);
+    let outputs: Vec<Result<ObservationInterval, OracleError>> =
+        receipt.outputs("try_observation_interval");
+
+    assert_eq!(outputs[0], Err(OracleError::PriceOverflow));
+    // Intervals before the extreme minute are unaffected
+    assert!(outputs[1].is_ok());
+}
+
+// Missing state of the current minute
+
+// Observations for the minutes 4 to 7 without the state of the current minute, queried at
+// minute 9
+fn helper_without_sub_observations() -> OracleTestHelper {
+    let minutes: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper
+        .load_hook_auth()
+        .clear_sub_observations()
+        .execute_expect_success(false);
+    helper.jump_to_timestamp_seconds(540);
+
+    helper
+}
+
+#[test]
+fn test_observation_without_sub_observations_fails() {
+    let mut helper = helper_without_sub_observations();
+
+    let receipt = helper.observation(480).execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains(
+            "Timestamp 480 (rounded to the minute) is after the last observation at 420, but the \
+            state of the current minute needed to extrapolate is missing.",
+        )
+    });
+}
+
+#[test]
+fn test_try_observation_without_sub_observations() {
+    let mut helper = helper_without_sub_observations();
+
+    let receipt = helper
+        .try_observation(480)
+        .try_observation(420)
+        .observation(420)
+        .observation(300)
+        .execute_expect_success(false);
+    let outputs: Vec<Result<AccumulatedObservation, OracleError>> =
+        receipt.outputs("try_observation");
+    let expected: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    assert_eq!(outputs[0], Err(OracleError::ExtrapolationUnavailable));
+    // The stored observations are still served
+    assert_eq!(outputs[1], Ok(expected[0].clone()));
+    assert_eq!(expected[1].timestamp, 300);
+}
@@ -0,0 +1,286 @@
+mod helper;
+use helper::*;
+use oracle::{AccumulatedObservation, ObservationInterval};
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use test_case::test_case;
+
+/// An observation with all accumulators (except volume, swap count and observed minutes) equal to
+/// the minute, so that any interpolation between two of them is exact.
+fn injected_observation(minutes: u64) -> AccumulatedObservation {
+    AccumulatedObservation {
+        timestamp: minutes,
+        price_sqrt_log_acc: Decimal::from(minutes),
+        price_sqrt_log_sq_acc: Decimal::from(minutes),
+        price_inverse_acc: PreciseDecimal::from(minutes),
+        liquidity_acc: Decimal::from(minutes),
+        fee_rate_acc: Decimal::from(minutes),
+        volume_acc: dec!(0),
+        swap_count_acc: 0,
+        observed_minutes_acc: 0,
+    }
+}
+
+/// The same observation with the timestamp in seconds, as returned by the queries.
+fn expected_observation(minutes: u64) -> AccumulatedObservation {
+    AccumulatedObservation {
+        timestamp: minutes * 60,
+        ..injected_observation(minutes)
+    }
+}
+
+// Observations for the minutes 10, 20, 30, 40 and 50 in a wrapped ring of 5, i.e. stored at the
+// indexes 2, 3, 4, 0 and 1, queried in minute 55
+fn helper_with_wrapped_ring() -> OracleTestHelper {
+    let observations: Vec<AccumulatedObservation> =
+        (1..=5).map(|i| injected_observation(i * 10)).collect();
+
+    OracleTestHelper::new_with_injected_observations(observations, 5, 1, pdec!(1), 55 * 60)
+}
+
+#[test_case(600, 10; "oldest")]
+#[test_case(1800, 30; "exact")]
+#[test_case(1830, 30; "exact_rounded")]
+#[test_case(3000, 50; "last")]
+#[test_case(1500, 25; "interpolated")]
+#[test_case(2100, 35; "interpolated_across_wrap")]
+#[test_case(2700, 45; "interpolated_after_wrap")]
+fn test_injected_observation(seconds: u64, expected_minutes: u64) {
+    let mut helper = helper_with_wrapped_ring();
+
+    let outputs: Vec<AccumulatedObservation> = helper
+        .observation(seconds)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    assert_eq!(outputs, vec![expected_observation(expected_minutes)]);
+}
+
+// The current minute holds a price square root, liquidity and fee rate of 1, so only the inverse
+// price, liquidity and fee rate accumulators grow past the last observation, besides the current
+// minute counted as observed
+#[test_case(51; "one_minute")]
+#[test_case(53; "three_minutes")]
+#[test_case(55; "now")]
+fn test_injected_observation_extrapolated(minutes: u64) {
+    let mut helper = helper_with_wrapped_ring();
+
+    let outputs: Vec<AccumulatedObservation> = helper
+        .observation(minutes * 60)
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    let expected = AccumulatedObservation {
+        timestamp: minutes * 60,
+        price_inverse_acc: PreciseDecimal::from(minutes),
+        liquidity_acc: Decimal::from(minutes),
+        fee_rate_acc: Decimal::from(minutes),
+        observed_minutes_acc: 1,
+        ..injected_observation(50)
+    };
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test_case(1800, 30, 30; "exact")]
+#[test_case(1500, 20, 30; "interpolated")]
+#[test_case(2100, 30, 40; "across_wrap")]
+#[test_case(2700, 40, 50; "after_wrap")]
+fn test_injected_observation_neighbors(seconds: u64, left_minutes: u64, right_minutes: u64) {
+    let mut helper = helper_with_wrapped_ring();
+
+    let outputs: Vec<(AccumulatedObservation, AccumulatedObservation)> = helper
+        .observation_neighbors(seconds)
+        .execute_expect_success(false)
+        .outputs("observation_neighbors");
+
+    assert_eq!(
+        outputs,
+        vec![(
+            expected_observation(left_minutes),
+            expected_observation(right_minutes)
+        )]
+    );
+}
+
+#[test_case(540; "too_old")]
+#[test_case(56 * 60; "in_future")]
+fn test_injected_observation_out_of_range(seconds: u64) {
+    let mut helper = helper_with_wrapped_ring();
+
+    helper.observation(seconds).execute_expect_failure(false);
+}
+
+// Precise observation
+
+// Observations for the minutes 10 and 17 whose accumulators differ by 10 attos, so that the
+// truncated slope of the `Decimal` interpolation is 1 instead of 10 / 7 attos per minute
+fn helper_with_tiny_gap_slope() -> OracleTestHelper {
+    let observations = vec![
+        injected_observation(10),
+        AccumulatedObservation {
+            price_sqrt_log_acc: dec!("10.00000000000000001"),
+            ..injected_observation(17)
+        },
+    ];
+
+    OracleTestHelper::new_with_injected_observations(observations, 2, 1, pdec!(1), 20 * 60)
+}
+
+#[test]
+fn test_observation_precise_compounded_error() {
+    let mut helper = helper_with_tiny_gap_slope();
+    for minutes in 11..17 {
+        helper.observation(minutes * 60);
+        helper.observation_precise(minutes * 60);
+    }
+    let receipt = helper.execute_expect_success(false);
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let precise: Vec<(u64, PreciseDecimal)> = receipt.outputs("observation_precise");
+
+    // Summing the accumulators above the left one over the gap, exactly 10 / 7 * (1 + ... + 6)
+    let exact = pdec!("0.00000000000000003");
+    let sum = observations.iter().fold(pdec!(0), |sum, observation| {
+        sum + PreciseDecimal::from(observation.price_sqrt_log_acc - dec!(10))
+    });
+    let sum_precise = precise
+        .iter()
+        .fold(pdec!(0), |sum, (_, acc)| sum + (*acc - pdec!(10)));
+
+    // Every truncated read is off by up to one atto, adding up over the reads
+    assert!(exact - sum >= pdec!("0.000000000000000006"), "{}", sum);
+    assert!(
+        (exact - sum_precise).checked_abs().unwrap() < pdec!("0.000000000000000000000000000001"),
+        "{}",
+        sum_precise
+    );
+    let timestamps: Vec<u64> = precise.iter().map(|(timestamp, _)| *timestamp).collect();
+    assert_eq!(
+        timestamps,
+        (11..17).map(|minutes| minutes * 60).collect::<Vec<u64>>()
+    );
+}
+
+// A stored observation is returned as is, an extrapolation with a price square root of 1 keeps
+// the accumulator
+#[test_case(600, pdec!(10); "oldest")]
+#[test_case(1020, pdec!("10.00000000000000001"); "last")]
+#[test_case(1200, pdec!("10.00000000000000001"); "extrapolated")]
+fn test_observation_precise_stored_and_extrapolated(seconds: u64, expected: PreciseDecimal) {
+    let mut helper = helper_with_tiny_gap_slope();
+
+    let outputs: Vec<(u64, PreciseDecimal)> = helper
+        .observation_precise(seconds)
+        .execute_expect_success(false)
+        .outputs("observation_precise");
+
+    assert_eq!(outputs, vec![(seconds, expected)]);
+}
+
+// Precision of the slope
+
+// An accumulator delta of the smallest `Decimal` over about two years would be a slope of zero
+// as a `Decimal`, but is kept by the `PreciseDecimal` slope
+#[test]
+fn test_tiny_accumulator_delta_over_long_interval() {
+    let minutes = 1_000_000;
+    let observations = vec![
+        injected_observation(10),
+        AccumulatedObservation {
+            price_sqrt_log_acc: dec!("10.000000000000000001"),
+            ..injected_observation(10 + minutes)
+        },
+    ];
+    let mut helper = OracleTestHelper::new_with_injected_observations(
+        observations,
+        2,
+        1,
+        pdec!(1),
+        (10 + minutes) * 60,
+    );
+
+    let outputs: Vec<Vec<ObservationInterval>> = helper
+        .observation_intervals(vec![(600, (10 + minutes) * 60)])
+        .execute_expect_success(false)
+        .outputs("observation_intervals");
+
+    assert_eq!(dec!("0.000000000000000001") / minutes, dec!(0));
+    let price_sqrt = outputs[0][0].price_sqrt;
+    assert!(price_sqrt > pdec!(1), "{}", price_sqrt);
+    assert!(
+        price_sqrt < pdec!("1.00000000000000000000001"),
+        "{}",
+        price_sqrt
+    );
+}
+
+// Interpolation error bound
+
+// Observations for the minutes 10, 12 and 22 with log samples alternating between 1 and -1, i.e.
+// a mean of 0 and a variance of 1 per minute over both gaps
+fn helper_with_alternating_logs() -> OracleTestHelper {
+    let observation = |minutes: u64, log_sq_acc: Decimal| AccumulatedObservation {
+        price_sqrt_log_acc: dec!(0),
+        price_sqrt_log_sq_acc: log_sq_acc,
+        ..injected_observation(minutes)
+    };
+    let observations = vec![
+        observation(10, dec!(0)),
+        observation(12, dec!(2)),
+        observation(22, dec!(12)),
+    ];
+
+    OracleTestHelper::new_with_injected_observations(observations, 3, 2, pdec!(1), 25 * 60)
+}
+
+#[test_case(11 * 60, dec!(1); "narrow_gap")]
+#[test_case(13 * 60, dec!(3); "wide_gap_near_left")]
+#[test_case(17 * 60, dec!(5); "wide_gap_middle")]
+#[test_case(21 * 60 + 30, dec!(3); "wide_gap_near_right")]
+#[test_case(12 * 60, dec!(0); "exact")]
+#[test_case(25 * 60, dec!(0); "extrapolated")]
+fn test_interpolation_error_bound(seconds: u64, expected: Decimal) {
+    let mut helper = helper_with_alternating_logs();
+
+    let outputs: Vec<Decimal> = helper
+        .interpolation_error_bound(seconds)
+        .execute_expect_success(false)
+        .outputs("interpolation_error_bound");
+
+    assert_eq!(outputs, vec![expected]);
+}
+
+#[test]
+fn test_interpolation_error_bound_wide_gap_exceeds_narrow_gap() {
+    let mut helper = helper_with_alternating_logs();
+    // One minute after the left neighbor in both gaps
+    helper.interpolation_error_bound(11 * 60);
+    helper.interpolation_error_bound(13 * 60);
+    let outputs: Vec<Decimal> = helper
+        .execute_expect_success(false)
+        .outputs("interpolation_error_bound");
+
+    assert!(outputs[1] > outputs[0], "{:?}", outputs);
+}
+
+#[test]
+fn test_interpolation_error_bound_constant_price() {
+    let mut helper = helper_with_wrapped_ring();
+
+    let outputs: Vec<Decimal> = helper
+        .interpolation_error_bound(25 * 60)
+        .execute_expect_success(false)
+        .outputs("interpolation_error_bound");
+
+    // A sample of 1 every minute has no variance
+    assert_eq!(outputs, vec![dec!(0)]);
+}
+
+#[test]
+fn test_interpolation_error_bound_out_of_range() {
+    let mut helper = helper_with_alternating_logs();
+
+    helper
+        .interpolation_error_bound(9 * 60)
+        .execute_expect_failure(false);
+}
@@ -0,0 +1,317 @@
+mod helper;
+use helper::*;
+use oracle::{AccumulatedObservation, OracleSnapshot};
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use std::ops::Range;
+use test_case::test_case;
+
+const OBSERVATIONS: u64 = 2000;
+const OBSERVATIONS_PER_TRANSACTION: u64 = 100;
+
+/// A deterministic linear congruential generator, keeping the randomized tests reproducible.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % bound
+    }
+}
+
+/// Observes at random minute gaps (mostly short, occasionally long), asserting the ring buffer
+/// invariants after every observation. Calls `between_transactions` with the number of
+/// observations performed so far after every transaction.
+fn observe_randomly(
+    helper: &mut OracleTestHelper,
+    seed: u64,
+    mut between_transactions: impl FnMut(&mut OracleTestHelper, u64),
+) {
+    let mut lcg = Lcg(seed);
+    let mut minute = 1;
+    // Past the largest instant the gaps can reach, since `observe_at` refuses future instants
+    helper.jump_to_timestamp_seconds((2 + OBSERVATIONS * 501) * 60);
+
+    for performed in 1..=OBSERVATIONS {
+        let gap = if lcg.next(10) == 0 {
+            1 + lcg.next(500)
+        } else {
+            1 + lcg.next(3)
+        };
+        minute += gap;
+        let seconds = minute * 60 + lcg.next(60);
+        let price_sqrt = TEST_DATAPOINTS[lcg.next(TEST_DATAPOINTS.len() as u64) as usize];
+
+        if (performed - 1) % OBSERVATIONS_PER_TRANSACTION == 0 {
+            helper.load_hook_auth();
+        }
+        helper
+            .observe_at(price_sqrt, *TEST_LIQUIDITY, dec!(1), seconds)
+            .assert_invariants();
+        if performed % OBSERVATIONS_PER_TRANSACTION == 0 {
+            helper.execute_expect_success(false);
+            between_transactions(helper, performed);
+        }
+    }
+}
+
+#[test_case(1, None, 1; "limit_one")]
+#[test_case(2, None, 2; "limit_two")]
+#[test_case(10, None, 3; "limit_ten")]
+#[test_case(37, None, 4; "limit_odd")]
+#[test_case(10, Some(5), 5; "min_spacing")]
+fn test_invariants_random_gaps(
+    observations_limit: u16,
+    min_spacing_minutes: Option<u64>,
+    seed: u64,
+) {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(observations_limit);
+    helper
+        .load_hook_auth()
+        .set_min_spacing_minutes(min_spacing_minutes)
+        .assert_invariants()
+        .execute_expect_success(false);
+
+    observe_randomly(&mut helper, seed, |_, _| {});
+
+    let stored: Vec<u16> = helper
+        .observations_stored()
+        .execute_expect_success(false)
+        .outputs("observations_stored");
+    assert_eq!(stored, vec![observations_limit]);
+}
+
+#[test]
+fn test_invariants_random_gaps_increase_capacity() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(7);
+
+    // Increase the capacity while wrapped as well as while filling up
+    observe_randomly(&mut helper, 6, |helper, performed| {
+        if performed % 500 == 0 {
+            helper
+                .load_hook_auth()
+                .increase_capacity(7 + performed as u16 / 10)
+                .assert_invariants()
+                .execute_expect_success(false);
+        }
+    });
+}
+
+// Storage verification
+
+#[test_case(4..4; "no_observations")]
+#[test_case(4..7; "not_full")]
+#[test_case(4..16; "wrapped")]
+fn test_verify_storage_healthy(timestamps: Range<u64>) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .verify_storage()
+        .load_hook_auth()
+        .increase_capacity(20)
+        .verify_storage()
+        .execute_expect_success(false);
+    let outputs: Vec<bool> = receipt.outputs("verify_storage");
+
+    assert_eq!(outputs, vec![true, true]);
+}
+
+#[test_case(4..7, 0; "oldest")]
+#[test_case(4..7, 2; "last")]
+#[test_case(4..16, 5; "wrapped")]
+fn test_verify_storage_missing_slot(timestamps: Range<u64>, index: u16) {
+    let timestamps: Vec<u64> = timestamps.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .load_hook_auth()
+        .clear_observation_at_index(index)
+        .verify_storage()
+        .execute_expect_success(false);
+    let outputs: Vec<bool> = receipt.outputs("verify_storage");
+
+    assert_eq!(outputs, vec![false]);
+}
+
+#[test]
+fn test_verify_storage_ignores_slots_beyond_stored() {
+    let timestamps: Vec<u64> = (4..7).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let receipt = helper
+        .load_hook_auth()
+        .clear_observation_at_index(3)
+        .verify_storage()
+        .execute_expect_success(false);
+    let outputs: Vec<bool> = receipt.outputs("verify_storage");
+
+    assert_eq!(outputs, vec![true]);
+}
+
+#[test]
+fn test_verify_storage_non_monotonic() {
+    let timestamps: Vec<u64> = (4..8).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&timestamps);
+
+    let snapshots: Vec<OracleSnapshot> = helper
+        .snapshot()
+        .execute_expect_success(false)
+        .outputs("snapshot");
+    let mut snapshot = snapshots[0].clone();
+    snapshot.observations.swap(1, 2);
+    helper.instantiate_from_snapshot_instant(snapshot);
+
+    let outputs: Vec<bool> = helper
+        .verify_storage()
+        .execute_expect_success(false)
+        .outputs("verify_storage");
+
+    assert_eq!(outputs, vec![false]);
+}
+
+// Seeding
+
+#[test]
+fn test_seeded_full_ring_matches_observed() {
+    // 12 observations wrap around the ring of 10, keeping the minutes 6 to 15
+    let minutes: Vec<u64> = (4..16).collect();
+    let mut observed = OracleTestHelper::new_with_observations_minutes(&minutes);
+    for n in (0..10).rev() {
+        observed.observation_back(n);
+    }
+    let stored: Vec<Option<AccumulatedObservation>> = observed
+        .execute_expect_success(false)
+        .outputs("observation_back");
+    let stored: Vec<AccumulatedObservation> = stored.into_iter().flatten().collect();
+
+    let mut seeded = OracleTestHelper::new();
+    seeded.instantiate_instant();
+    seeded.jump_to_timestamp_minutes(15);
+    seeded
+        .load_hook_auth()
+        .seed_observations(stored)
+        .assert_invariants()
+        .execute_expect_success(false);
+
+    for helper in [&mut observed, &mut seeded] {
+        let stored: Vec<u16> = helper
+            .observations_stored()
+            .execute_expect_success(false)
+            .outputs("observations_stored");
+        assert_eq!(stored, vec![10]);
+    }
+
+    for seconds in (6 * 60..=15 * 60).step_by(30) {
+        let expected: Vec<AccumulatedObservation> = observed
+            .observation(seconds)
+            .execute_expect_success(false)
+            .outputs("observation");
+        let observations: Vec<AccumulatedObservation> = seeded
+            .observation(seconds)
+            .execute_expect_success(false)
+            .outputs("observation");
+        assert_eq!(observations, expected, "at {} seconds", seconds);
+    }
+}
+
+#[test]
+fn test_seed_observations_not_increasing() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    let observations: Vec<AccumulatedObservation> = [360, 300]
+        .into_iter()
+        .map(|seconds| AccumulatedObservation {
+            timestamp: seconds,
+            ..AccumulatedObservation::empty()
+        })
+        .collect();
+    let receipt = helper
+        .load_hook_auth()
+        .seed_observations(observations)
+        .execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("An observation for minute 6 is already stored.")
+    });
+}
+
+#[test]
+fn test_seed_observations_large_history() {
+    const SEEDED: u64 = 10_000;
+    const SEEDED_PER_TRANSACTION: u64 = 500;
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(SEEDED as u16);
+    helper.jump_to_timestamp_minutes(SEEDED);
+
+    // A constant price_sqrt of 2 from minute 1 to minute 10000
+    let log_price_sqrt = log(pdec!(2));
+    let observations: Vec<AccumulatedObservation> = (1..=SEEDED)
+        .map(|minute| AccumulatedObservation {
+            timestamp: minute * 60,
+            price_sqrt_log_acc: log_price_sqrt * (minute - 1),
+            ..AccumulatedObservation::empty()
+        })
+        .collect();
+    for batch in observations.chunks(SEEDED_PER_TRANSACTION as usize) {
+        helper
+            .load_hook_auth()
+            .seed_observations(batch.to_vec())
+            .execute_expect_success(false);
+    }
+
+    let stored: Vec<u16> = helper
+        .observations_stored()
+        .execute_expect_success(false)
+        .outputs("observations_stored");
+    assert_eq!(stored, vec![SEEDED as u16]);
+
+    let observations: Vec<AccumulatedObservation> = helper
+        .observation(5_000 * 60 + 30)
+        .execute_expect_success(false)
+        .outputs("observation");
+    assert_eq!(observations[0].timestamp, 5_000 * 60);
+    assert_eq!(observations[0].price_sqrt_log_acc, log_price_sqrt * 4_999);
+}
+
+// Export
+
+#[test]
+fn test_export_import_round_trip() {
+    // Only the timestamps and the accumulated logarithms are exported
+    let observations: Vec<AccumulatedObservation> = (4..10)
+        .map(|minute| AccumulatedObservation {
+            timestamp: minute * 60,
+            price_sqrt_log_acc: dec!("-1.234567890123456789") * (minute - 4),
+            ..AccumulatedObservation::empty()
+        })
+        .collect();
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_minutes(10);
+    helper
+        .load_hook_auth()
+        .seed_observations(observations.clone())
+        .execute_expect_success(false);
+    for n in (0..observations.len() as u16).rev() {
+        helper.observation_back(n);
+    }
+    let stored: Vec<Option<AccumulatedObservation>> = helper
+        .execute_expect_success(false)
+        .outputs("observation_back");
+    let stored: Vec<AccumulatedObservation> = stored.into_iter().flatten().collect();
+
+    let path = std::env::temp_dir().join("oracle_test_export_import_round_trip.json");
+    let path = path.to_str().unwrap();
+    export_accumulated_observations(path, &stored);
+    let imported = import_accumulated_observations(path);
+    std::fs::remove_file(path).unwrap();
+
+    assert_eq!(imported, observations);
+}
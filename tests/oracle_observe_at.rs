@@ -0,0 +1,193 @@
+mod helper;
+use helper::*;
+use oracle::AccumulatedObservation;
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use test_case::test_case;
+use test_oracle::test_oracle::AfterSwapState;
+
+#[test_case(vec![70, 75, 85, 120, 130])]
+#[test_case(vec![70, 75, 85, 125, 135, 150, 160, 187, 205])]
+#[test_case(vec![123, 130, 135, 607])]
+#[test_case(vec![123, 607, 908])]
+fn test_observe_at_matches_live_clock(seconds: Vec<u64>) {
+    let expected = get_observations_from_swap_seconds(&seconds);
+    let targets: Vec<u64> = expected
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+
+    let mut live = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+    live.observation_batch(targets.clone());
+    let live_outputs: Vec<AccumulatedObservation> =
+        live.execute_expect_success(false).outputs("observation");
+
+    let mut backfilled = OracleTestHelper::new();
+    backfilled.instantiate_instant();
+    backfilled.jump_to_timestamp_seconds(*seconds.last().unwrap());
+    backfilled
+        .observe_at_swap_seconds(&seconds)
+        .execute_expect_success(false);
+    backfilled.observation_batch(targets);
+    let backfilled_outputs: Vec<AccumulatedObservation> = backfilled
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    assert_eq!(backfilled_outputs, live_outputs);
+    assert_eq!(backfilled_outputs, expected);
+}
+
+#[test]
+fn test_observe_at_same_instant_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(300);
+
+    helper
+        .observe_at_swap_seconds(&vec![130, 130])
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_observe_at_earlier_instant_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(300);
+
+    helper
+        .observe_at_swap_seconds(&vec![130, 125])
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_observe_at_future_instant_fails() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(300);
+
+    let receipt = helper
+        .observe_at_swap_seconds(&vec![130, 301])
+        .execute_expect_failure(false);
+    receipt.execution_receipt.expect_specific_failure(|error| {
+        format!("{:?}", error).contains("later than the current ledger time")
+    });
+}
+
+#[test]
+fn test_observe_at_future_instant_keeps_live_observe() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(130);
+    helper
+        .observe_at_swap_seconds(&vec![130])
+        .execute_expect_success(false);
+
+    // Refused, so the live swaps of the following minutes are still recorded
+    helper
+        .observe_at_swap_seconds(&vec![600])
+        .execute_expect_failure(false);
+    helper.jump_to_timestamp_seconds(200);
+    helper
+        .load_hook_auth()
+        .after_swap_default()
+        .execute_expect_success(false);
+
+    let stored: Vec<u16> = helper
+        .observations_stored()
+        .execute_expect_success(false)
+        .outputs("observations_stored");
+    assert_eq!(stored, vec![1]);
+}
+
+#[test]
+fn test_observe_at_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .observe_at(TEST_DATAPOINTS[0], *TEST_LIQUIDITY, dec!(0), 120)
+        .execute_expect_failure(false);
+}
+
+// Observe many
+
+fn replay_series() -> Vec<(u64, PreciseDecimal, PreciseDecimal, Decimal)> {
+    (0..30)
+        .map(|i| {
+            (
+                70 + 37 * i as u64,
+                TEST_DATAPOINTS[i % TEST_DATAPOINTS.len()],
+                *TEST_LIQUIDITY,
+                dec!(0),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_observe_many_matches_live_clock() {
+    let entries = replay_series();
+    let last_seconds = entries.last().unwrap().0;
+    // The limit of 10 observations keeps the minutes 10 to 19
+    let targets: Vec<u64> = (600..=last_seconds).step_by(30).collect();
+
+    let mut live = OracleTestHelper::new();
+    live.instantiate_instant();
+    for &(seconds, price_sqrt, active_liquidity, _) in &entries {
+        let mut state = AfterSwapState::empty(
+            live.pool_address.unwrap(),
+            live.x_address(),
+            live.y_address(),
+        );
+        state.price_sqrt = price_sqrt;
+        state.active_liquidity = active_liquidity;
+
+        live.jump_to_timestamp_seconds(seconds);
+        live.load_hook_auth();
+        live.after_swap(state, live.y_address(), dec!(1));
+        live.execute_expect_success(false);
+    }
+    live.observation_batch(targets.clone());
+    let live_outputs: Vec<AccumulatedObservation> =
+        live.execute_expect_success(false).outputs("observation");
+
+    let mut replayed = OracleTestHelper::new();
+    replayed.instantiate_instant();
+    replayed.jump_to_timestamp_seconds(last_seconds);
+    replayed
+        .load_hook_auth()
+        .observe_many(entries)
+        .execute_expect_success(false);
+    replayed.observation_batch(targets);
+    let replayed_outputs: Vec<AccumulatedObservation> = replayed
+        .execute_expect_success(false)
+        .outputs("observation");
+
+    assert_eq!(replayed_outputs, live_outputs);
+}
+
+#[test]
+fn test_observe_many_not_increasing_fails() {
+    let mut entries = replay_series();
+    entries.swap(10, 11);
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+    helper.jump_to_timestamp_seconds(entries.last().unwrap().0);
+
+    helper
+        .load_hook_auth()
+        .observe_many(entries)
+        .execute_expect_failure(false);
+}
+
+#[test]
+fn test_observe_many_auth_failure() {
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_instant();
+
+    helper
+        .observe_many(replay_series())
+        .execute_expect_failure(false);
+}
@@ -0,0 +1,242 @@
+mod helper;
+use helper::*;
+use oracle::{AccumulatedObservation, CompressedOracleSnapshot, OracleSnapshot};
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+use scrypto_testenv::environment::TestHelperExecution;
+use std::ops::Range;
+use test_case::test_case;
+
+#[test_case(4..4, 5..6; "empty")]
+#[test_case(4..9, 4..12; "not_full")]
+#[test_case(4..14, 4..16; "full")]
+#[test_case(4..20, 10..22; "wrapped")]
+fn test_snapshot_restore(minutes: Range<u64>, target_minutes: Range<u64>) {
+    let minutes: Vec<u64> = minutes.collect();
+    let targets: Vec<u64> = target_minutes.map(|minute| minute * 60).collect();
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+    helper.jump_to_timestamp_seconds(*targets.last().unwrap());
+
+    if !minutes.is_empty() {
+        helper.observation_batch(targets.clone());
+    }
+    helper.observations_stored();
+    helper.last_observation_index();
+    helper.oldest_observation_at();
+    helper.snapshot();
+    let receipt = helper.execute_expect_success(false);
+
+    let expected_observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let expected_stored: Vec<u16> = receipt.outputs("observations_stored");
+    let expected_last_index: Vec<Option<u16>> = receipt.outputs("last_observation_index");
+    let expected_oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    assert_eq!(
+        snapshots[0].observations.len(),
+        snapshots[0].observations_stored as usize
+    );
+
+    helper.instantiate_from_snapshot_instant(snapshots[0].clone());
+
+    if !minutes.is_empty() {
+        helper.observation_batch(targets);
+    }
+    helper.observations_stored();
+    helper.last_observation_index();
+    helper.oldest_observation_at();
+    helper.snapshot();
+    let receipt = helper.execute_expect_success(false);
+
+    let observations: Vec<AccumulatedObservation> = receipt.outputs("observation");
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let last_index: Vec<Option<u16>> = receipt.outputs("last_observation_index");
+    let oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+    let restored_snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    assert_eq!(observations, expected_observations);
+    assert_eq!(stored, expected_stored);
+    assert_eq!(last_index, expected_last_index);
+    assert_eq!(oldest, expected_oldest);
+    assert_eq!(restored_snapshots, snapshots);
+}
+
+#[test]
+fn test_snapshot_observations_chronological() {
+    let minutes: Vec<u64> = (4..20).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper.snapshot().execute_expect_success(false);
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    let timestamps: Vec<u64> = snapshots[0]
+        .observations
+        .iter()
+        .map(|observation| observation.timestamp)
+        .collect();
+
+    assert_eq!(timestamps, (10..20).collect::<Vec<u64>>());
+}
+
+#[test]
+fn test_restore_continues_observing() {
+    let minutes: Vec<u64> = (4..20).collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper.snapshot().execute_expect_success(false);
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    helper.instantiate_from_snapshot_instant(snapshots[0].clone());
+    helper.jump_to_timestamp_minutes(21);
+    helper
+        .load_hook_auth()
+        .after_swap_default()
+        .execute_expect_success(false);
+
+    helper.observations_stored();
+    helper.oldest_observation_at();
+    let receipt = helper.execute_expect_success(false);
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    let oldest: Vec<Option<u64>> = receipt.outputs("oldest_observation_at");
+
+    assert_eq!(stored, vec![10]);
+    assert_eq!(oldest, vec![Some(11 * 60)]);
+}
+
+#[test]
+fn test_restore_refuses_duplicate_minute_observation() {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&(4..8).collect());
+    let receipt = helper.snapshot().execute_expect_success(false);
+    let earlier: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&(4..9).collect());
+    let receipt = helper.snapshot().execute_expect_success(false);
+    let later: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    // The sub-observations lag one minute behind the last stored observation (minute 8), so the
+    // next swap in minute 8 would create a second observation for the same minute
+    let mut snapshot = later[0].clone();
+    snapshot.sub_observations = earlier[0].sub_observations.clone();
+
+    helper.instantiate_from_snapshot_instant(snapshot);
+    helper.jump_to_timestamp_minutes(8);
+    helper
+        .load_hook_auth()
+        .after_swap_default()
+        .execute_expect_failure(false);
+}
+
+// Compressed snapshot
+
+#[test_case(4..4; "empty")]
+#[test_case(4..5; "single")]
+#[test_case(4..9; "not_full")]
+#[test_case(4..20; "wrapped")]
+fn test_compressed_snapshot_restore(minutes: Range<u64>) {
+    let minutes: Vec<u64> = minutes.collect();
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&minutes);
+
+    let receipt = helper
+        .snapshot()
+        .compressed_snapshot()
+        .execute_expect_success(false);
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+    let compressed: Vec<CompressedOracleSnapshot> = receipt.outputs("compressed_snapshot");
+
+    assert_eq!(
+        compressed[0].observations.len(),
+        snapshots[0].observations.len().saturating_sub(1)
+    );
+
+    helper.instantiate_from_compressed_snapshot_instant(compressed[0].clone());
+    let restored: Vec<OracleSnapshot> = helper
+        .snapshot()
+        .execute_expect_success(false)
+        .outputs("snapshot");
+
+    assert_eq!(restored, snapshots);
+}
+
+#[test]
+fn test_compressed_snapshot_large_history() {
+    const SEEDED: u64 = 1_000;
+    const SEEDED_PER_TRANSACTION: u64 = 500;
+
+    let mut helper = OracleTestHelper::new();
+    helper.instantiate_with_limit_instant(SEEDED as u16);
+    helper.jump_to_timestamp_minutes(SEEDED);
+
+    // A constant price_sqrt of 2 with a liquidity of 100 from minute 1 to minute 1000, with the
+    // volume growing every minute
+    let log_price_sqrt = log(pdec!(2));
+    let observations: Vec<AccumulatedObservation> = (1..=SEEDED)
+        .map(|minute| AccumulatedObservation {
+            timestamp: minute * 60,
+            price_sqrt_log_acc: log_price_sqrt * (minute - 1),
+            price_sqrt_log_sq_acc: log_price_sqrt * log_price_sqrt * (minute - 1),
+            price_inverse_acc: pdec!("0.25") * (minute - 1),
+            liquidity_acc: dec!(100) * (minute - 1),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!("1.25") * (minute * (minute + 1) / 2),
+            swap_count_acc: 3 * minute,
+            observed_minutes_acc: minute - 1,
+        })
+        .collect();
+    for batch in observations.chunks(SEEDED_PER_TRANSACTION as usize) {
+        helper
+            .load_hook_auth()
+            .seed_observations(batch.to_vec())
+            .execute_expect_success(false);
+    }
+
+    let receipt = helper
+        .snapshot()
+        .compressed_snapshot()
+        .execute_expect_success(false);
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+    let compressed: Vec<CompressedOracleSnapshot> = receipt.outputs("compressed_snapshot");
+
+    let raw_size = scrypto_encode(&snapshots[0]).unwrap().len();
+    let compressed_size = scrypto_encode(&compressed[0]).unwrap().len();
+    assert!(
+        compressed_size * 2 < raw_size,
+        "{} compressed vs {} raw bytes",
+        compressed_size,
+        raw_size
+    );
+
+    helper.instantiate_from_compressed_snapshot_instant(compressed[0].clone());
+    let restored: Vec<OracleSnapshot> = helper
+        .snapshot()
+        .execute_expect_success(false)
+        .outputs("snapshot");
+
+    assert_eq!(
+        scrypto_encode(&restored[0]).unwrap(),
+        scrypto_encode(&snapshots[0]).unwrap()
+    );
+}
+
+// Malformed snapshot
+
+#[test_case(|snapshot| { snapshot.observations.pop(); }, "does not match `observations_stored`"; "missing_observation")]
+#[test_case(|snapshot| snapshot.observations_limit = 3, "more observations than its `observations_limit`"; "exceeding_limit")]
+#[test_case(|snapshot| snapshot.last_observation_index = None, "does not match its `observations_stored`"; "missing_last_index")]
+#[test_case(|snapshot| snapshot.last_observation_index = Some(5), "is beyond its 5 observations stored"; "last_index_beyond_stored")]
+#[test_case(|snapshot| snapshot.observations.swap(1, 2), "not strictly increasing"; "unordered_timestamps")]
+fn test_restore_malformed_snapshot_fails(corrupt: fn(&mut OracleSnapshot), message: &str) {
+    let mut helper = OracleTestHelper::new_with_observations_minutes(&(4..10).collect());
+    let receipt = helper.snapshot().execute_expect_success(false);
+    let snapshots: Vec<OracleSnapshot> = receipt.outputs("snapshot");
+
+    let mut snapshot = snapshots[0].clone();
+    corrupt(&mut snapshot);
+
+    let receipt = helper
+        .instantiate_from_snapshot(snapshot)
+        .execute_expect_failure(false);
+    receipt
+        .execution_receipt
+        .expect_specific_failure(|error| format!("{:?}", error).contains(message));
+}
@@ -1,6 +1,6 @@
 mod helper;
 use helper::*;
-use oracle::AccumulatedObservation;
+use oracle::{AccumulatedObservation, OracleSnapshot};
 use pretty_assertions::assert_eq;
 use scrypto::prelude::*;
 use scrypto_testenv::environment::TestHelperExecution;
@@ -38,10 +38,54 @@ fn test_first_minute_same_transaction() {
         vec![AccumulatedObservation {
             timestamp: 180,
             price_sqrt_log_acc: log(states[1].price_sqrt),
+            price_sqrt_log_sq_acc: log_squared(states[1].price_sqrt),
+            price_inverse_acc: price_inverse(states[1].price_sqrt),
+            liquidity_acc: dec!(100),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: 2,
+            observed_minutes_acc: 1,
         }]
     );
 }
 
+// Swaps at 170 (minute 2) and 185 (minute 3) straddle a minute boundary, followed by a swap at
+// 200 in the new minute. Only the swap at 185 stores an observation (for minute 3), and minute 3
+// is weighted from its start: 2 during [180, 185), 4 during [185, 200) and 8 during [200, 240).
+#[test]
+fn test_swaps_straddling_minute_boundary() {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&vec![]);
+    swap_at(&mut helper, 170, pdec!(2));
+    swap_at(&mut helper, 185, pdec!(4));
+    swap_at(&mut helper, 200, pdec!(8));
+
+    let receipt = helper.observations_stored().execute_expect_success(false);
+    let stored: Vec<u16> = receipt.outputs("observations_stored");
+    assert_eq!(stored, vec![1]);
+
+    helper.jump_to_timestamp_seconds(240);
+    let receipt = helper
+        .observation(180)
+        .observation(240)
+        .execute_expect_success(false);
+    let output: Vec<AccumulatedObservation> = receipt.outputs("observation");
+
+    // Minute 2 only contains the price 2 since the initialization at 170
+    assert_eq!(output[0].price_sqrt_log_acc, log(pdec!(2)));
+    assert_eq!(output[0].price_inverse_acc, price_inverse(pdec!(2)));
+    // (2 * 5 + 4 * 15 + 8 * 40) / 60 = 6.5 and (0.25 * 5 + 0.0625 * 15 + 0.015625 * 40) / 60 =
+    // 0.046875
+    assert_eq!(output[1].timestamp, 240);
+    assert_eq!(
+        output[1].price_sqrt_log_acc,
+        log(pdec!(2)) + log(pdec!("6.5"))
+    );
+    assert_eq!(
+        output[1].price_inverse_acc,
+        price_inverse(pdec!(2)) + pdec!("0.046875")
+    );
+}
+
 #[test]
 fn test_first_minute_same_second() {
     // let seconds: Vec<u64> = (0..9).collect();
@@ -58,6 +102,13 @@ fn test_first_minute_same_second() {
         vec![AccumulatedObservation {
             timestamp: 120,
             price_sqrt_log_acc: log(TEST_DATAPOINTS[2]),
+            price_sqrt_log_sq_acc: log_squared(TEST_DATAPOINTS[2]),
+            price_inverse_acc: price_inverse(TEST_DATAPOINTS[2]),
+            liquidity_acc: dec!(100),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: 3,
+            observed_minutes_acc: 1,
         }]
     )
 }
@@ -72,6 +123,8 @@ fn test_first_minute_multiple_seconds() {
 
     let output: Vec<AccumulatedObservation> = receipt.outputs("observation");
 
+    let weighted_average_inverse =
+        weighted_average(inverses(&TEST_DATAPOINTS), vec![15, 0, 5, 0, 0, 10]);
     let weighted_average = weighted_average(TEST_DATAPOINTS.clone(), vec![15, 0, 5, 0, 0, 10]);
 
     assert_eq!(
@@ -79,6 +132,13 @@ fn test_first_minute_multiple_seconds() {
         vec![AccumulatedObservation {
             timestamp: 120,
             price_sqrt_log_acc: log(weighted_average),
+            price_sqrt_log_sq_acc: log_squared(weighted_average),
+            price_inverse_acc: weighted_average_inverse,
+            liquidity_acc: dec!(100),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: 6,
+            observed_minutes_acc: 1,
         }]
     )
 }
@@ -97,6 +157,7 @@ fn test_first_minute_multiple_seconds_2(
 
     let output: Vec<AccumulatedObservation> = receipt.outputs("observation");
 
+    let weighted_average_inverse = weighted_average(inverses(&TEST_DATAPOINTS), weights.clone());
     let weighted_average = weighted_average(TEST_DATAPOINTS.clone(), weights);
 
     assert_eq!(
@@ -104,6 +165,16 @@ fn test_first_minute_multiple_seconds_2(
         vec![AccumulatedObservation {
             timestamp: inspected_timestamp,
             price_sqrt_log_acc: log(weighted_average),
+            price_sqrt_log_sq_acc: log_squared(weighted_average),
+            price_inverse_acc: weighted_average_inverse,
+            liquidity_acc: dec!(100),
+            fee_rate_acc: dec!(0),
+            volume_acc: dec!(0),
+            swap_count_acc: seconds
+                .iter()
+                .filter(|&&second| second / 60 < inspected_timestamp / 60)
+                .count() as u64,
+            observed_minutes_acc: 1,
         }]
     )
 }
@@ -116,3 +187,102 @@ fn test_first_minute_multiple_seconds_2(
 fn test_assert_observations_batch(seconds: Vec<u64>) {
     assert_observations_batch(&seconds, get_observations_from_swap_seconds(&seconds));
 }
+
+// Leaked value
+
+// The last price of a minute leaks into the following minutes until the next swap, both into the
+// average of the next active minute and into the idle minutes in between. The raw stored
+// observations, not only the queried ones, have to match the model of the helper.
+#[test_case(vec![70, 75, 85, 125, 135]; "next_minute")]
+#[test_case(vec![70, 75, 85, 125, 135, 150, 150, 160, 187, 205]; "next_minutes")]
+#[test_case(vec![123, 130, 130, 130, 135, 135, 607]; "big_gap")]
+#[test_case(vec![123, 607, 908]; "big_gaps")]
+#[test_case(vec![70, 75, 85, 125, 135, 400, 410, 700]; "gaps_after_active_minutes")]
+fn test_stored_observations_leak(seconds: Vec<u64>) {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&seconds);
+
+    let snapshots: Vec<OracleSnapshot> = helper
+        .snapshot()
+        .execute_expect_success(false)
+        .outputs("snapshot");
+    let stored: Vec<AccumulatedObservation> = snapshots[0]
+        .observations
+        .iter()
+        .map(|observation| AccumulatedObservation {
+            timestamp: observation.timestamp * 60,
+            ..observation.clone()
+        })
+        .collect();
+
+    assert_eq!(stored, get_observations_from_swap_seconds(&seconds));
+}
+
+// Raw state of the minute in progress
+
+fn swap_at(helper: &mut OracleTestHelper, seconds: u64, price_sqrt: PreciseDecimal) {
+    let mut state = AfterSwapState::empty(
+        helper.pool_address.unwrap(),
+        helper.x_address(),
+        helper.y_address(),
+    );
+    state.price_sqrt = price_sqrt;
+    helper.jump_to_timestamp_seconds(seconds);
+    helper.load_hook_auth();
+    helper.after_swap(state, helper.y_address(), dec!("1.2344"));
+    helper.execute_expect_success(false);
+}
+
+fn sub_observation_state(
+    helper: &mut OracleTestHelper,
+) -> Option<(PreciseDecimal, PreciseDecimal, u64, bool)> {
+    let receipt = helper
+        .load_hook_auth()
+        .sub_observation_state()
+        .execute_expect_success(false);
+    let outputs: Vec<Option<(PreciseDecimal, PreciseDecimal, u64, bool)>> =
+        receipt.outputs("sub_observation_state");
+    outputs[0]
+}
+
+#[test]
+fn test_sub_observation_state_none_before_first_price() {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&vec![]);
+
+    assert_eq!(sub_observation_state(&mut helper), None);
+}
+
+#[test]
+fn test_sub_observation_state_first_minute() {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&vec![]);
+    swap_at(&mut helper, 130, pdec!(1));
+    swap_at(&mut helper, 150, pdec!(3));
+    swap_at(&mut helper, 170, pdec!(5));
+
+    // 1 during [130, 150) and 3 during [150, 170)
+    assert_eq!(
+        sub_observation_state(&mut helper),
+        Some((pdec!(80), pdec!(5), 170, true))
+    );
+}
+
+#[test]
+fn test_sub_observation_state_after_finalization() {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&vec![]);
+    swap_at(&mut helper, 130, pdec!(1));
+    swap_at(&mut helper, 170, pdec!(5));
+    swap_at(&mut helper, 190, pdec!(2));
+    swap_at(&mut helper, 205, pdec!(4));
+
+    // 5 leaked into [180, 190), then 2 during [190, 205)
+    assert_eq!(
+        sub_observation_state(&mut helper),
+        Some((pdec!(80), pdec!(4), 205, false))
+    );
+}
+
+#[test]
+fn test_sub_observation_state_auth_failure() {
+    let mut helper = OracleTestHelper::new_with_swap_state_seconds(&vec![]);
+
+    helper.sub_observation_state().execute_expect_failure(false);
+}
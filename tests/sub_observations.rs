@@ -0,0 +1,175 @@
+use oracle::oracle::SubObservations;
+use pretty_assertions::assert_eq;
+use scrypto::prelude::*;
+
+fn instant(seconds: i64) -> Instant {
+    Instant::new(seconds)
+}
+
+#[test]
+fn test_backward_instant_does_not_underflow() {
+    let mut sub_observations = SubObservations::new_at(instant(130));
+    sub_observations.new_subobservation_at(pdec!(1), pdec!(100), dec!(1), dec!(0), instant(130));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(1), dec!(0), instant(150));
+    // Earlier than the last update, handled like the same instant
+    sub_observations.new_subobservation_at(pdec!(3), pdec!(100), dec!(1), dec!(0), instant(140));
+
+    let summary = sub_observations.finalize_at(instant(180));
+
+    // Price 1 during [130, 150), replaced by 3 (instead of 2) during [150, 180)
+    assert_eq!(summary.price_sqrt_avg, pdec!("2.2"));
+    assert_eq!(summary.price_sqrt_last, pdec!(3));
+    assert_eq!(summary.volume, dec!(3));
+}
+
+#[test]
+fn test_backward_instant_same_as_last_update() {
+    let mut backward = SubObservations::new_at(instant(130));
+    backward.new_subobservation_at(pdec!(1), pdec!(100), dec!(0), dec!(0), instant(130));
+    backward.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(150));
+    backward.new_subobservation_at(pdec!(3), pdec!(100), dec!(0), dec!(0), instant(140));
+
+    let mut same = SubObservations::new_at(instant(130));
+    same.new_subobservation_at(pdec!(1), pdec!(100), dec!(0), dec!(0), instant(130));
+    same.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(150));
+    same.new_subobservation_at(pdec!(3), pdec!(100), dec!(0), dec!(0), instant(150));
+
+    assert_eq!(backward, same);
+}
+
+#[test]
+fn test_finalize_weights_by_marginal_seconds() {
+    let mut sub_observations = SubObservations::new_at(instant(120));
+    sub_observations.new_subobservation_at(pdec!(1), pdec!(100), dec!(0), dec!(0), instant(120));
+    sub_observations.finalize_at(instant(180));
+
+    // Price 1 during [180, 190), 2 during [190, 220) and 4 during [220, 240)
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(190));
+    sub_observations.new_subobservation_at(pdec!(4), pdec!(50), dec!(0), dec!(0), instant(220));
+    let preview = sub_observations.finalize_preview();
+    let summary = sub_observations.finalize_at(instant(240));
+
+    assert_eq!(summary.price_sqrt_avg, pdec!("2.5"));
+    assert_eq!(summary.price_sqrt_last, pdec!(4));
+    // 1 / 1 during 10 seconds, 1 / 4 during 30 seconds and 1 / 16 during 20 seconds
+    assert_eq!(summary.price_inverse_avg, pdec!("0.3125"));
+    // Liquidity 100 during 40 seconds and 50 during 20 seconds
+    assert_eq!(summary.liquidity_avg, pdec!(5000) / 60);
+    assert_eq!(preview, summary);
+}
+
+#[test]
+fn test_finalize_weights_fee_rate_by_marginal_seconds() {
+    let mut sub_observations = SubObservations::new_at(instant(120));
+    sub_observations.new_subobservation_at(
+        pdec!(1),
+        pdec!(100),
+        dec!(0),
+        dec!("0.003"),
+        instant(120),
+    );
+    sub_observations.finalize_at(instant(180));
+
+    // Fee rate 0.003 during [180, 200), 0.01 during [200, 240) and kept by the idle next minute
+    sub_observations.new_subobservation_at(
+        pdec!(1),
+        pdec!(100),
+        dec!(0),
+        dec!("0.01"),
+        instant(200),
+    );
+    let summary = sub_observations.finalize_at(instant(240));
+
+    assert_eq!(summary.fee_rate_avg, pdec!("0.46") / 60);
+    assert_eq!(summary.fee_rate_last, pdec!("0.01"));
+    assert_eq!(
+        sub_observations.finalize_at(instant(300)).fee_rate_avg,
+        pdec!("0.01")
+    );
+}
+
+#[test]
+fn test_finalize_first_minute_from_initialization() {
+    let mut sub_observations = SubObservations::new_at(instant(135));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(135));
+    sub_observations.new_subobservation_at(pdec!(5), pdec!(100), dec!(0), dec!(0), instant(150));
+
+    let summary = sub_observations.finalize_at(instant(180));
+
+    // Averaged over the 45 seconds since the initialization: 2 during 15 and 5 during 30 seconds
+    assert_eq!(summary.price_sqrt_avg, pdec!(4));
+    assert_eq!(summary.price_sqrt_last, pdec!(5));
+    assert_eq!(summary.liquidity_avg, pdec!(100));
+}
+
+#[test]
+fn test_finalize_first_minute_from_minute_boundary() {
+    let mut sub_observations = SubObservations::new_at(instant(120));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(120));
+    sub_observations.new_subobservation_at(pdec!(5), pdec!(50), dec!(0), dec!(0), instant(150));
+
+    let summary = sub_observations.finalize_at(instant(180));
+
+    // Initialized on the boundary, so averaged over the full minute: 2 during 30 and 5 during
+    // 30 seconds, counting the boundary second once
+    assert_eq!(summary.price_sqrt_avg, pdec!("3.5"));
+    assert_eq!(summary.price_sqrt_last, pdec!(5));
+    assert_eq!(summary.liquidity_avg, pdec!(75));
+}
+
+#[test]
+fn test_multiple_swaps_same_second() {
+    let mut sub_observations = SubObservations::new_at(instant(120));
+    sub_observations.new_subobservation_at(pdec!(1), pdec!(100), dec!(1), dec!(0), instant(120));
+    sub_observations.new_subobservation_at(pdec!(3), pdec!(100), dec!(1), dec!(0), instant(130));
+    sub_observations.new_subobservation_at(pdec!(5), pdec!(100), dec!(1), dec!(0), instant(130));
+    sub_observations.new_subobservation_at(pdec!(4), pdec!(100), dec!(1), dec!(0), instant(130));
+
+    let summary = sub_observations.finalize_at(instant(180));
+
+    // Only the last price of second 130 counts: 1 during 10 and 4 during 50 seconds
+    assert_eq!(summary.price_sqrt_avg, pdec!("3.5"));
+    // 1 / 1 during 10 and 1 / 16 during 50 seconds
+    assert_eq!(summary.price_inverse_avg, pdec!("0.21875"));
+    assert_eq!(summary.price_sqrt_last, pdec!(4));
+    // The volume of every swap counts
+    assert_eq!(summary.volume, dec!(4));
+}
+
+#[test]
+#[should_panic(
+    expected = "Cannot finalize at 60 (rounded to the minute) before the last update at 150."
+)]
+fn test_finalize_backward_clock_fails() {
+    let mut sub_observations = SubObservations::new_at(instant(130));
+    sub_observations.new_subobservation_at(pdec!(1), pdec!(100), dec!(0), dec!(0), instant(130));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(150));
+
+    sub_observations.finalize_at(instant(90));
+}
+
+#[test]
+#[should_panic(
+    expected = "Cannot finalize at 120 (rounded to the minute) before the last update at 150."
+)]
+fn test_finalize_before_minute_ended_fails() {
+    let mut sub_observations = SubObservations::new_at(instant(130));
+    sub_observations.new_subobservation_at(pdec!(1), pdec!(100), dec!(0), dec!(0), instant(130));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(150));
+
+    // The minute of the last update has not ended at 170
+    sub_observations.finalize_at(instant(170));
+}
+
+#[test]
+fn test_finalize_twice_at_minute_start() {
+    let mut sub_observations = SubObservations::new_at(instant(130));
+    sub_observations.new_subobservation_at(pdec!(2), pdec!(100), dec!(0), dec!(0), instant(130));
+    sub_observations.finalize_at(instant(180));
+
+    // Finalizing again at the same minute start is not going backward, the idle minute keeps
+    // the last price
+    let summary = sub_observations.finalize_at(instant(180));
+
+    assert_eq!(summary.price_sqrt_avg, pdec!(2));
+}
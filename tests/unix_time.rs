@@ -0,0 +1,27 @@
+use oracle::{UnixMinutes, UnixSeconds};
+use pretty_assertions::assert_eq;
+use test_case::test_case;
+
+#[test_case(0, 0; "zero")]
+#[test_case(59, 0; "rounded_down")]
+#[test_case(60, 1; "exact")]
+#[test_case(1_700_000_030, 28_333_333; "unix")]
+fn test_seconds_to_minutes(seconds: u64, minutes: u64) {
+    assert_eq!(UnixSeconds(seconds).to_minutes(), UnixMinutes(minutes));
+}
+
+#[test_case(0, 0; "zero")]
+#[test_case(1, 60; "one")]
+#[test_case(28_333_333, 1_699_999_980; "unix")]
+fn test_minutes_to_seconds(minutes: u64, seconds: u64) {
+    assert_eq!(UnixMinutes(minutes).to_seconds(), UnixSeconds(seconds));
+}
+
+#[test]
+fn test_seconds_from_u64() {
+    let seconds: UnixSeconds = 150u64.into();
+
+    assert_eq!(seconds, UnixSeconds(150));
+    // The round trip rounds down to the start of the minute
+    assert_eq!(seconds.to_minutes().to_seconds(), UnixSeconds(120));
+}